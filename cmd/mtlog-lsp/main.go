@@ -14,6 +14,7 @@ import (
 	"log"
 	"os"
 	"path/filepath"
+	"runtime/debug"
 	"strings"
 	"sync"
 	"time"
@@ -29,8 +30,31 @@ import (
 const (
 	// editContextLength is the number of characters to show before and after an edit location for debugging
 	editContextLength = 20
+
+	// protocolVersion is the version of the contract with the Zed extension,
+	// which compares it with its own before launching the server
+	protocolVersion = 1
 )
 
+// version is set at build time with -ldflags "-X main.version=v0.11.0".
+// When empty, the module version recorded by go install is reported.
+var version = ""
+
+// versionString returns the line printed for --version, for example
+// "mtlog-lsp v0.11.0 protocol 1".
+func versionString() string {
+	v := version
+	if v == "" {
+		if info, ok := debug.ReadBuildInfo(); ok {
+			v = info.Main.Version
+		}
+	}
+	if v == "" {
+		v = "(devel)"
+	}
+	return fmt.Sprintf("mtlog-lsp %s protocol %d", v, protocolVersion)
+}
+
 // WorkspaceConfiguration holds configuration options received from the client.
 type WorkspaceConfiguration struct {
 	Mtlog struct {
@@ -118,6 +142,12 @@ type Position struct {
 }
 
 func main() {
+	// Answer --version without entering the LSP loop, which would wait on stdin
+	if len(os.Args) > 1 && (os.Args[1] == "--version" || os.Args[1] == "-version") {
+		fmt.Println(versionString())
+		return
+	}
+
 	// Set up logging to stderr (stdout is for LSP communication)
 	logger := log.New(os.Stderr, "[mtlog-lsp] ", log.LstdFlags)
 	
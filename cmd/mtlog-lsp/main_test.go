@@ -7,6 +7,7 @@ import (
 	"log"
 	"os"
 	"path/filepath"
+	"strings"
 	"testing"
 	"time"
 	
@@ -726,3 +727,18 @@ func TestDiagnosticBatching(t *testing.T) {
 		t.Error("Last diagnostic should be truncation warning")
 	}
 }
+
+func TestVersionString(t *testing.T) {
+	defer func(saved string) { version = saved }(version)
+
+	version = "v0.11.0"
+	if got, want := versionString(), "mtlog-lsp v0.11.0 protocol 1"; got != want {
+		t.Errorf("versionString() = %q, want %q", got, want)
+	}
+
+	version = ""
+	got := versionString()
+	if !strings.HasPrefix(got, "mtlog-lsp ") || !strings.HasSuffix(got, fmt.Sprintf(" protocol %d", protocolVersion)) {
+		t.Errorf("versionString() = %q, want mtlog-lsp <version> protocol %d", got, protocolVersion)
+	}
+}
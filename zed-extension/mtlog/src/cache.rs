@@ -0,0 +1,158 @@
+//! The `cacheDir` setting, pointing mtlog-lsp's package-fact cache at a
+//! directory of the user's choosing.
+//!
+//! The directory is expanded once and handed to the server twice, as the
+//! `cacheDir` initialization option and as `MTLOG_CACHE_DIR` in its
+//! environment, so servers that read either see the same path.
+
+use std::collections::HashMap;
+
+use zed_extension_api::serde_json::Value;
+
+use crate::discovery;
+use crate::settings::{EffectiveSettings, Source};
+
+/// Setting, and initialization option, naming the cache directory.
+/// Forwarded only; requires mtlog-lsp support.
+pub(crate) const CACHE_DIR_KEY: &str = "cacheDir";
+
+/// Environment variable mtlog-lsp reads the cache directory from.
+pub(crate) const CACHE_DIR_ENV: &str = "MTLOG_CACHE_DIR";
+
+/// Placeholder replaced by the worktree root.
+const WORKTREE_ROOT: &str = "${worktreeRoot}";
+
+/// Prefix of placeholders replaced by an environment variable, `${env:NAME}`.
+const ENV_PREFIX: &str = "${env:";
+
+/// Expands the `cacheDir` setting into an absolute path.
+///
+/// `~` and `${env:NAME}` are taken from the worktree's shell environment,
+/// `${worktreeRoot}` is the worktree root, and relative paths are resolved
+/// against the root. Returns `Ok(None)` when the setting is absent.
+pub(crate) fn expand(setting: Option<&Value>, root: &str, env: &HashMap<String, String>) -> Result<Option<String>, String> {
+    let Some(setting) = setting else {
+        return Ok(None);
+    };
+    let dir = setting.as_str().map(str::trim).ok_or("expected a directory path")?;
+    if dir.is_empty() {
+        return Err("the path is empty".to_string());
+    }
+    if dir.contains(['\n', '\r', '\0']) {
+        return Err("the path contains control characters".to_string());
+    }
+
+    let mut expanded = match dir.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            let home = env.get("HOME").ok_or("~ needs HOME to be set")?;
+            format!("{home}{rest}")
+        }
+        Some(_) => return Err("only ~ and ~/ are expanded, not ~user".to_string()),
+        None => dir.to_string(),
+    };
+    expanded = expanded.replace(WORKTREE_ROOT, root);
+    while let Some(start) = expanded.find(ENV_PREFIX) {
+        let name_start = start + ENV_PREFIX.len();
+        let end = expanded[name_start..]
+            .find('}')
+            .map(|end| name_start + end)
+            .ok_or("unterminated ${env:...} placeholder")?;
+        let name = &expanded[name_start..end];
+        let value = env.get(name).ok_or_else(|| format!("{name} is not set"))?;
+        expanded.replace_range(start..=end, value);
+    }
+    if expanded.contains("${") {
+        return Err(format!("unknown placeholder; use {WORKTREE_ROOT} or ${{env:NAME}}"));
+    }
+
+    Ok(Some(discovery::resolve_dir(&expanded, root)))
+}
+
+/// Adds the result of [`expand`] to the initialization options, or a
+/// warning when the setting was invalid.
+pub(crate) fn apply(expanded: Result<Option<String>, String>, effective: &mut EffectiveSettings) {
+    match expanded {
+        Ok(Some(dir)) => {
+            if let Some(options) = effective.options.as_object_mut() {
+                options.insert(CACHE_DIR_KEY.to_string(), Value::String(dir));
+                effective.sources.insert(CACHE_DIR_KEY.to_string(), Source::Settings);
+            }
+        }
+        Ok(None) => {}
+        Err(err) => effective
+            .warnings
+            .push(format!("ignoring invalid {CACHE_DIR_KEY}: {err}; using the server default")),
+    }
+}
+
+/// The environment variable carrying `dir` to the server.
+pub(crate) fn env_var(dir: &str) -> (String, String) {
+    (CACHE_DIR_ENV.to_string(), dir.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings;
+    use zed_extension_api::{serde_json::json, settings::LspSettings};
+
+    fn env() -> HashMap<String, String> {
+        [("HOME", "/home/ci"), ("SCRATCH", "/mnt/scratch")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn expand_str(dir: &str) -> Result<Option<String>, String> {
+        expand(Some(&json!(dir)), "/work/app", &env())
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expand(None, "/work/app", &env()), Ok(None));
+        assert_eq!(expand_str("~/.cache/mtlog"), Ok(Some("/home/ci/.cache/mtlog".to_string())));
+        assert_eq!(expand_str("${env:SCRATCH}/mtlog"), Ok(Some("/mnt/scratch/mtlog".to_string())));
+        assert_eq!(
+            expand_str("${worktreeRoot}/.mtlog-cache"),
+            Ok(Some("/work/app/.mtlog-cache".to_string()))
+        );
+        assert_eq!(expand_str(".cache/mtlog"), Ok(Some("/work/app/.cache/mtlog".to_string())));
+    }
+
+    #[test]
+    fn test_expand_invalid() {
+        for dir in ["", "  ", "${env:MISSING}/mtlog", "${env:SCRATCH", "${workspace}/cache", "~ci/cache", "/tmp/a\nb"] {
+            assert!(expand_str(dir).is_err(), "{dir:?}");
+        }
+        assert!(expand(Some(&json!(7)), "/work/app", &env()).is_err());
+        assert!(expand(Some(&json!("~/cache")), "/work/app", &HashMap::new()).is_err());
+    }
+
+    /// The option and the environment variable always carry the same path.
+    #[test]
+    fn test_emit_option_and_env_together() {
+        let expanded = expand_str("${env:SCRATCH}/mtlog");
+        let mut effective = settings::initialization_options(&LspSettings::default());
+
+        apply(expanded.clone(), &mut effective);
+
+        assert_eq!(effective.options["cacheDir"], json!("/mnt/scratch/mtlog"));
+        assert_eq!(effective.sources["cacheDir"], Source::Settings);
+        assert_eq!(
+            env_var(&expanded.unwrap().unwrap()),
+            ("MTLOG_CACHE_DIR".to_string(), "/mnt/scratch/mtlog".to_string())
+        );
+    }
+
+    /// An invalid directory is left out of the options with a warning.
+    #[test]
+    fn test_apply_invalid() {
+        let mut effective = settings::initialization_options(&LspSettings::default());
+
+        apply(expand_str(""), &mut effective);
+
+        assert!(effective.options.get("cacheDir").is_none());
+        assert_eq!(effective.warnings.len(), 1);
+        assert!(effective.warnings[0].contains("using the server default"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Slash commands for Zed's assistant panel.
+//!
+//! Each command lives in its own submodule with a `run` entry point; the
+//! conversion and formatting logic is kept separate from the Zed API calls so
+//! it can be tested natively.
+
+mod analyze;
+mod check;
+mod codes;
+mod compare;
+mod config;
+mod doctor;
+mod excluded;
+mod explain_merge;
+mod goenv;
+mod import_vscode;
+mod init_config;
+mod preview_suppress;
+mod profile;
+mod recent;
+mod reset;
+mod resolve;
+mod sarif;
+mod support_bundle;
+mod vs_gopls;
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+use zed_extension_api::{
+    process::Command, settings::LspSettings, SlashCommandArgumentCompletion, SlashCommandOutput,
+    SlashCommandOutputSection, Worktree,
+};
+
+use crate::cache;
+use crate::context;
+use crate::decode::decode_output;
+use crate::history::{Analysis, AnalysisHistory};
+use crate::install;
+use crate::locale;
+use crate::property_dictionary;
+use crate::report::{self, Diagnostic};
+use crate::session::lock;
+use crate::settings::{self, EffectiveSettings};
+use crate::suppressions;
+use crate::warmup;
+use crate::MtlogAnalyzerExtension;
+
+/// Runs the slash command named `name` with the given arguments.
+pub(crate) fn run(
+    name: &str,
+    args: &[String],
+    worktree: Option<&Worktree>,
+    extension: &MtlogAnalyzerExtension,
+) -> Result<SlashCommandOutput, String> {
+    let session = &extension.session;
+    let mut output = match name {
+        "mtlog-analyze" => analyze::run(args, require_worktree(name, worktree)?, &extension.history),
+        "mtlog-check" => check::run(args, require_worktree(name, worktree)?, &extension.history),
+        "mtlog-codes" => codes::run(require_worktree(name, worktree)?, extension),
+        "mtlog-compare" => compare::run(args, require_worktree(name, worktree)?, &extension.history),
+        "mtlog-config" => config::run(require_worktree(name, worktree)?, extension),
+        "mtlog-doctor" => doctor::run(require_worktree(name, worktree)?, extension),
+        "mtlog-excluded" => excluded::run(require_worktree(name, worktree)?, extension),
+        "mtlog-explain-merge" => explain_merge::run(require_worktree(name, worktree)?, extension),
+        "mtlog-goenv" => goenv::run(require_worktree(name, worktree)?),
+        "mtlog-import-vscode" => import_vscode::run(args, require_worktree(name, worktree)?),
+        "mtlog-init-config" => init_config::run(require_worktree(name, worktree)?),
+        "mtlog-preview-suppress" => {
+            preview_suppress::run(args, require_worktree(name, worktree)?, &extension.history)
+        }
+        "mtlog-profile" => profile::run(args, &mut lock(session)),
+        "mtlog-recent" => recent::run(args, &lock(&extension.history)),
+        "mtlog-reset" => Ok(reset::run(&mut lock(session))),
+        "mtlog-resolve" => resolve::run(require_worktree(name, worktree)?, extension),
+        "mtlog-sarif" => sarif::run(args, require_worktree(name, worktree)?, &extension.history),
+        "mtlog-support-bundle" => support_bundle::run(require_worktree(name, worktree)?, extension),
+        "mtlog-vs-gopls" => Ok(vs_gopls::run()),
+        _ => Err(format!("unknown slash command: \"{name}\"")),
+    }?;
+
+    // A queued firstRunReport baseline is reported along with the next analysis
+    if let Some(worktree) = worktree.filter(|_| ANALYSIS_COMMANDS.contains(&name)) {
+        if let Some(report) = first_run_report(worktree, extension) {
+            append_section(&mut output, "mtlog: first-run report", report);
+        }
+    }
+    Ok(output)
+}
+
+/// Commands that analyze the project, and so also run a queued `firstRunReport` baseline.
+const ANALYSIS_COMMANDS: &[&str] = &["mtlog-analyze", "mtlog-check", "mtlog-compare", "mtlog-sarif"];
+
+/// Suggests arguments for the slash command named `name`.
+pub(crate) fn complete(
+    name: &str,
+    args: &[String],
+    extension: &MtlogAnalyzerExtension,
+) -> Vec<SlashCommandArgumentCompletion> {
+    match name {
+        "mtlog-compare" => compare::complete(args, &lock(&extension.history)),
+        "mtlog-preview-suppress" => preview_suppress::complete(args),
+        "mtlog-profile" => profile::complete(args),
+        _ => Vec::new(),
+    }
+}
+
+fn require_worktree<'a>(name: &str, worktree: Option<&'a Worktree>) -> Result<&'a Worktree, String> {
+    worktree.ok_or_else(|| format!("/{name} requires an open project"))
+}
+
+/// The options mtlog-lsp would receive for `worktree`, session overrides included.
+fn effective_settings(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<EffectiveSettings, String> {
+    let lsp_settings = LspSettings::for_worktree(settings::LANGUAGE_SERVER_NAME, worktree)?;
+    Ok(merge_settings(Ok(lsp_settings), worktree, extension, |_, _| {}))
+}
+
+/// Builds the initialization options mtlog-lsp receives for `worktree` from
+/// the settings `lookup` returned, layer by layer, calling `step` with the
+/// name of each layer and the options as they stand once it is merged. A
+/// failed lookup yields the defaults with a warning, as when the server is
+/// started.
+pub(crate) fn merge_settings(
+    lookup: Result<LspSettings, String>,
+    worktree: &Worktree,
+    extension: &MtlogAnalyzerExtension,
+    mut step: impl FnMut(&'static str, &EffectiveSettings),
+) -> EffectiveSettings {
+    let lsp_settings = lookup.as_ref().ok();
+    let cache_dir =
+        lsp_settings.map_or(Ok(None), |lsp_settings| MtlogAnalyzerExtension::cache_dir(lsp_settings, worktree));
+    let suppressions =
+        lsp_settings.map_or(Ok(None), |lsp_settings| MtlogAnalyzerExtension::suppressions(lsp_settings, worktree));
+    let dictionary = lsp_settings
+        .map_or(Ok(None), |lsp_settings| MtlogAnalyzerExtension::property_dictionary(lsp_settings, worktree));
+    let warmup =
+        lsp_settings.map_or(Ok(None), |lsp_settings| MtlogAnalyzerExtension::warmup_sample(lsp_settings, worktree));
+    let locale = lsp_settings.map_or(Ok(None), |lsp_settings| locale::setting(settings::locale(lsp_settings)));
+
+    let mut effective = settings::initialization_options_for(lookup, settings::LANGUAGE_SERVER_NAME);
+    step("Zed settings: profile, settings, and initialization_options", &effective);
+    cache::apply(cache_dir, &mut effective);
+    suppressions::apply(suppressions, &mut effective);
+    property_dictionary::apply(dictionary, &mut effective);
+    warmup::apply(warmup, &mut effective);
+    locale::apply(locale, &worktree.shell_env().into_iter().collect(), &mut effective);
+    step("Project files and environment", &effective);
+    lock(&extension.session).apply(&mut effective);
+    step("Session overrides", &effective);
+    settings::set_protocol_version(&mut effective);
+    step("Set by the extension", &effective);
+
+    // Only known once the server was started with a probed binary
+    let binary = extension.binary_in_use(worktree.id());
+    if let Some(version) = binary.and_then(|path| extension.binary_versions.get(path)) {
+        settings::set_docs_version(&mut effective, version);
+        let warnings = settings::unsupported_option_warnings(&effective, version);
+        effective.warnings.extend(warnings);
+    }
+    step("Matched to the binary", &effective);
+    effective
+}
+
+/// Programs run by name, as `extension.toml` grants them. Zed matches a grant
+/// against the exact command, so `which` only confirms the program is there,
+/// and the shell's `PATH`, passed along with the rest of its environment,
+/// finds the same binary.
+pub(crate) const GO: &str = "go";
+const GIT: &str = "git";
+
+/// Name of the standalone analyzer, run as a `go vet` tool.
+const ANALYZER_NAME: &str = "mtlog-analyzer";
+
+/// Package pattern analyzed, covering the whole project.
+const ANALYSIS_TARGET: &str = "./...";
+
+/// Analyzes the whole project with `go vet -vettool=mtlog-analyzer` for the
+/// command `name`, recording the run in `history`.
+fn analyze_project(name: &str, worktree: &Worktree, history: &Mutex<AnalysisHistory>) -> Result<Vec<Diagnostic>, String> {
+    let diagnostics = analyze(name, worktree, &[ANALYSIS_TARGET], history)?;
+    context::record(&diagnostics, &worktree.root_path(), &install::work_dir());
+    Ok(diagnostics)
+}
+
+/// Runs the `firstRunReport` analysis queued when the server for `worktree`
+/// started, if it has not run yet, returning a line summarizing the findings.
+/// A failed analysis is queued again, so the next analysis command retries it.
+fn first_run_report(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Option<String> {
+    if !lock(&extension.first_runs).take_pending(worktree.id()) {
+        return None;
+    }
+    let report = match analyze_project("mtlog-check", worktree, &extension.history) {
+        Ok(diagnostics) => check::summary_line(&diagnostics, &worktree.root_path()),
+        Err(err) => {
+            lock(&extension.first_runs).retry(worktree.id());
+            format!("firstRunReport failed and will be retried with the next analysis: {err}")
+        }
+    };
+    eprintln!("mtlog-analyzer: {report}");
+    Some(report)
+}
+
+/// Lists the import paths of the project's packages with `go list`.
+fn list_packages(name: &str, worktree: &Worktree) -> Result<Vec<String>, String> {
+    worktree.which(GO).ok_or_else(|| format!("/{name} needs go on PATH"))?;
+    let root = worktree.root_path();
+    let output = Command::new(GO)
+        .args(["-C", root.as_str(), "list", ANALYSIS_TARGET])
+        .envs(worktree.shell_env())
+        .output()?;
+    if output.status != Some(0) {
+        let stderr = decode_output(&output.stderr, "go list");
+        let detail = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+        return Err(format!("go list failed: {}", detail.trim()));
+    }
+    Ok(decode_output(&output.stdout, "go list")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Lists the project's Go files, relative to the root, with `git ls-files`:
+/// tracked or not, but not ignored. Extensions cannot walk the worktree,
+/// so projects that are not git repositories cannot be listed.
+pub(crate) fn list_go_files(worktree: &Worktree) -> Result<Vec<String>, String> {
+    worktree.which(GIT).ok_or("listing the project's Go files needs git on PATH")?;
+    let root = worktree.root_path();
+    let output = Command::new(GIT)
+        .args(["-C", root.as_str(), "ls-files", "--cached", "--others", "--exclude-standard", "*.go"])
+        .envs(worktree.shell_env())
+        .output()?;
+    if output.status != Some(0) {
+        let stderr = decode_output(&output.stderr, "git ls-files");
+        let detail = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+        return Err(format!("git ls-files failed, is the project a git repository? {}", detail.trim()));
+    }
+    Ok(decode_output(&output.stdout, "git ls-files")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Analyzes the packages matched by `targets`, relative to the project root.
+fn analyze(
+    name: &str,
+    worktree: &Worktree,
+    targets: &[&str],
+    history: &Mutex<AnalysisHistory>,
+) -> Result<Vec<Diagnostic>, String> {
+    let analyzer = worktree.which(ANALYZER_NAME).ok_or_else(|| {
+        format!(
+            "/{name} needs {ANALYZER_NAME} on PATH; install it with: \
+             go install github.com/willibrandon/mtlog/cmd/mtlog-analyzer@latest"
+        )
+    })?;
+    analyze_with(name, worktree, &analyzer, targets, history)
+}
+
+/// Like [`analyze`], running the analyzer binary at `analyzer`.
+fn analyze_with(
+    name: &str,
+    worktree: &Worktree,
+    analyzer: &str,
+    targets: &[&str],
+    history: &Mutex<AnalysisHistory>,
+) -> Result<Vec<Diagnostic>, String> {
+    worktree.which(GO).ok_or_else(|| format!("/{name} needs go on PATH"))?;
+
+    // Zed runs commands outside the project, so `-C` points go at the root.
+    let root = worktree.root_path();
+    let started = Instant::now();
+    let output = Command::new(GO)
+        .args(["-C", root.as_str(), "vet", &format!("-vettool={analyzer}"), "-json"])
+        .args(targets.iter().copied())
+        .envs(worktree.shell_env())
+        .output()?;
+    let stderr = decode_output(&output.stderr, "go vet");
+    let diagnostics = report::parse_vet_json(&stderr).map_err(|err| {
+        let detail = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+        format!("go vet did not produce a report ({err}): {}", detail.trim())
+    })?;
+    lock(history).record(Analysis {
+        target: targets.join(" "),
+        analyzer: analyzer.to_string(),
+        elapsed: started.elapsed(),
+        findings: diagnostics.len(),
+    });
+    Ok(diagnostics)
+}
+
+/// Note appended to commands that change what mtlog-lsp receives.
+const RESTART_NOTE: &str = "mtlog-lsp reads its options at startup; run \"editor: restart language server\" to apply the change.";
+
+/// Wraps `text` in a single output section labelled `label`.
+fn output(label: &str, text: String) -> SlashCommandOutput {
+    SlashCommandOutput {
+        sections: vec![SlashCommandOutputSection {
+            range: (0..text.len()).into(),
+            label: label.to_string(),
+        }],
+        text,
+    }
+}
+
+/// Adds `text` to `output` as a section of its own, after a blank line.
+fn append_section(output: &mut SlashCommandOutput, label: &str, text: String) {
+    if !output.text.is_empty() && !output.text.ends_with('\n') {
+        output.text.push('\n');
+    }
+    output.text.push('\n');
+    let start = output.text.len();
+    output.text.push_str(&text);
+    output.sections.push(SlashCommandOutputSection {
+        range: (start..output.text.len()).into(),
+        label: label.to_string(),
+    });
+}
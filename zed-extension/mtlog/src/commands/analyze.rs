@@ -0,0 +1,154 @@
+//! `/mtlog-analyze <file>`: analyzes the package holding `file` and lists the
+//! findings in that file, for `analysisTiming: "manual"`.
+//!
+//! Extensions cannot send requests to a running language server, so the
+//! package is analyzed with mtlog-analyzer, as `/mtlog-sarif` does, and the
+//! findings are shown in the assistant panel rather than in the editor.
+
+use std::sync::Mutex;
+
+use zed_extension_api::{SlashCommandOutput, Worktree};
+
+use crate::history::AnalysisHistory;
+use crate::report::{Diagnostic, Severity};
+
+pub(super) fn run(
+    args: &[String],
+    worktree: &Worktree,
+    history: &Mutex<AnalysisHistory>,
+) -> Result<SlashCommandOutput, String> {
+    let file = match args {
+        [file] => validate_file(file)?,
+        _ => return Err("usage: /mtlog-analyze <file.go>".to_string()),
+    };
+
+    let diagnostics = super::analyze("mtlog-analyze", worktree, &[&package_target(&file)], history)?;
+    let findings = in_file(&diagnostics, &worktree.root_path(), &file);
+
+    Ok(super::output("mtlog: analysis", render(&file, &findings)))
+}
+
+/// Checks that `file` is a project-relative Go file and returns it with
+/// forward slashes and without a leading `./`.
+fn validate_file(file: &str) -> Result<String, String> {
+    let file = file.trim().replace('\\', "/");
+    let file = file.strip_prefix("./").unwrap_or(&file);
+
+    if file.starts_with('/') || file.get(1..2) == Some(":") {
+        return Err(format!("{file} must be relative to the project root"));
+    }
+    if file.split('/').any(|component| component == "..") {
+        return Err(format!("{file} must stay inside the project"));
+    }
+    if !file.ends_with(".go") {
+        return Err(format!("{file} is not a Go file"));
+    }
+    Ok(file.to_string())
+}
+
+/// The `go vet` pattern for the package in `file`'s directory.
+fn package_target(file: &str) -> String {
+    match file.rsplit_once('/') {
+        Some((dir, _)) => format!("./{dir}"),
+        None => ".".to_string(),
+    }
+}
+
+/// The findings reported for `file`, which is relative to `root`.
+fn in_file<'a>(diagnostics: &'a [Diagnostic], root: &str, file: &str) -> Vec<&'a Diagnostic> {
+    let root = root.trim_end_matches(['/', '\\']);
+    diagnostics
+        .iter()
+        .filter(|diagnostic| {
+            diagnostic
+                .file
+                .strip_prefix(root)
+                .and_then(|rest| rest.strip_prefix(['/', '\\']))
+                .is_some_and(|relative| relative.replace('\\', "/") == file)
+        })
+        .collect()
+}
+
+fn render(file: &str, findings: &[&Diagnostic]) -> String {
+    if findings.is_empty() {
+        return format!("No mtlog findings in {file}.\n");
+    }
+    let mut text = match findings.len() {
+        1 => format!("1 mtlog finding in {file}:\n\n"),
+        n => format!("{n} mtlog findings in {file}:\n\n"),
+    };
+    for finding in findings {
+        let code = finding.code.as_deref().map(|code| format!("[{code}] ")).unwrap_or_default();
+        let severity = match finding.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Suggestion => "suggestion",
+        };
+        text.push_str(&format!(
+            "- {}:{} {code}{severity}: {}\n",
+            finding.line, finding.column, finding.message
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(file: &str, line: u64, code: Option<&str>, severity: Severity) -> Diagnostic {
+        Diagnostic {
+            file: file.to_string(),
+            line,
+            column: 2,
+            code: code.map(str::to_string),
+            severity,
+            message: "finding".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_validate_file() {
+        assert_eq!(validate_file("./internal/db.go"), Ok("internal/db.go".to_string()));
+        assert_eq!(validate_file("internal\\db.go"), Ok("internal/db.go".to_string()));
+        assert!(validate_file("/work/app/main.go").is_err());
+        assert!(validate_file("C:\\app\\main.go").is_err());
+        assert!(validate_file("../other/main.go").is_err());
+        assert!(validate_file("README.md").is_err());
+    }
+
+    /// The trigger analyzes just the package holding the file.
+    #[test]
+    fn test_package_target() {
+        assert_eq!(package_target("main.go"), ".");
+        assert_eq!(package_target("internal/db/db.go"), "./internal/db");
+    }
+
+    #[test]
+    fn test_in_file() {
+        let diagnostics = vec![
+            diagnostic("/work/app/internal/db.go", 7, Some("MTLOG007"), Severity::Suggestion),
+            diagnostic("/work/app/internal/cache.go", 3, None, Severity::Error),
+            diagnostic("/work/app/internal/db.go", 9, None, Severity::Error),
+        ];
+
+        let findings = in_file(&diagnostics, "/work/app/", "internal/db.go");
+
+        assert_eq!(findings.iter().map(|d| d.line).collect::<Vec<_>>(), vec![7, 9]);
+    }
+
+    #[test]
+    fn test_render() {
+        let suggestion = diagnostic("/work/app/main.go", 7, Some("MTLOG007"), Severity::Suggestion);
+        let uncoded = diagnostic("/work/app/main.go", 9, None, Severity::Error);
+
+        assert_eq!(
+            render("main.go", &[&suggestion, &uncoded]),
+            "2 mtlog findings in main.go:\n\n\
+             - 7:2 [MTLOG007] suggestion: finding\n\
+             - 9:2 error: finding\n"
+        );
+        assert!(render("main.go", &[&uncoded]).starts_with("1 mtlog finding in main.go:"));
+        assert_eq!(render("main.go", &[]), "No mtlog findings in main.go.\n");
+    }
+}
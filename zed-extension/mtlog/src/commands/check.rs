@@ -0,0 +1,292 @@
+//! `/mtlog-check [--timeout <duration>]`: analyzes the whole project and
+//! summarizes the findings by code, within a deadline.
+//!
+//! Extensions cannot interrupt a command they started, so the project is
+//! analyzed in batches of packages and no batch is started once the deadline
+//! has passed. A run that times out reports what the finished batches found.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use zed_extension_api::{SlashCommandOutput, Worktree};
+
+use crate::context;
+use crate::history::AnalysisHistory;
+use crate::install;
+use crate::report::Diagnostic;
+
+/// Deadline used when `--timeout` is not given.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Packages analyzed per `go vet` run.
+const BATCH_SIZE: usize = 16;
+
+/// Packages named when listing the ones a timeout left out.
+const SKIPPED_SHOWN: usize = 5;
+
+pub(super) fn run(
+    args: &[String],
+    worktree: &Worktree,
+    history: &Mutex<AnalysisHistory>,
+) -> Result<SlashCommandOutput, String> {
+    let timeout = parse_timeout(args)?;
+    let packages = super::list_packages("mtlog-check", worktree)?;
+
+    let started = Instant::now();
+    let check = check_batches(&packages, timeout, || started.elapsed(), |batch| {
+        let targets: Vec<&str> = batch.iter().map(String::as_str).collect();
+        super::analyze("mtlog-check", worktree, &targets, history)
+    })?;
+    context::record(&check.diagnostics, &worktree.root_path(), &install::work_dir());
+
+    Ok(super::output("mtlog: project check", render(&check, timeout)))
+}
+
+/// Parses `--timeout 90s`, `--timeout=2m`, or a bare number of seconds.
+fn parse_timeout(args: &[String]) -> Result<Duration, String> {
+    let value = match args {
+        [] => return Ok(DEFAULT_TIMEOUT),
+        [flag, value] if flag == "--timeout" => value.as_str(),
+        [arg] => match arg.strip_prefix("--timeout=") {
+            Some(value) => value,
+            None => return Err(format!("unexpected argument \"{arg}\"; usage: /mtlog-check [--timeout <duration>]")),
+        },
+        _ => return Err("usage: /mtlog-check [--timeout <duration>]".to_string()),
+    };
+
+    let (number, unit) = match value.trim().strip_suffix('m') {
+        Some(minutes) => (minutes, 60),
+        None => (value.trim().strip_suffix('s').unwrap_or(value.trim()), 1),
+    };
+    match number.parse::<u64>().ok().filter(|&amount| amount > 0).and_then(|amount| amount.checked_mul(unit)) {
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => Err(format!("invalid timeout \"{value}\"; expected a duration such as 90s or 5m")),
+    }
+}
+
+/// The outcome of a whole-project check.
+#[derive(Debug, PartialEq, Eq)]
+struct Check {
+    diagnostics: Vec<Diagnostic>,
+    analyzed: usize,
+    /// Packages left out because the deadline passed first.
+    skipped: Vec<String>,
+    elapsed: Duration,
+}
+
+/// Analyzes `packages` in batches with `analyze`, starting no batch once
+/// `elapsed` reports that `timeout` has passed.
+fn check_batches(
+    packages: &[String],
+    timeout: Duration,
+    elapsed: impl Fn() -> Duration,
+    mut analyze: impl FnMut(&[String]) -> Result<Vec<Diagnostic>, String>,
+) -> Result<Check, String> {
+    let mut diagnostics = Vec::new();
+    let mut analyzed = 0;
+    for batch in packages.chunks(BATCH_SIZE) {
+        if elapsed() >= timeout {
+            break;
+        }
+        diagnostics.extend(analyze(batch)?);
+        analyzed += batch.len();
+    }
+    Ok(Check {
+        diagnostics,
+        analyzed,
+        skipped: packages[analyzed..].to_vec(),
+        elapsed: elapsed(),
+    })
+}
+
+/// One line summarizing a whole-project analysis, as logged for
+/// `firstRunReport`.
+pub(super) fn summary_line(diagnostics: &[Diagnostic], root: &str) -> String {
+    if diagnostics.is_empty() {
+        return format!("first analysis of {root}: no findings");
+    }
+    let mut by_code: BTreeMap<&str, usize> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        *by_code.entry(diagnostic.code.as_deref().unwrap_or("uncoded")).or_default() += 1;
+    }
+    let files = diagnostics.iter().map(|diagnostic| diagnostic.file.as_str()).collect::<BTreeSet<_>>();
+    let counts: Vec<String> = by_code.into_iter().map(|(code, count)| format!("{code}: {count}")).collect();
+    format!(
+        "first analysis of {root}: {} findings in {} files ({}); run /mtlog-check for the full report",
+        diagnostics.len(),
+        files.len(),
+        counts.join(", ")
+    )
+}
+
+fn render(check: &Check, timeout: Duration) -> String {
+    let total = check.analyzed + check.skipped.len();
+    let mut text = format!(
+        "Analyzed {} of {total} packages in {} s: {} findings.\n",
+        check.analyzed,
+        check.elapsed.as_secs(),
+        check.diagnostics.len()
+    );
+
+    let mut by_code: BTreeMap<&str, usize> = BTreeMap::new();
+    for diagnostic in &check.diagnostics {
+        *by_code.entry(diagnostic.code.as_deref().unwrap_or("uncoded")).or_default() += 1;
+    }
+    if !by_code.is_empty() {
+        text.push_str("\n| Code | Findings |\n|------|----------|\n");
+        for (code, count) in by_code {
+            text.push_str(&format!("| {code} | {count} |\n"));
+        }
+    }
+
+    if !check.skipped.is_empty() {
+        let shown: Vec<&str> = check.skipped.iter().take(SKIPPED_SHOWN).map(String::as_str).collect();
+        let more = match check.skipped.len().saturating_sub(SKIPPED_SHOWN) {
+            0 => String::new(),
+            n => format!(" and {n} more"),
+        };
+        text.push_str(&format!(
+            "\nTimed out after {} s; these results are partial. Not analyzed: {}{more}. \
+             Pass a longer --timeout to check everything.\n",
+            timeout.as_secs(),
+            shown.join(", ")
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::report::Severity;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn packages(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("example.com/app/pkg{i}")).collect()
+    }
+
+    fn finding(code: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            file: "/work/app/main.go".to_string(),
+            line: 1,
+            column: 1,
+            code: code.map(str::to_string),
+            severity: Severity::Warning,
+            message: "finding".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout(&[]), Ok(DEFAULT_TIMEOUT));
+        assert_eq!(parse_timeout(&args(&["--timeout", "90s"])), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_timeout(&args(&["--timeout=2m"])), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_timeout(&args(&["--timeout", "45"])), Ok(Duration::from_secs(45)));
+        for invalid in [
+            &["--timeout", "0"][..],
+            &["--timeout", "soon"],
+            &["--timeout"],
+            &["90s"],
+            &["--timeout=-5s"],
+            &["--timeout", "999999999999999999m"],
+        ] {
+            assert!(parse_timeout(&args(invalid)).is_err(), "{invalid:?}");
+        }
+    }
+
+    /// Every batch runs when each finishes well within the deadline.
+    #[test]
+    fn test_check_within_deadline() {
+        let clock = Cell::new(Duration::ZERO);
+        let mut batches = Vec::new();
+
+        let check = check_batches(&packages(40), Duration::from_secs(60), || clock.get(), |batch| {
+            batches.push(batch.len());
+            clock.set(clock.get() + Duration::from_secs(10));
+            Ok(vec![finding(Some("MTLOG001"))])
+        })
+        .unwrap();
+
+        assert_eq!(batches, vec![16, 16, 8]);
+        assert_eq!(check.analyzed, 40);
+        assert!(check.skipped.is_empty());
+        assert_eq!(check.diagnostics.len(), 3);
+        assert_eq!(check.elapsed, Duration::from_secs(30));
+    }
+
+    /// Once the deadline passes no further batch starts, and the rest are reported as skipped.
+    #[test]
+    fn test_check_times_out() {
+        let clock = Cell::new(Duration::ZERO);
+
+        let check = check_batches(&packages(40), Duration::from_secs(60), || clock.get(), |_| {
+            clock.set(clock.get() + Duration::from_secs(45));
+            Ok(vec![finding(None)])
+        })
+        .unwrap();
+
+        assert_eq!(check.analyzed, 32);
+        assert_eq!(check.skipped, packages(40)[32..].to_vec());
+        assert_eq!(check.diagnostics.len(), 2);
+    }
+
+    #[test]
+    fn test_check_error_stops() {
+        let result = check_batches(&packages(20), DEFAULT_TIMEOUT, || Duration::ZERO, |_| Err("go vet failed".to_string()));
+
+        assert_eq!(result, Err("go vet failed".to_string()));
+    }
+
+    #[test]
+    fn test_render_complete() {
+        let check = Check {
+            diagnostics: vec![finding(Some("MTLOG004")), finding(Some("MTLOG001")), finding(Some("MTLOG004"))],
+            analyzed: 3,
+            skipped: Vec::new(),
+            elapsed: Duration::from_secs(12),
+        };
+
+        assert_eq!(
+            render(&check, DEFAULT_TIMEOUT),
+            "Analyzed 3 of 3 packages in 12 s: 3 findings.\n\n\
+             | Code | Findings |\n|------|----------|\n\
+             | MTLOG001 | 1 |\n\
+             | MTLOG004 | 2 |\n"
+        );
+    }
+
+    #[test]
+    fn test_summary_line() {
+        let mut diagnostics = vec![finding(Some("MTLOG004")), finding(Some("MTLOG001")), finding(None)];
+        diagnostics[2].file = "/work/app/db.go".to_string();
+
+        assert_eq!(
+            summary_line(&diagnostics, "/work/app"),
+            "first analysis of /work/app: 3 findings in 2 files (MTLOG001: 1, MTLOG004: 1, uncoded: 1); \
+             run /mtlog-check for the full report"
+        );
+        assert_eq!(summary_line(&[], "/work/app"), "first analysis of /work/app: no findings");
+    }
+
+    #[test]
+    fn test_render_timed_out() {
+        let check = Check {
+            diagnostics: Vec::new(),
+            analyzed: 16,
+            skipped: packages(8),
+            elapsed: Duration::from_secs(75),
+        };
+
+        let rendered = render(&check, Duration::from_secs(60));
+
+        assert!(rendered.starts_with("Analyzed 16 of 24 packages in 75 s: 0 findings.\n"));
+        assert!(rendered.contains("Timed out after 60 s; these results are partial."));
+        assert!(rendered.contains("example.com/app/pkg4 and 3 more."));
+    }
+}
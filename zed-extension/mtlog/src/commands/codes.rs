@@ -0,0 +1,197 @@
+//! `/mtlog-codes`: lists every MTLOG code with its default severity and what
+//! the effective configuration does with it.
+//!
+//! mtlog-analyzer has no flag listing its codes, so the table is kept here,
+//! each code with the release that introduced it. Codes newer than the
+//! probed mtlog-lsp are marked rather than hidden.
+
+use zed_extension_api::{serde_json::Value, SlashCommandOutput, Worktree};
+
+use crate::probe::{self, VersionProbe};
+use crate::report::RULES;
+use crate::MtlogAnalyzerExtension;
+
+/// Severity each code is reported with by default, and the release that
+/// introduced it, for codes added after the first releases.
+const DEFAULTS: &[(&str, &str, Option<&str>)] = &[
+    ("MTLOG001", "error", None),
+    ("MTLOG002", "error", None),
+    ("MTLOG003", "error; warning in With() calls", None),
+    ("MTLOG004", "suggestion; error for invalid names", None),
+    ("MTLOG005", "warning; suggestion for a missing `@`", None),
+    ("MTLOG006", "suggestion; error when the last argument is not an error", None),
+    ("MTLOG007", "suggestion", None),
+    ("MTLOG008", "warning", None),
+    ("MTLOG009", "error", Some("0.9.0")),
+    ("MTLOG010", "error", Some("0.9.0")),
+    ("MTLOG011", "warning", Some("0.9.0")),
+    ("MTLOG012", "suggestion", Some("0.9.0")),
+    ("MTLOG013", "error", Some("0.9.0")),
+];
+
+/// How the effective configuration treats one code.
+#[derive(Debug, PartialEq, Eq)]
+enum Effective {
+    Default,
+    Suppressed(&'static str),
+    Overridden(String),
+}
+
+/// The parts of the configuration that change how codes are reported.
+#[derive(Debug, Default)]
+struct Overrides {
+    disable_all: bool,
+    suppressed: Vec<String>,
+    severities: Vec<(String, String)>,
+}
+
+impl Overrides {
+    fn from_options(options: &Value) -> Self {
+        Self {
+            disable_all: options.get("disableAll").and_then(Value::as_bool).unwrap_or(false),
+            suppressed: options
+                .get("suppressedCodes")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(|code| code.trim().to_ascii_uppercase())
+                .collect(),
+            severities: options
+                .get("severityOverrides")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .filter_map(|(code, severity)| Some((code.trim().to_ascii_uppercase(), severity.as_str()?.to_string())))
+                .collect(),
+        }
+    }
+
+    fn effective(&self, code: &str) -> Effective {
+        if self.disable_all {
+            Effective::Suppressed("disableAll")
+        } else if self.suppressed.iter().any(|suppressed| suppressed == code) {
+            Effective::Suppressed("suppressedCodes")
+        } else if let Some((_, severity)) = self.severities.iter().find(|(overridden, _)| overridden == code) {
+            Effective::Overridden(severity.clone())
+        } else {
+            Effective::Default
+        }
+    }
+
+    /// Codes the configuration names that mtlog-analyzer does not report.
+    fn unknown_codes(&self) -> Vec<(&'static str, &str)> {
+        let known = |code: &str| RULES.iter().any(|(rule, _)| *rule == code);
+        let suppressed = self.suppressed.iter().map(|code| ("suppressedCodes", code.as_str()));
+        let overridden = self.severities.iter().map(|(code, _)| ("severityOverrides", code.as_str()));
+        suppressed.chain(overridden).filter(|(_, code)| !known(code)).collect()
+    }
+}
+
+pub(super) fn run(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<SlashCommandOutput, String> {
+    let effective = super::effective_settings(worktree, extension)?;
+    let version = extension
+        .binary_in_use(worktree.id())
+        .and_then(|path| extension.binary_versions.get(path));
+
+    let text = render(&Overrides::from_options(&effective.options), version);
+    Ok(super::output("mtlog: diagnostic codes", text))
+}
+
+fn render(overrides: &Overrides, version: Option<&VersionProbe>) -> String {
+    let mut text = String::from(
+        "| Code | Reports | Default severity | Effective |\n\
+         |------|---------|------------------|-----------|\n",
+    );
+    for (code, description) in RULES {
+        let (default, since) = DEFAULTS
+            .iter()
+            .find(|(listed, _, _)| listed == code)
+            .map_or(("error", None), |(_, default, since)| (*default, *since));
+        let effective = match overrides.effective(code) {
+            Effective::Default => "default".to_string(),
+            Effective::Suppressed(setting) => format!("off, by `{setting}`"),
+            Effective::Overridden(severity) => format!("{severity}, by `severityOverrides`"),
+        };
+        let effective = match (since, version) {
+            (Some(since), Some(version @ VersionProbe::Version(reported))) if probe::is_older_than(version, since) => {
+                format!("{effective}; not in {reported}, added in {since}")
+            }
+            _ => effective,
+        };
+        text.push_str(&format!("| {code} | {description} | {default} | {effective} |\n"));
+    }
+
+    let unknown = overrides.unknown_codes();
+    if !unknown.is_empty() {
+        text.push_str("\nNot MTLOG codes, so these entries have no effect:\n");
+        for (setting, code) in unknown {
+            text.push_str(&format!("- `{code}` in `{setting}`\n"));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    fn row<'a>(rendered: &'a str, code: &str) -> &'a str {
+        rendered.lines().find(|line| line.starts_with(&format!("| {code} |"))).unwrap()
+    }
+
+    #[test]
+    fn test_defaults_cover_every_code() {
+        assert_eq!(DEFAULTS.len(), RULES.len());
+        for (code, _) in RULES {
+            assert!(DEFAULTS.iter().any(|(listed, _, _)| listed == code), "{code}");
+        }
+    }
+
+    /// Suppressions win over severity overrides; codes are matched regardless of case.
+    #[test]
+    fn test_render_merges_overrides() {
+        let overrides = Overrides::from_options(&json!({
+            "suppressedCodes": ["mtlog004", "MTLOG099"],
+            "severityOverrides": { "MTLOG002": "warning", "MTLOG004": "error" },
+        }));
+
+        let rendered = render(&overrides, None);
+
+        assert_eq!(row(&rendered, "MTLOG001"), "| MTLOG001 | Template/argument count mismatch | error | default |");
+        assert_eq!(
+            row(&rendered, "MTLOG002"),
+            "| MTLOG002 | Invalid format specifier | error | warning, by `severityOverrides` |"
+        );
+        assert!(row(&rendered, "MTLOG004").ends_with("| off, by `suppressedCodes` |"));
+        assert!(rendered.contains("- `MTLOG099` in `suppressedCodes`\n"));
+        assert!(!rendered.contains("in `severityOverrides`\n"));
+    }
+
+    #[test]
+    fn test_render_disable_all() {
+        let rendered = render(&Overrides::from_options(&json!({ "disableAll": true })), None);
+
+        for (code, _) in RULES {
+            assert!(row(&rendered, code).ends_with("| off, by `disableAll` |"), "{code}");
+        }
+        assert!(!rendered.contains("Not MTLOG codes"));
+    }
+
+    /// Codes newer than the probed binary are marked; an unknown version marks nothing.
+    #[test]
+    fn test_render_version_gated() {
+        let overrides = Overrides::default();
+        let old = VersionProbe::Version("0.8.1".to_string());
+
+        let rendered = render(&overrides, Some(&old));
+        assert!(row(&rendered, "MTLOG009").ends_with("| default; not in 0.8.1, added in 0.9.0 |"));
+        assert!(row(&rendered, "MTLOG008").ends_with("| default |"));
+
+        for version in [VersionProbe::Version("0.10.0".to_string()), VersionProbe::Unrecognized] {
+            assert!(!render(&overrides, Some(&version)).contains("not in"));
+        }
+        assert!(!render(&overrides, None).contains("not in"));
+    }
+}
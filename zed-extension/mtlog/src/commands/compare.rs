@@ -0,0 +1,211 @@
+//! `/mtlog-compare <pathA> <pathB>`: analyzes the project with two
+//! mtlog-analyzer binaries and lists the findings that differ, for checking
+//! an analyzer change before it is released.
+//!
+//! Extensions cannot list directories outside their work dir, so paths are
+//! completed from the analyzer binaries the extension has already run.
+
+use std::sync::Mutex;
+
+use zed_extension_api::{SlashCommandArgumentCompletion, SlashCommandOutput, Worktree};
+
+use crate::discovery;
+use crate::history::AnalysisHistory;
+use crate::report::{self, Diagnostic, DiagnosticDiff, Severity};
+
+/// Findings listed per section before the rest are only counted.
+const LISTED: usize = 20;
+
+pub(super) fn run(
+    args: &[String],
+    worktree: &Worktree,
+    history: &Mutex<AnalysisHistory>,
+) -> Result<SlashCommandOutput, String> {
+    let root = worktree.root_path();
+    let (baseline, candidate) = match args {
+        [a, b] => (discovery::resolve_dir(a.trim(), &root), discovery::resolve_dir(b.trim(), &root)),
+        _ => return Err("usage: /mtlog-compare <pathA> <pathB>".to_string()),
+    };
+
+    let analyze = |analyzer: &str| {
+        super::analyze_with("mtlog-compare", worktree, analyzer, &[super::ANALYSIS_TARGET], history)
+            .map_err(|err| format!("{analyzer}: {err}"))
+    };
+    let before = analyze(&baseline)?;
+    let after = analyze(&candidate)?;
+    let diff = report::diff(&before, &after);
+
+    Ok(super::output(
+        "mtlog: analyzer comparison",
+        render(&baseline, &candidate, (before.len(), after.len()), &diff, &root),
+    ))
+}
+
+/// Completes either path with the analyzer binaries run so far.
+pub(super) fn complete(args: &[String], history: &AnalysisHistory) -> Vec<SlashCommandArgumentCompletion> {
+    let typed = args.last().map(String::as_str).unwrap_or("");
+    history
+        .analyzers()
+        .into_iter()
+        .filter(|analyzer| analyzer.starts_with(typed))
+        .map(|analyzer| SlashCommandArgumentCompletion {
+            label: analyzer.to_string(),
+            new_text: analyzer.to_string(),
+            run_command: args.len() >= 2,
+        })
+        .collect()
+}
+
+fn render(baseline: &str, candidate: &str, counts: (usize, usize), diff: &DiagnosticDiff, root: &str) -> String {
+    let mut text = format!(
+        "Compared A: {baseline} ({} findings)\nwith B: {candidate} ({} findings).\n\n\
+         | New in B | Only in A | Severity changed | Unchanged |\n\
+         |----------|-----------|------------------|-----------|\n\
+         | {} | {} | {} | {} |\n",
+        counts.0,
+        counts.1,
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len(),
+        diff.unchanged
+    );
+    if diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty() {
+        text.push_str("\nBoth binaries report the same findings.\n");
+        return text;
+    }
+
+    let location = |finding: &Diagnostic| {
+        let file = finding
+            .file
+            .strip_prefix(root.trim_end_matches(['/', '\\']))
+            .and_then(|rest| rest.strip_prefix(['/', '\\']))
+            .unwrap_or(&finding.file);
+        let code = finding.code.as_deref().map(|code| format!(" [{code}]")).unwrap_or_default();
+        format!("{}:{}:{}{code}", file.replace('\\', "/"), finding.line, finding.column)
+    };
+    let mut section = |title: &str, lines: Vec<String>| {
+        if lines.is_empty() {
+            return;
+        }
+        text.push_str(&format!("\n### {title}\n\n"));
+        for line in lines.iter().take(LISTED) {
+            text.push_str(&format!("- {line}\n"));
+        }
+        if lines.len() > LISTED {
+            text.push_str(&format!("- and {} more\n", lines.len() - LISTED));
+        }
+    };
+
+    section(
+        "New in B",
+        diff.added
+            .iter()
+            .map(|finding| format!("{} {}: {}", location(finding), severity(finding.severity), finding.message))
+            .collect(),
+    );
+    section(
+        "Only in A (possible regressions)",
+        diff.removed
+            .iter()
+            .map(|finding| format!("{} {}: {}", location(finding), severity(finding.severity), finding.message))
+            .collect(),
+    );
+    section(
+        "Severity changed",
+        diff.changed
+            .iter()
+            .map(|(before, after)| {
+                format!(
+                    "{} {} → {}: {}",
+                    location(before),
+                    severity(before.severity),
+                    severity(after.severity),
+                    before.message
+                )
+            })
+            .collect(),
+    );
+    text
+}
+
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Suggestion => "suggestion",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::history::Analysis;
+
+    fn finding(line: u64, code: &str, severity: Severity) -> Diagnostic {
+        Diagnostic {
+            file: "/work/app/internal/db.go".to_string(),
+            line,
+            column: 2,
+            code: Some(code.to_string()),
+            severity,
+            message: "finding".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let before = vec![finding(3, "MTLOG001", Severity::Error), finding(7, "MTLOG004", Severity::Warning)];
+        let after = vec![finding(7, "MTLOG004", Severity::Error), finding(9, "MTLOG002", Severity::Error)];
+        let diff = report::diff(&before, &after);
+
+        let rendered = render("/go/bin/mtlog-analyzer", "/src/mtlog/mtlog-analyzer", (2, 2), &diff, "/work/app");
+
+        assert!(rendered.starts_with(
+            "Compared A: /go/bin/mtlog-analyzer (2 findings)\nwith B: /src/mtlog/mtlog-analyzer (2 findings).\n"
+        ));
+        assert!(rendered.contains("| 1 | 1 | 1 | 0 |\n"));
+        assert!(rendered.contains("### New in B\n\n- internal/db.go:9:2 [MTLOG002] error: finding\n"));
+        assert!(rendered.contains("### Only in A (possible regressions)\n\n- internal/db.go:3:2 [MTLOG001] error: finding\n"));
+        assert!(rendered.contains("### Severity changed\n\n- internal/db.go:7:2 [MTLOG004] warning → error: finding\n"));
+    }
+
+    #[test]
+    fn test_render_identical() {
+        let findings = vec![finding(3, "MTLOG001", Severity::Error)];
+        let rendered = render("a", "b", (1, 1), &report::diff(&findings, &findings), "/work/app");
+
+        assert!(rendered.ends_with("| 0 | 0 | 0 | 1 |\n\nBoth binaries report the same findings.\n"));
+    }
+
+    #[test]
+    fn test_render_limits_listing() {
+        let after: Vec<Diagnostic> = (1..=25).map(|line| finding(line, "MTLOG001", Severity::Error)).collect();
+        let rendered = render("a", "b", (0, 25), &report::diff(&[], &after), "/work/app");
+
+        assert_eq!(rendered.matches("[MTLOG001] error").count(), LISTED);
+        assert!(rendered.contains("- and 5 more\n"));
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut history = AnalysisHistory::default();
+        for analyzer in ["/go/bin/mtlog-analyzer", "/src/mtlog/mtlog-analyzer"] {
+            history.record(Analysis {
+                target: "./...".to_string(),
+                analyzer: analyzer.to_string(),
+                elapsed: Duration::from_millis(10),
+                findings: 0,
+            });
+        }
+
+        let first = complete(&["/go".to_string()], &history);
+        assert_eq!(first.iter().map(|c| c.new_text.as_str()).collect::<Vec<_>>(), vec!["/go/bin/mtlog-analyzer"]);
+        assert!(!first[0].run_command);
+
+        let second = complete(&["/go/bin/mtlog-analyzer".to_string(), String::new()], &history);
+        assert_eq!(second.len(), 2);
+        assert!(second.iter().all(|c| c.run_command));
+    }
+}
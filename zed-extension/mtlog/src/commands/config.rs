@@ -0,0 +1,196 @@
+//! `/mtlog-config`: prints the initialization options mtlog-lsp receives,
+//! with each top-level key annotated with where its value came from.
+
+use zed_extension_api::{
+    serde_json::{self, Value},
+    SlashCommandOutput, Worktree,
+};
+
+use crate::settings::EffectiveSettings;
+use crate::MtlogAnalyzerExtension;
+
+/// Substrings of key names whose values are never printed.
+const SECRET_MARKERS: &[&str] = &["token", "secret", "password", "passwd", "credential", "apikey", "api_key", "private"];
+
+/// Replacement shown for redacted values.
+const REDACTED: &str = "<redacted>";
+
+pub(super) fn run(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<SlashCommandOutput, String> {
+    Ok(super::output("mtlog: effective configuration", text(worktree, extension)?))
+}
+
+/// The effective configuration, also included in `/mtlog-support-bundle`.
+pub(super) fn text(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<String, String> {
+    let effective = super::effective_settings(worktree, extension)?;
+    Ok(render(&effective))
+}
+
+/// Renders the options as JSON with a provenance comment after each top-level key.
+fn render(effective: &EffectiveSettings) -> String {
+    let mut text = String::from("Effective mtlog-lsp initialization options:\n\n```jsonc\n");
+
+    match effective.options.as_object() {
+        Some(options) if !options.is_empty() => {
+            text.push_str("{\n");
+            let last = options.len() - 1;
+            for (i, (key, value)) in options.iter().enumerate() {
+                let comma = if i == last { "" } else { "," };
+                let source = effective
+                    .sources
+                    .get(key)
+                    .map_or_else(|| "unknown".to_string(), ToString::to_string);
+                text.push_str(&format!(
+                    "  {}: {}{} // {}\n",
+                    Value::String(key.clone()),
+                    display_value(key, value),
+                    comma,
+                    source
+                ));
+            }
+            text.push_str("}\n");
+        }
+        _ => {
+            text.push_str(&serde_json::to_string_pretty(&redact(&effective.options)).unwrap_or_default());
+            text.push('\n');
+        }
+    }
+    text.push_str("```\n");
+
+    if !effective.warnings.is_empty() {
+        text.push_str("\nWarnings:\n");
+        for warning in &effective.warnings {
+            text.push_str(&format!("- {warning}\n"));
+        }
+    }
+
+    text
+}
+
+/// `value` of the option `key` as compact JSON, with secrets redacted.
+pub(super) fn display_value(key: &str, value: &Value) -> String {
+    let value = if is_secret(key) {
+        Value::String(REDACTED.to_string())
+    } else {
+        redact(value)
+    };
+    serde_json::to_string(&value).unwrap_or_default()
+}
+
+fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_MARKERS.iter().any(|marker| key.contains(marker))
+}
+
+/// Returns `value` with every secret-looking object entry replaced.
+fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let value = if is_secret(key) {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{self, Source};
+    use zed_extension_api::{serde_json::json, settings::LspSettings};
+
+    /// Snapshot of a configuration mixing explicit settings, defaults, and a warning.
+    #[test]
+    fn test_render_layered_settings() {
+        let effective = EffectiveSettings {
+            options: json!({
+                "disableAll": false,
+                "minConsideredLevel": "Verbose",
+                "severityOverrides": { "MTLOG002": "warning" },
+                "suppressedCodes": ["MTLOG004"]
+            }),
+            sources: [
+                ("disableAll", Source::Default),
+                ("minConsideredLevel", Source::Default),
+                ("severityOverrides", Source::Settings),
+                ("suppressedCodes", Source::Settings),
+            ]
+            .into_iter()
+            .map(|(key, source)| (key.to_string(), source))
+            .collect(),
+            warnings: vec!["ignoring invalid minConsideredLevel \"Trace\"".to_string()],
+        };
+
+        assert_eq!(
+            render(&effective),
+            r#"Effective mtlog-lsp initialization options:
+
+```jsonc
+{
+  "disableAll": false, // default
+  "minConsideredLevel": "Verbose", // default
+  "severityOverrides": {"MTLOG002":"warning"}, // settings
+  "suppressedCodes": ["MTLOG004"] // settings
+}
+```
+
+Warnings:
+- ignoring invalid minConsideredLevel "Trace"
+"#
+        );
+    }
+
+    /// Every shaped option is annotated with a source.
+    #[test]
+    fn test_render_shaped_settings_annotates_every_key() {
+        let lsp_settings = LspSettings {
+            settings: Some(json!({ "strictMode": true })),
+            ..Default::default()
+        };
+        let effective = settings::initialization_options(&lsp_settings);
+
+        let rendered = render(&effective);
+
+        assert!(rendered.contains(r#""strictMode": true, // settings"#));
+        assert!(!rendered.contains("// unknown"));
+        let annotated = rendered.lines().filter(|line| line.contains(" // ")).count();
+        assert_eq!(annotated, effective.sources.len());
+    }
+
+    /// Secret-looking keys are redacted at any depth.
+    #[test]
+    fn test_render_redacts_secrets() {
+        let lsp_settings = LspSettings {
+            initialization_options: Some(json!({
+                "apiToken": "abc123",
+                "upload": { "password": "hunter2", "url": "https://example.com" }
+            })),
+            ..Default::default()
+        };
+
+        let rendered = render(&settings::initialization_options(&lsp_settings));
+
+        assert!(!rendered.contains("abc123"));
+        assert!(!rendered.contains("hunter2"));
+        assert!(rendered.contains(r#""apiToken": "<redacted>", // initialization_options"#));
+        assert!(rendered.contains("https://example.com"));
+    }
+
+    /// Key names that merely resemble secrets, such as commonKeys, are left readable.
+    #[test]
+    fn test_is_secret() {
+        assert!(is_secret("githubToken"));
+        assert!(is_secret("API_KEY"));
+        assert!(!is_secret("commonKeys"));
+        assert!(!is_secret("suppressedCodes"));
+    }
+}
@@ -0,0 +1,214 @@
+//! `/mtlog-doctor`: reports which binary the extension uses, what it said
+//! about its version, and the state of the launch, metrics, and
+//! notification settings.
+
+use zed_extension_api::{serde_json::Value, SlashCommandOutput, Worktree};
+
+use crate::probe::{self, VersionProbe};
+use crate::quiet_hours::{self, QuietHours, QUIET_HOURS_KEY};
+use crate::settings::{CROSS_FILE_ANALYSIS_KEY, ENABLE_METRICS_KEY};
+use crate::MtlogAnalyzerExtension;
+
+/// Everything the doctor reports, gathered before rendering.
+#[derive(Debug)]
+struct Report {
+    /// The binary and how it was chosen, if one was found.
+    binary: Option<(String, String)>,
+    version: Option<VersionProbe>,
+    failed_launches: u32,
+    metrics_enabled: bool,
+    cross_file_analysis: bool,
+    /// The configured quiet hours, and whether they are in effect now when
+    /// the local time could be read.
+    quiet_hours: Option<(QuietHours, Option<bool>)>,
+    warnings: Vec<String>,
+}
+
+pub(super) fn run(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<SlashCommandOutput, String> {
+    Ok(super::output("mtlog: doctor", text(worktree, extension)?))
+}
+
+/// The doctor's report, also included in `/mtlog-support-bundle`.
+pub(super) fn text(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<String, String> {
+    let effective = super::effective_settings(worktree, extension)?;
+
+    let binary = match extension.binary_in_use(worktree.id()) {
+        Some(path) => Some((path.to_string(), "in use".to_string())),
+        None => extension
+            .find_mtlog_lsp(worktree)
+            .map(|candidate| (candidate.path, format!("via {}", candidate.source))),
+    };
+    let version = binary.as_ref().map(|(path, _)| {
+        extension
+            .binary_versions
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| probe::probe(path).version)
+    });
+
+    let report = Report {
+        binary,
+        version,
+        failed_launches: extension
+            .launches
+            .get(&worktree.id())
+            .map_or(0, |tracker| tracker.restarts()),
+        metrics_enabled: effective.options.get(ENABLE_METRICS_KEY).and_then(Value::as_bool).unwrap_or(false),
+        cross_file_analysis: effective
+            .options
+            .get(CROSS_FILE_ANALYSIS_KEY)
+            .and_then(Value::as_bool)
+            .unwrap_or(true),
+        quiet_hours: effective
+            .options
+            .get(QUIET_HOURS_KEY)
+            .and_then(|value| QuietHours::parse(value).ok())
+            .map(|range| (range, quiet_hours::local_minute().map(|minute| range.contains(minute)))),
+        warnings: effective.warnings,
+    };
+
+    Ok(render(&report))
+}
+
+fn render(report: &Report) -> String {
+    let mut text = String::from("mtlog-analyzer doctor\n\n");
+
+    match &report.binary {
+        Some((path, how)) => text.push_str(&format!("Binary:   {path} ({how})\n")),
+        None => text.push_str(
+            "Binary:   not found (searched binary.path, extraBinDirs, extension install, PATH, $GOBIN, \
+             $GOPATH/bin, ~/go/bin)\n",
+        ),
+    }
+    if let Some(version) = &report.version {
+        text.push_str(&format!("Version:  {version}\n"));
+    }
+    text.push_str(&match report.failed_launches {
+        0 => "Launches: no failed launches in a row\n".to_string(),
+        failed => format!("Launches: {failed} failed in a row\n"),
+    });
+
+    // Zed does not pass server notifications to extensions, so timings
+    // reported by the server cannot be shown here.
+    if report.metrics_enabled {
+        text.push_str(&format!(
+            "Metrics:  {ENABLE_METRICS_KEY} is on; mtlog-lsp writes per-pass analysis timings to the \
+             language server log (View → Toggle LSP Log)\n"
+        ));
+    } else {
+        text.push_str(&format!(
+            "Metrics:  {ENABLE_METRICS_KEY} is off; turn it on to have mtlog-lsp report analysis timings\n"
+        ));
+    }
+
+    text.push_str(if report.cross_file_analysis {
+        "Analysis: per-file and cross-file checks
+"
+    } else {
+        "Analysis: per-file checks only; crossFileAnalysis is off
+"
+    });
+
+    if let Some((range, now)) = &report.quiet_hours {
+        let now = match now {
+            Some(true) => "in effect now",
+            Some(false) => "not in effect now",
+            None => "could not read the local time",
+        };
+        text.push_str(&format!("Quiet:    {range}, notifications go to the server log only ({now})\n"));
+    }
+
+    if !report.warnings.is_empty() {
+        text.push_str("\nSettings warnings:\n");
+        for warning in &report.warnings {
+            text.push_str(&format!("- {warning}\n"));
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report {
+            binary: Some(("/usr/bin/mtlog-lsp".to_string(), "via PATH".to_string())),
+            version: Some(VersionProbe::Version("0.9.0".to_string())),
+            failed_launches: 0,
+            metrics_enabled: false,
+            cross_file_analysis: true,
+            quiet_hours: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_healthy() {
+        assert_eq!(
+            render(&report()),
+            "mtlog-analyzer doctor\n\n\
+             Binary:   /usr/bin/mtlog-lsp (via PATH)\n\
+             Version:  version 0.9.0\n\
+             Launches: no failed launches in a row\n\
+             Metrics:  enableMetrics is off; turn it on to have mtlog-lsp report analysis timings\n\
+             Analysis: per-file and cross-file checks\n"
+        );
+    }
+
+    #[test]
+    fn test_render_cross_file_analysis_off() {
+        let rendered = render(&Report {
+            cross_file_analysis: false,
+            ..report()
+        });
+
+        assert!(rendered.contains("Analysis: per-file checks only; crossFileAnalysis is off"));
+    }
+
+    #[test]
+    fn test_render_quiet_hours() {
+        let range = QuietHours::parse(&zed_extension_api::serde_json::json!({ "start": "22:00", "end": "07:00" })).unwrap();
+
+        let quiet = render(&Report {
+            quiet_hours: Some((range, Some(true))),
+            ..report()
+        });
+        assert!(quiet.contains("Quiet:    22:00–07:00, notifications go to the server log only (in effect now)\n"));
+
+        let unknown = render(&Report {
+            quiet_hours: Some((range, None)),
+            ..report()
+        });
+        assert!(unknown.contains("(could not read the local time)"));
+    }
+
+    /// With metrics on, the doctor points at the server log for timings.
+    #[test]
+    fn test_render_metrics_enabled() {
+        let rendered = render(&Report {
+            metrics_enabled: true,
+            ..report()
+        });
+
+        assert!(rendered.contains("enableMetrics is on"));
+        assert!(rendered.contains("Toggle LSP Log"));
+    }
+
+    #[test]
+    fn test_render_missing_binary() {
+        let rendered = render(&Report {
+            binary: None,
+            version: None,
+            failed_launches: 3,
+            warnings: vec!["ignoring invalid enableMetrics \"yes\"".to_string()],
+            ..report()
+        });
+
+        assert!(rendered.contains("Binary:   not found"));
+        assert!(!rendered.contains("Version:"));
+        assert!(rendered.contains("Launches: 3 failed in a row"));
+        assert!(rendered.contains("- ignoring invalid enableMetrics"));
+    }
+}
@@ -0,0 +1,326 @@
+//! `/mtlog-excluded`: lists the project's Go files that `excludeGlobs`,
+//! `ignoreDirs`, `skipVendor`, and `skipGenerated` keep out of analysis.
+//!
+//! mtlog-lsp has no request reporting what it skipped, so the settings are
+//! evaluated here against the files `git ls-files` reports, tracked or not
+//! but not ignored. Projects that are not git repositories cannot be listed.
+
+use std::collections::BTreeMap;
+
+use zed_extension_api::{serde_json::Value, SlashCommandOutput, Worktree};
+
+use crate::settings::{EXCLUDE_GLOBS_KEY, IGNORE_DIRS_KEY, SKIP_GENERATED_KEY, SKIP_VENDOR_KEY};
+use crate::MtlogAnalyzerExtension;
+
+/// Files listed per reason before the rest are only counted.
+const LISTED: usize = 50;
+
+/// Why a file is excluded, in the order the reasons are checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Reason {
+    Vendor,
+    IgnoredDir,
+    Glob,
+    Generated,
+}
+
+impl Reason {
+    fn label(self) -> &'static str {
+        match self {
+            Reason::Vendor => SKIP_VENDOR_KEY,
+            Reason::IgnoredDir => IGNORE_DIRS_KEY,
+            Reason::Glob => EXCLUDE_GLOBS_KEY,
+            Reason::Generated => SKIP_GENERATED_KEY,
+        }
+    }
+}
+
+/// The exclusion settings mtlog-lsp receives.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Exclusions {
+    globs: Vec<String>,
+    ignore_dirs: Vec<String>,
+    skip_vendor: bool,
+    skip_generated: bool,
+}
+
+impl Exclusions {
+    fn from_options(options: &Value) -> Self {
+        let strings = |key: &str| -> Vec<String> {
+            options
+                .get(key)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(|item| item.trim().trim_matches('/').to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        };
+        Self {
+            globs: strings(EXCLUDE_GLOBS_KEY),
+            ignore_dirs: strings(IGNORE_DIRS_KEY),
+            skip_vendor: options.get(SKIP_VENDOR_KEY).and_then(Value::as_bool).unwrap_or(false),
+            skip_generated: options.get(SKIP_GENERATED_KEY).and_then(Value::as_bool).unwrap_or(false),
+        }
+    }
+
+    /// Why `path`, relative to the root with `/` separators, is excluded
+    /// by name alone. Generated files are only known from their contents.
+    fn reason(&self, path: &str) -> Option<Reason> {
+        let dirs: Vec<&str> = path.split('/').collect();
+        let dirs = &dirs[..dirs.len() - 1];
+        if self.skip_vendor && dirs.contains(&"vendor") {
+            return Some(Reason::Vendor);
+        }
+        // A name matches that directory anywhere; a path only from the root
+        let in_ignored_dir = self.ignore_dirs.iter().any(|ignored| match ignored.contains('/') {
+            true => path.starts_with(&format!("{ignored}/")),
+            false => dirs.contains(&ignored.as_str()),
+        });
+        if in_ignored_dir {
+            return Some(Reason::IgnoredDir);
+        }
+        if self.globs.iter().any(|glob| matches_glob(glob, path)) {
+            return Some(Reason::Glob);
+        }
+        None
+    }
+}
+
+pub(super) fn run(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<SlashCommandOutput, String> {
+    let effective = super::effective_settings(worktree, extension)?;
+    let exclusions = Exclusions::from_options(&effective.options);
+    let files = super::list_go_files(worktree)?;
+
+    let mut excluded: BTreeMap<Reason, Vec<&str>> = BTreeMap::new();
+    for file in &files {
+        let reason = exclusions.reason(file).or_else(|| {
+            let generated = exclusions.skip_generated
+                && worktree.read_text_file(file).is_ok_and(|text| is_generated(&text));
+            generated.then_some(Reason::Generated)
+        });
+        if let Some(reason) = reason {
+            excluded.entry(reason).or_default().push(file);
+        }
+    }
+
+    Ok(super::output("mtlog: excluded files", render(files.len(), &excluded)))
+}
+
+/// Whether a Go file carries the standard `// Code generated ... DO NOT EDIT.`
+/// comment before its package clause.
+fn is_generated(text: &str) -> bool {
+    text.lines()
+        .map(str::trim_end)
+        .take_while(|line| !line.starts_with("package "))
+        .any(|line| line.starts_with("// Code generated ") && line.ends_with(" DO NOT EDIT."))
+}
+
+/// Whether `path` matches `glob`. `*` and `?` stay within one path segment,
+/// `**` spans any number of them, and `[...]` and `{a,b}` work as in shells.
+/// A glob without a `/` matches the file name in any directory.
+fn matches_glob(glob: &str, path: &str) -> bool {
+    expand_braces(glob).iter().any(|glob| {
+        let glob = glob.strip_prefix("./").unwrap_or(glob);
+        let pattern: Vec<&str> = match glob.contains('/') {
+            true => glob.split('/').collect(),
+            false => vec!["**", glob],
+        };
+        let segments: Vec<&str> = path.split('/').collect();
+        matches_segments(&pattern, &segments)
+    })
+}
+
+/// Expands the first `{a,b}` group, and recursively the rest.
+fn expand_braces(glob: &str) -> Vec<String> {
+    let Some(open) = glob.find('{') else {
+        return vec![glob.to_string()];
+    };
+    let mut depth = 0;
+    let mut alternatives = Vec::new();
+    let mut start = open + 1;
+    for (i, c) in glob[open..].char_indices().map(|(i, c)| (open + i, c)) {
+        match c {
+            '{' => depth += 1,
+            ',' if depth == 1 => {
+                alternatives.push(&glob[start..i]);
+                start = i + 1;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    alternatives.push(&glob[start..i]);
+                    let (prefix, suffix) = (&glob[..open], &glob[i + 1..]);
+                    return alternatives
+                        .into_iter()
+                        .flat_map(|alternative| expand_braces(&format!("{prefix}{alternative}{suffix}")))
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+    }
+    vec![glob.to_string()]
+}
+
+fn matches_segments(pattern: &[&str], segments: &[&str]) -> bool {
+    match (pattern.first(), segments.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            matches_segments(&pattern[1..], segments)
+                || (!segments.is_empty() && matches_segments(pattern, &segments[1..]))
+        }
+        (Some(glob), Some(segment)) => {
+            matches_segment(&glob.chars().collect::<Vec<_>>(), &segment.chars().collect::<Vec<_>>())
+                && matches_segments(&pattern[1..], &segments[1..])
+        }
+        _ => false,
+    }
+}
+
+fn matches_segment(glob: &[char], name: &[char]) -> bool {
+    match glob.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| matches_segment(&glob[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && matches_segment(&glob[1..], &name[1..]),
+        Some('[') => {
+            let Some(close) = glob.iter().skip(2).position(|&c| c == ']').map(|i| i + 2) else {
+                return name.first() == Some(&'[') && matches_segment(&glob[1..], &name[1..]);
+            };
+            let Some(&c) = name.first() else {
+                return false;
+            };
+            let (negated, class) = match glob[1] {
+                '!' | '^' => (true, &glob[2..close]),
+                _ => (false, &glob[1..close]),
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    found |= (class[i]..=class[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    found |= class[i] == c;
+                    i += 1;
+                }
+            }
+            found != negated && matches_segment(&glob[close + 1..], &name[1..])
+        }
+        Some(&literal) => name.first() == Some(&literal) && matches_segment(&glob[1..], &name[1..]),
+    }
+}
+
+fn render(total: usize, excluded: &BTreeMap<Reason, Vec<&str>>) -> String {
+    let count: usize = excluded.values().map(Vec::len).sum();
+    if count == 0 {
+        return format!("None of the project's {total} Go files are excluded from analysis.\n");
+    }
+
+    let mut text = format!("{count} of the project's {total} Go files are excluded from analysis.\n");
+    for (reason, files) in excluded {
+        text.push_str(&format!("\n### {} ({})\n\n", reason.label(), files.len()));
+        for file in files.iter().take(LISTED) {
+            text.push_str(&format!("- {file}\n"));
+        }
+        if files.len() > LISTED {
+            text.push_str(&format!("- and {} more\n", files.len() - LISTED));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use zed_extension_api::serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_matches_glob() {
+        assert!(matches_glob("*_test.go", "internal/db/db_test.go"));
+        assert!(matches_glob("*_test.go", "main_test.go"));
+        assert!(!matches_glob("*_test.go", "internal/db/db.go"));
+
+        assert!(matches_glob("internal/*.go", "internal/db.go"));
+        assert!(!matches_glob("internal/*.go", "internal/db/db.go"));
+        assert!(matches_glob("internal/**/*.go", "internal/db.go"));
+        assert!(matches_glob("./internal/**/*.go", "internal/db/pg/pool.go"));
+        assert!(matches_glob("**/mocks/**", "pkg/mocks/store.go"));
+        assert!(!matches_glob("**/mocks/**", "pkg/mockstore/store.go"));
+
+        assert!(matches_glob("gen/{api,db}/*.go", "gen/db/models.go"));
+        assert!(!matches_glob("gen/{api,db}/*.go", "gen/web/models.go"));
+        assert!(matches_glob("zz_generated.?.go", "zz_generated.a.go"));
+        assert!(matches_glob("v[0-9]/*.go", "v2/client.go"));
+        assert!(!matches_glob("v[!0-9]/*.go", "v2/client.go"));
+    }
+
+    #[test]
+    fn test_expand_braces() {
+        assert_eq!(expand_braces("a/*.go"), vec!["a/*.go"]);
+        assert_eq!(expand_braces("{a,b}/{x,y}.go"), vec!["a/x.go", "a/y.go", "b/x.go", "b/y.go"]);
+        assert_eq!(expand_braces("{a,{b,c}}.go"), vec!["a.go", "b.go", "c.go"]);
+        assert_eq!(expand_braces("{a,b.go"), vec!["{a,b.go"]);
+    }
+
+    /// Reasons are checked in order, so each file is listed once.
+    #[test]
+    fn test_reason() {
+        let exclusions = Exclusions::from_options(&json!({
+            "excludeGlobs": ["*.pb.go", "", 3],
+            "ignoreDirs": ["testdata", "/tools/gen/"],
+            "skipVendor": true,
+            "skipGenerated": true
+        }));
+
+        assert_eq!(exclusions.globs, vec!["*.pb.go"]);
+        assert_eq!(exclusions.ignore_dirs, vec!["testdata", "tools/gen"]);
+        assert_eq!(exclusions.reason("vendor/github.com/x/y.pb.go"), Some(Reason::Vendor));
+        assert_eq!(exclusions.reason("internal/testdata/a.go"), Some(Reason::IgnoredDir));
+        assert_eq!(exclusions.reason("tools/gen/main.go"), Some(Reason::IgnoredDir));
+        assert_eq!(exclusions.reason("cmd/tools/gen/main.go"), None);
+        assert_eq!(exclusions.reason("api/api.pb.go"), Some(Reason::Glob));
+        assert_eq!(exclusions.reason("testdata.go"), None);
+        assert_eq!(exclusions.reason("vendor.go"), None);
+
+        let defaults = Exclusions::from_options(&json!({}));
+        assert_eq!(defaults.reason("vendor/github.com/x/y.go"), None);
+    }
+
+    #[test]
+    fn test_is_generated() {
+        assert!(is_generated("// Code generated by protoc-gen-go. DO NOT EDIT.\n\npackage api\n"));
+        assert!(is_generated("//go:build linux\n\n// Code generated by stringer; DO NOT EDIT.\npackage x\n"));
+        assert!(!is_generated("package x\n\n// Code generated by hand. DO NOT EDIT.\n"));
+        assert!(!is_generated("// Code generated by protoc-gen-go.\npackage api\n"));
+    }
+
+    #[test]
+    fn test_render() {
+        let excluded = BTreeMap::from([
+            (Reason::Glob, vec!["api/api.pb.go"]),
+            (Reason::Vendor, vec!["vendor/a/a.go", "vendor/b/b.go"]),
+        ]);
+
+        assert_eq!(
+            render(10, &excluded),
+            "3 of the project's 10 Go files are excluded from analysis.\n\n\
+             ### skipVendor (2)\n\n- vendor/a/a.go\n- vendor/b/b.go\n\n\
+             ### excludeGlobs (1)\n\n- api/api.pb.go\n"
+        );
+        assert_eq!(render(4, &BTreeMap::new()), "None of the project's 4 Go files are excluded from analysis.\n");
+    }
+
+    #[test]
+    fn test_render_limits_listing() {
+        let files: Vec<String> = (0..60).map(|i| format!("gen/f{i}.go")).collect();
+        let excluded = BTreeMap::from([(Reason::IgnoredDir, files.iter().map(String::as_str).collect())]);
+
+        let rendered = render(60, &excluded);
+
+        assert_eq!(rendered.matches("- gen/").count(), LISTED);
+        assert!(rendered.ends_with("- and 10 more\n"));
+    }
+}
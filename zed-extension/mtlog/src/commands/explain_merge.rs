@@ -0,0 +1,223 @@
+//! `/mtlog-explain-merge`: shows, layer by layer, how the initialization
+//! options mtlog-lsp receives were built, and which layer set each value.
+//!
+//! The layers are the ones [`super::merge_settings`] applies, in its order:
+//! the built-in defaults, the Zed settings with any profile, the project files
+//! and environment, the session overrides, what the extension sets itself,
+//! and the options matched to the running binary's version.
+
+use zed_extension_api::{
+    serde_json::Value,
+    settings::LspSettings,
+    SlashCommandOutput, Worktree,
+};
+
+use crate::settings::{self, EffectiveSettings};
+use crate::MtlogAnalyzerExtension;
+
+/// Name of the first layer, the options before any setting is read.
+const DEFAULTS: &str = "Built-in defaults";
+
+pub(super) fn run(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<SlashCommandOutput, String> {
+    let lsp_settings = LspSettings::for_worktree(settings::LANGUAGE_SERVER_NAME, worktree)?;
+    let mut layers = vec![(DEFAULTS, settings::initialization_options(&LspSettings::default()))];
+    super::merge_settings(Ok(lsp_settings), worktree, extension, |name, effective| {
+        layers.push((name, effective.clone()))
+    });
+
+    Ok(super::output("mtlog: settings merge", render(&layers)))
+}
+
+/// One change a layer made to a top-level option.
+#[derive(Debug, PartialEq, Eq)]
+enum Change<'a> {
+    Added(&'a str, &'a Value),
+    Changed(&'a str, &'a Value, &'a Value),
+    Removed(&'a str, &'a Value),
+}
+
+/// The changes from `before` to `after`, in the order of `after`'s keys,
+/// followed by the keys `after` no longer has.
+fn changes<'a>(before: &'a Value, after: &'a Value) -> Vec<Change<'a>> {
+    let (Some(before), Some(after)) = (before.as_object(), after.as_object()) else {
+        return Vec::new();
+    };
+
+    let mut changes: Vec<Change> = after
+        .iter()
+        .filter_map(|(key, value)| match before.get(key) {
+            None => Some(Change::Added(key, value)),
+            Some(old) if old != value => Some(Change::Changed(key, old, value)),
+            Some(_) => None,
+        })
+        .collect();
+    changes.extend(
+        before
+            .iter()
+            .filter(|(key, _)| !after.contains_key(*key))
+            .map(|(key, value)| Change::Removed(key, value)),
+    );
+    changes
+}
+
+/// Renders each layer's contribution, then the final value of every option.
+fn render(layers: &[(&str, EffectiveSettings)]) -> String {
+    let mut text = String::from("How the mtlog-lsp initialization options were built, in merge order:\n\n");
+
+    let mut previous: Option<&EffectiveSettings> = None;
+    for (i, (name, effective)) in layers.iter().enumerate() {
+        let Some(before) = previous else {
+            let count = effective.options.as_object().map_or(0, |options| options.len());
+            text.push_str(&format!("{}. {name}: {count} options\n", i + 1));
+            previous = Some(effective);
+            continue;
+        };
+
+        let changes = changes(&before.options, &effective.options);
+        let new_warnings = &effective.warnings[before.warnings.len().min(effective.warnings.len())..];
+        if changes.is_empty() {
+            text.push_str(&format!("{}. {name}: no changes\n", i + 1));
+        } else {
+            text.push_str(&format!("{}. {name}:\n", i + 1));
+        }
+        for change in changes {
+            let source =
+                |key: &str| effective.sources.get(key).map_or_else(|| "unknown".to_string(), ToString::to_string);
+            let line = match change {
+                Change::Added(key, value) => {
+                    format!("`{key}` = {} ({})", super::config::display_value(key, value), source(key))
+                }
+                Change::Changed(key, old, new) => format!(
+                    "`{key}`: {} → {} ({})",
+                    super::config::display_value(key, old),
+                    super::config::display_value(key, new),
+                    source(key)
+                ),
+                Change::Removed(key, _) => format!("`{key}` removed"),
+            };
+            text.push_str(&format!("   - {line}\n"));
+        }
+        for warning in new_warnings {
+            text.push_str(&format!("   - warning: {warning}\n"));
+        }
+        previous = Some(effective);
+    }
+
+    if let Some((_, last)) = layers.last() {
+        text.push_str("\nFinal values:\n\n| Option | Value | Set by |\n|--------|-------|--------|\n");
+        for (key, value) in last.options.as_object().into_iter().flatten() {
+            let source = last.sources.get(key).map_or_else(|| "unknown".to_string(), ToString::to_string);
+            text.push_str(&format!("| `{key}` | `{}` | {source} |\n", super::config::display_value(key, value)));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache;
+    use crate::probe::VersionProbe;
+    use crate::session::SessionOverrides;
+    use zed_extension_api::serde_json::json;
+
+    fn with_settings(value: Value) -> LspSettings {
+        LspSettings {
+            settings: Some(value),
+            ..Default::default()
+        }
+    }
+
+    /// Each layer, in merge order, lists what it changed and with which source.
+    #[test]
+    fn test_render_layers_in_merge_order() {
+        let defaults = settings::initialization_options(&LspSettings::default());
+        let mut effective = settings::initialization_options(&with_settings(json!({
+            "profile": "strict",
+            "suppressedCodes": ["MTLOG004"],
+        })));
+        let zed_settings = effective.clone();
+        cache::apply(Ok(Some("/mnt/scratch/mtlog".to_string())), &mut effective);
+        let project = effective.clone();
+        let mut session = SessionOverrides::default();
+        session.set_profile("none").unwrap();
+        session.apply(&mut effective);
+        let overridden = effective.clone();
+        settings::set_protocol_version(&mut effective);
+        let extension_set = effective.clone();
+        settings::set_docs_version(&mut effective, &VersionProbe::Version("0.11.2".to_string()));
+
+        let rendered = render(&[
+            (DEFAULTS, defaults),
+            ("Zed settings", zed_settings),
+            ("Project files and environment", project),
+            ("Session overrides", overridden),
+            ("Set by the extension", extension_set),
+            ("Matched to the binary", effective),
+        ]);
+
+        let steps: Vec<&str> = rendered.lines().filter(|line| line.starts_with(char::is_numeric)).collect();
+        assert_eq!(steps.len(), 6);
+        assert!(steps[0].starts_with("1. Built-in defaults: "));
+        assert_eq!(steps[1], "2. Zed settings:");
+        assert_eq!(steps[2], "3. Project files and environment:");
+        assert_eq!(steps[3], "4. Session overrides:");
+        assert_eq!(steps[4], "5. Set by the extension:");
+        assert_eq!(steps[5], "6. Matched to the binary:");
+
+        let step = |n: usize| {
+            let start = rendered.find(steps[n - 1]).unwrap();
+            let end = steps
+                .get(n)
+                .map_or(rendered.find("\nFinal values:").unwrap(), |next| rendered.find(next).unwrap());
+            &rendered[start..end]
+        };
+        assert!(step(2).contains("   - `strictMode`: false → true (profile)\n"));
+        assert!(step(2).contains("   - `suppressedCodes`: [] → [\"MTLOG004\"] (settings)\n"));
+        assert!(step(3).contains("   - `cacheDir` = \"/mnt/scratch/mtlog\" (settings)\n"));
+        assert!(step(4).contains("   - `strictMode`: true → false (session)\n"));
+        assert!(step(4).contains("   - `samplingChecks`: true → false (session)\n"));
+        assert!(step(5).contains("   - `protocolVersion` = 1 (default)\n"));
+        assert!(step(6).contains("   - `docsVersion` = \"0.11\" (default)\n"));
+
+        assert!(rendered.contains("| `strictMode` | `false` | session |\n"));
+        assert!(rendered.contains("| `samplingChecks` | `false` | session |\n"));
+        assert!(rendered.contains("| `disableAll` | `false` | default |\n"));
+    }
+
+    #[test]
+    fn test_render_unchanged_and_removed() {
+        let defaults = settings::initialization_options(&LspSettings::default());
+        let forwarded = settings::initialization_options(&LspSettings {
+            initialization_options: Some(json!({ "strictMode": true, "apiToken": "hunter2" })),
+            ..Default::default()
+        });
+
+        let rendered = render(&[
+            (DEFAULTS, defaults),
+            ("Zed settings", forwarded.clone()),
+            ("Session overrides", forwarded),
+        ]);
+
+        assert!(rendered.contains("   - `disableAll` removed\n"));
+        assert!(rendered.contains("3. Session overrides: no changes\n"));
+        assert!(rendered.contains("| `strictMode` | `true` | initialization_options |\n"));
+        assert!(rendered.contains("   - `apiToken` = \"<redacted>\" (initialization_options)\n"));
+        assert!(!rendered.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_changes() {
+        let before = json!({ "a": 1, "b": 2, "c": 3 });
+        let after = json!({ "a": 1, "b": 5, "d": 4 });
+
+        assert_eq!(
+            changes(&before, &after),
+            vec![
+                Change::Changed("b", &json!(2), &json!(5)),
+                Change::Added("d", &json!(4)),
+                Change::Removed("c", &json!(3)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! `/mtlog-goenv`: shows the Go variables that binary discovery and the
+//! `go install` fallback depend on, both as the shell environment has them
+//! and as `go env` resolves them.
+
+use std::collections::{BTreeMap, HashMap};
+
+use zed_extension_api::{process::Command, serde_json, SlashCommandOutput, Worktree};
+
+use crate::decode::decode_output;
+
+/// The variables reported, in display order.
+const GO_VARIABLES: &[&str] = &["GOBIN", "GOPATH", "GOROOT", "GOOS", "GOARCH", "GOTOOLCHAIN"];
+
+pub(super) fn run(worktree: &Worktree) -> Result<SlashCommandOutput, String> {
+    Ok(super::output("mtlog: Go environment", text(worktree)))
+}
+
+/// The Go environment table, also included in `/mtlog-support-bundle`.
+pub(super) fn text(worktree: &Worktree) -> String {
+    let shell_env: HashMap<String, String> = worktree.shell_env().into_iter().collect();
+    let go_env = match worktree.which(super::GO) {
+        Some(_) => go_env(worktree),
+        None => Err("go is not on PATH".to_string()),
+    };
+
+    render(&shell_env, &go_env)
+}
+
+/// Runs `go env -json` for [`GO_VARIABLES`].
+fn go_env(worktree: &Worktree) -> Result<BTreeMap<String, String>, String> {
+    let output = Command::new(super::GO)
+        .arg("env")
+        .arg("-json")
+        .args(GO_VARIABLES.iter().copied())
+        .envs(worktree.shell_env())
+        .output()?;
+    if output.status != Some(0) {
+        let stderr = decode_output(&output.stderr, "go env");
+        return Err(format!("go env failed: {}", stderr.trim()));
+    }
+    parse_go_env(&decode_output(&output.stdout, "go env"))
+}
+
+/// Parses the `{"NAME": "value"}` object `go env -json` prints.
+fn parse_go_env(output: &str) -> Result<BTreeMap<String, String>, String> {
+    serde_json::from_str(output).map_err(|err| format!("unexpected go env output: {err}"))
+}
+
+fn render(shell_env: &HashMap<String, String>, go_env: &Result<BTreeMap<String, String>, String>) -> String {
+    let mut text = String::from("mtlog-analyzer Go environment\n\n");
+    text.push_str("| Variable | Shell environment | go env |\n|----------|-------------------|--------|\n");
+
+    let cell = |value: Option<&String>| match value.map(String::as_str) {
+        Some("") | None => "(unset)".to_string(),
+        Some(value) => format!("`{value}`"),
+    };
+    for name in GO_VARIABLES {
+        let resolved = match go_env {
+            Ok(go_env) => cell(go_env.get(*name)),
+            Err(_) => "-".to_string(),
+        };
+        text.push_str(&format!("| {name} | {} | {resolved} |\n", cell(shell_env.get(*name))));
+    }
+
+    if let Err(err) = go_env {
+        text.push_str(&format!("\nCould not run go env: {err}\n"));
+    }
+    text.push_str(
+        "\nDiscovery reads the shell environment; go env also shows Go's own defaults, such as a GOPATH of ~/go.\n",
+    );
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GO_ENV_OUTPUT: &str = r#"{
+	"GOARCH": "arm64",
+	"GOBIN": "",
+	"GOOS": "darwin",
+	"GOPATH": "/Users/dev/go",
+	"GOROOT": "/opt/homebrew/Cellar/go/1.23.2/libexec",
+	"GOTOOLCHAIN": "auto"
+}
+"#;
+
+    fn shell_env() -> HashMap<String, String> {
+        [("GOPATH", "/Users/dev/src/go"), ("HOME", "/Users/dev")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_go_env() {
+        let go_env = parse_go_env(GO_ENV_OUTPUT).unwrap();
+
+        assert_eq!(go_env["GOOS"], "darwin");
+        assert_eq!(go_env["GOBIN"], "");
+        assert!(parse_go_env("go: unknown flag -json").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = render(&shell_env(), &parse_go_env(GO_ENV_OUTPUT));
+
+        assert!(rendered.contains("| GOBIN | (unset) | (unset) |\n"));
+        assert!(rendered.contains("| GOPATH | `/Users/dev/src/go` | `/Users/dev/go` |\n"));
+        assert!(rendered.contains("| GOROOT | (unset) | `/opt/homebrew/Cellar/go/1.23.2/libexec` |\n"));
+        assert!(rendered.contains("| GOTOOLCHAIN | (unset) | `auto` |\n"));
+        assert!(!rendered.contains("Could not run go env"));
+    }
+
+    /// Without go, the shell environment is still reported.
+    #[test]
+    fn test_render_without_go() {
+        let rendered = render(&shell_env(), &Err("go is not on PATH".to_string()));
+
+        assert!(rendered.contains("| GOPATH | `/Users/dev/src/go` | - |\n"));
+        assert!(rendered.contains("Could not run go env: go is not on PATH"));
+    }
+}
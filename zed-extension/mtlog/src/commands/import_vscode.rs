@@ -0,0 +1,344 @@
+//! `/mtlog-import-vscode [path] [--apply]`: converts the VS Code mtlog
+//! extension's settings into an mtlog-analyzer block for `.zed/settings.json`.
+
+use zed_extension_api::{
+    serde_json::{self, Map, Value},
+    SlashCommandOutput, Worktree,
+};
+
+use crate::jsonc;
+
+/// Workspace settings file read when no path is given.
+const DEFAULT_VSCODE_SETTINGS: &str = ".vscode/settings.json";
+
+/// Project settings file written by `--apply`.
+const ZED_SETTINGS: &str = ".zed/settings.json";
+
+/// Prefix of the VS Code extension's settings.
+const VSCODE_PREFIX: &str = "mtlog";
+
+/// How a VS Code setting carries over to the Zed settings.
+enum Mapping {
+    /// Copied unchanged to the named Zed setting.
+    Rename(&'static str),
+    /// A boolean copied to the named Zed setting with its meaning inverted.
+    Inverted(&'static str),
+    /// Analyzer command-line flags, translated where a setting exists.
+    AnalyzerFlags,
+    /// No Zed equivalent, for the given reason.
+    Unsupported(&'static str),
+}
+
+/// VS Code setting names (without the `mtlog.` prefix) and their Zed mapping.
+const MAPPINGS: &[(&str, Mapping)] = &[
+    ("suppressedDiagnostics", Mapping::Rename("suppressedCodes")),
+    ("suppressedCodes", Mapping::Rename("suppressedCodes")),
+    ("severityOverrides", Mapping::Rename("severityOverrides")),
+    ("disableAll", Mapping::Rename("disableAll")),
+    ("commonKeys", Mapping::Rename("commonKeys")),
+    ("strictMode", Mapping::Rename("strictMode")),
+    ("ignoreDynamicTemplates", Mapping::Rename("ignoreDynamicTemplates")),
+    ("diagnosticsEnabled", Mapping::Inverted("disableAll")),
+    ("analyzerFlags", Mapping::AnalyzerFlags),
+    (
+        "analyzerPath",
+        Mapping::Unsupported("Zed runs mtlog-lsp; set lsp.mtlog-analyzer.binary.path instead"),
+    ),
+    (
+        "maxConcurrentAnalyses",
+        Mapping::Unsupported("mtlog-lsp schedules analysis itself"),
+    ),
+];
+
+/// The result of converting VS Code settings.
+#[derive(Debug, Default, PartialEq)]
+struct Conversion {
+    /// Settings for the `lsp.mtlog-analyzer.settings` block.
+    settings: Map<String, Value>,
+    /// VS Code settings (or flags) that were not carried over, with the reason.
+    not_mapped: Vec<String>,
+}
+
+pub(super) fn run(args: &[String], worktree: &Worktree) -> Result<SlashCommandOutput, String> {
+    let (path, apply) = parse_args(args)?;
+
+    let text = worktree
+        .read_text_file(path)
+        .or_else(|_| std::fs::read_to_string(path))
+        .map_err(|err| format!("could not read {path}: {err}"))?;
+    let vscode_settings = jsonc::parse(&text).map_err(|err| format!("could not parse {path}: {err}"))?;
+
+    let conversion = convert(&vscode_settings);
+    let block = zed_settings_block(&conversion.settings);
+
+    let note = if apply {
+        Some(apply_block(worktree, &block))
+    } else {
+        None
+    };
+
+    Ok(super::output(
+        "mtlog: VS Code settings import",
+        render(path, &conversion, &block, note.as_deref()),
+    ))
+}
+
+/// Splits the arguments into the settings path and whether `--apply` was given.
+fn parse_args(args: &[String]) -> Result<(&str, bool), String> {
+    let mut path = None;
+    let mut apply = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--apply" => apply = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option: {flag}")),
+            _ if path.is_some() => return Err("expected at most one settings path".to_string()),
+            given => path = Some(given),
+        }
+    }
+
+    Ok((path.unwrap_or(DEFAULT_VSCODE_SETTINGS), apply))
+}
+
+/// Maps the `mtlog.`-prefixed VS Code settings onto the Zed settings.
+fn convert(vscode_settings: &Value) -> Conversion {
+    let mut conversion = Conversion::default();
+
+    for (name, value) in mtlog_entries(vscode_settings) {
+        let mapping = MAPPINGS.iter().find(|(key, _)| *key == name).map(|(_, m)| m);
+        match mapping {
+            Some(Mapping::Rename(key)) => {
+                conversion.settings.insert(key.to_string(), value.clone());
+            }
+            Some(Mapping::Inverted(key)) => match value.as_bool() {
+                Some(enabled) => {
+                    conversion.settings.insert(key.to_string(), Value::Bool(!enabled));
+                }
+                None => conversion
+                    .not_mapped
+                    .push(format!("{VSCODE_PREFIX}.{name}: expected true or false")),
+            },
+            Some(Mapping::AnalyzerFlags) => convert_flags(value, &mut conversion),
+            Some(Mapping::Unsupported(reason)) => {
+                conversion.not_mapped.push(format!("{VSCODE_PREFIX}.{name}: {reason}"));
+            }
+            None => conversion
+                .not_mapped
+                .push(format!("{VSCODE_PREFIX}.{name}: no Zed equivalent")),
+        }
+    }
+
+    conversion
+}
+
+/// Returns the mtlog settings, whether written as flat `mtlog.name` keys or
+/// nested under an `mtlog` object.
+fn mtlog_entries(vscode_settings: &Value) -> Vec<(&str, &Value)> {
+    let Some(object) = vscode_settings.as_object() else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    for (key, value) in object {
+        if key == VSCODE_PREFIX {
+            if let Some(nested) = value.as_object() {
+                entries.extend(nested.iter().map(|(name, value)| (name.as_str(), value)));
+            }
+        } else if let Some(name) = key.strip_prefix("mtlog.") {
+            entries.push((name, value));
+        }
+    }
+    entries
+}
+
+/// Translates `mtlog.analyzerFlags` into the matching settings.
+fn convert_flags(value: &Value, conversion: &mut Conversion) {
+    let Some(flags) = value.as_array() else {
+        conversion
+            .not_mapped
+            .push(format!("{VSCODE_PREFIX}.analyzerFlags: expected an array of flags"));
+        return;
+    };
+
+    for flag in flags {
+        let Some(flag) = flag.as_str() else {
+            continue;
+        };
+        let (name, argument) = match flag.trim_start_matches('-').split_once('=') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (flag.trim_start_matches('-'), None),
+        };
+
+        let setting = match (name, argument) {
+            ("strict", None) => Some(("strictMode", Value::Bool(true))),
+            ("ignore-dynamic-templates", None) => Some(("ignoreDynamicTemplates", Value::Bool(true))),
+            ("common-keys", Some(keys)) => Some((
+                "commonKeys",
+                keys.split(',')
+                    .map(str::trim)
+                    .filter(|key| !key.is_empty())
+                    .map(|key| Value::String(key.to_string()))
+                    .collect(),
+            )),
+            _ => None,
+        };
+
+        match setting {
+            Some((key, value)) => {
+                conversion.settings.insert(key.to_string(), value);
+            }
+            None => conversion
+                .not_mapped
+                .push(format!("{VSCODE_PREFIX}.analyzerFlags: {flag} has no equivalent setting")),
+        }
+    }
+}
+
+/// Wraps the converted settings in the `.zed/settings.json` structure.
+fn zed_settings_block(settings: &Map<String, Value>) -> String {
+    let block = serde_json::json!({
+        "lsp": {
+            "mtlog-analyzer": {
+                "settings": settings
+            }
+        }
+    });
+    serde_json::to_string_pretty(&block).unwrap_or_default()
+}
+
+/// Writes `block` to the project's Zed settings unless that file already
+/// exists, returning a note describing what happened.
+fn apply_block(worktree: &Worktree, block: &str) -> String {
+    if worktree.read_text_file(ZED_SETTINGS).is_ok() {
+        return format!("{ZED_SETTINGS} already exists and was not changed; merge the block above into it.");
+    }
+
+    let dir = format!("{}/.zed", worktree.root_path());
+    let written = std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(format!("{dir}/settings.json"), format!("{block}\n")));
+    match written {
+        Ok(()) => format!("Wrote {ZED_SETTINGS}."),
+        Err(err) => format!("Could not write {ZED_SETTINGS} ({err}); copy the block above into it instead."),
+    }
+}
+
+fn render(source: &str, conversion: &Conversion, block: &str, note: Option<&str>) -> String {
+    let mut text = format!("Converted mtlog settings from {source}:\n\n```json\n{block}\n```\n");
+
+    if let Some(note) = note {
+        text.push_str(&format!("\n{note}\n"));
+    }
+
+    if !conversion.not_mapped.is_empty() {
+        text.push_str("\nNot mapped:\n");
+        for entry in &conversion.not_mapped {
+            text.push_str(&format!("- {entry}\n"));
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    /// Each entry in the mapping table lands on its Zed setting.
+    #[test]
+    fn test_convert_mapping_table() {
+        let conversion = convert(&json!({
+            "mtlog.suppressedDiagnostics": ["MTLOG001"],
+            "mtlog.severityOverrides": { "MTLOG002": "warning" },
+            "mtlog.commonKeys": ["tenant_id"],
+            "mtlog.strictMode": true,
+            "mtlog.ignoreDynamicTemplates": true,
+            "mtlog.diagnosticsEnabled": false,
+            "editor.tabSize": 4
+        }));
+
+        assert_eq!(
+            Value::Object(conversion.settings),
+            json!({
+                "suppressedCodes": ["MTLOG001"],
+                "severityOverrides": { "MTLOG002": "warning" },
+                "commonKeys": ["tenant_id"],
+                "strictMode": true,
+                "ignoreDynamicTemplates": true,
+                "disableAll": true
+            })
+        );
+        assert!(conversion.not_mapped.is_empty());
+    }
+
+    /// Settings nested under an "mtlog" object are read like flat keys.
+    #[test]
+    fn test_convert_nested_settings() {
+        let conversion = convert(&json!({ "mtlog": { "suppressedDiagnostics": ["MTLOG003"] } }));
+
+        assert_eq!(conversion.settings["suppressedCodes"], json!(["MTLOG003"]));
+    }
+
+    /// Analyzer flags with a setting are translated; others are listed.
+    #[test]
+    fn test_convert_analyzer_flags() {
+        let conversion = convert(&json!({
+            "mtlog.analyzerFlags": ["-strict", "-common-keys=tenant_id, org_id", "-downgrade-errors"]
+        }));
+
+        assert_eq!(conversion.settings["strictMode"], json!(true));
+        assert_eq!(conversion.settings["commonKeys"], json!(["tenant_id", "org_id"]));
+        assert_eq!(conversion.not_mapped.len(), 1);
+        assert!(conversion.not_mapped[0].contains("-downgrade-errors"));
+    }
+
+    /// Unknown and unsupported keys are reported rather than dropped.
+    #[test]
+    fn test_convert_lists_unmapped_keys() {
+        let conversion = convert(&json!({
+            "mtlog.analyzerPath": "/usr/local/bin/mtlog-analyzer",
+            "mtlog.somethingNew": 1
+        }));
+
+        assert!(conversion.settings.is_empty());
+        assert_eq!(conversion.not_mapped.len(), 2);
+        assert!(conversion.not_mapped[0].starts_with("mtlog.analyzerPath"));
+        assert!(conversion.not_mapped[1].starts_with("mtlog.somethingNew"));
+    }
+
+    /// The rendered block is valid Zed settings JSON with the unmapped list after it.
+    #[test]
+    fn test_render() {
+        let conversion = convert(&json!({
+            "mtlog.suppressedDiagnostics": ["MTLOG001"],
+            "mtlog.maxConcurrentAnalyses": 4
+        }));
+        let block = zed_settings_block(&conversion.settings);
+
+        let parsed: Value = serde_json::from_str(&block).unwrap();
+        assert_eq!(
+            parsed,
+            json!({ "lsp": { "mtlog-analyzer": { "settings": { "suppressedCodes": ["MTLOG001"] } } } })
+        );
+
+        let text = render(DEFAULT_VSCODE_SETTINGS, &conversion, &block, None);
+        assert!(text.contains("```json"));
+        assert!(text.contains("Not mapped:\n- mtlog.maxConcurrentAnalyses"));
+    }
+
+    /// The path defaults to the workspace settings and --apply can appear anywhere.
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&[]).unwrap(), (DEFAULT_VSCODE_SETTINGS, false));
+        assert_eq!(
+            parse_args(&args(&["--apply", "/home/me/.config/Code/User/settings.json"])).unwrap(),
+            ("/home/me/.config/Code/User/settings.json", true)
+        );
+        assert!(parse_args(&args(&["--force"])).is_err());
+        assert!(parse_args(&args(&["a.json", "b.json"])).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! `/mtlog-init-config`: writes a commented starter `.zed/settings.json` with
+//! an mtlog-analyzer block, or shows it as a merge suggestion when the
+//! project already has that file.
+
+use zed_extension_api::{SlashCommandOutput, Worktree};
+
+use crate::jsonc;
+
+/// Project settings file the starter config is written to.
+const ZED_SETTINGS: &str = ".zed/settings.json";
+
+/// The starter settings, at the defaults mtlog-lsp assumes.
+const STARTER_CONFIG: &str = r#"// Project settings for Zed: https://zed.dev/docs/configuring-zed
+{
+  "lsp": {
+    "mtlog-analyzer": {
+      "settings": {
+        // Diagnostic codes to hide, such as "MTLOG004".
+        "suppressedCodes": [],
+        // Severity per code, such as { "MTLOG002": "warning" }.
+        "severityOverrides": {},
+        // Context keys your code uses often, suggested as constants.
+        "commonKeys": [],
+        // Validate format specifiers strictly.
+        "strictMode": false,
+        // Skip log calls below this level.
+        "minConsideredLevel": "Verbose",
+        // When to re-analyze: "onType", "onSave", or "manual" (/mtlog-analyze).
+        "analysisTiming": "onType",
+        // Your own functions that wrap mtlog calls, such as "logx.Infof".
+        "loggerWrappers": []
+      }
+    }
+  }
+}
+"#;
+
+/// What to do with the starter config, given the existing settings file.
+#[derive(Debug, PartialEq, Eq)]
+enum Plan {
+    /// No settings file yet, so the starter config is written.
+    Write,
+    /// A settings file exists and is left alone; `configured` tells whether
+    /// it already has an mtlog-analyzer block.
+    Merge { configured: bool },
+}
+
+pub(super) fn run(worktree: &Worktree) -> Result<SlashCommandOutput, String> {
+    let existing = worktree.read_text_file(ZED_SETTINGS).ok();
+    let note = match plan(existing.as_deref()) {
+        Plan::Write => write(worktree),
+        Plan::Merge { configured: true } => format!(
+            "{ZED_SETTINGS} already configures mtlog-analyzer and was not changed; \
+             compare its lsp.mtlog-analyzer block with the one above."
+        ),
+        Plan::Merge { configured: false } => format!(
+            "{ZED_SETTINGS} already exists and was not changed; add the \"mtlog-analyzer\" entry above \
+             under its \"lsp\" key."
+        ),
+    };
+
+    Ok(super::output(
+        "mtlog: starter settings",
+        format!("```jsonc\n{STARTER_CONFIG}```\n\n{note}\n"),
+    ))
+}
+
+/// Never overwrites: an existing file, even one that does not parse, only
+/// gets a merge suggestion.
+fn plan(existing: Option<&str>) -> Plan {
+    let Some(text) = existing else {
+        return Plan::Write;
+    };
+    let configured = jsonc::parse(text)
+        .ok()
+        .is_some_and(|settings| settings.pointer("/lsp/mtlog-analyzer").is_some());
+    Plan::Merge { configured }
+}
+
+fn write(worktree: &Worktree) -> String {
+    let dir = format!("{}/.zed", worktree.root_path());
+    let written = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(format!("{dir}/settings.json"), STARTER_CONFIG));
+    match written {
+        Ok(()) => format!("Wrote {ZED_SETTINGS}."),
+        Err(err) => format!("Could not write {ZED_SETTINGS} ({err}); save the settings above to it instead."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zed_extension_api::settings::LspSettings;
+
+    use super::*;
+    use crate::settings;
+
+    /// The starter config parses, and every setting in it is forwarded without a warning.
+    #[test]
+    fn test_starter_config_is_valid() {
+        let config = jsonc::parse(STARTER_CONFIG).unwrap();
+        let block = config.pointer("/lsp/mtlog-analyzer/settings").unwrap();
+
+        let shaped = settings::initialization_options(&LspSettings {
+            settings: Some(block.clone()),
+            ..Default::default()
+        });
+
+        assert!(shaped.warnings.is_empty(), "{:?}", shaped.warnings);
+        for (key, value) in block.as_object().unwrap() {
+            assert_eq!(&shaped.options[key], value, "{key}");
+        }
+    }
+
+    #[test]
+    fn test_plan_writes_only_without_a_file() {
+        assert_eq!(plan(None), Plan::Write);
+        assert_eq!(plan(Some("{ \"tab_size\": 4 }")), Plan::Merge { configured: false });
+        assert_eq!(plan(Some("")), Plan::Merge { configured: false });
+        assert_eq!(plan(Some("{ not json")), Plan::Merge { configured: false });
+    }
+
+    #[test]
+    fn test_plan_detects_existing_block() {
+        let existing = r#"{
+  // already set up
+  "lsp": { "mtlog-analyzer": { "settings": { "strictMode": true } } },
+}"#;
+
+        assert_eq!(plan(Some(existing)), Plan::Merge { configured: true });
+    }
+}
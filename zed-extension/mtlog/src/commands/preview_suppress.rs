@@ -0,0 +1,161 @@
+//! `/mtlog-preview-suppress <code>`: analyzes the project and reports how
+//! many of the current diagnostics adding `code` to `suppressedCodes` would
+//! hide.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use zed_extension_api::{SlashCommandArgumentCompletion, SlashCommandOutput, Worktree};
+
+use crate::history::AnalysisHistory;
+use crate::report::{Diagnostic, RULES};
+
+pub(super) fn run(
+    args: &[String],
+    worktree: &Worktree,
+    history: &Mutex<AnalysisHistory>,
+) -> Result<SlashCommandOutput, String> {
+    let (code, description) = match args {
+        [code] => parse_code(code)?,
+        _ => return Err("usage: /mtlog-preview-suppress <code>".to_string()),
+    };
+
+    let diagnostics = super::analyze_project("mtlog-preview-suppress", worktree, history)?;
+    let preview = count(&diagnostics, code);
+
+    Ok(super::output("mtlog: suppression preview", render(code, description, &preview)))
+}
+
+/// Diagnostic codes matching the typed prefix, each run on selection.
+pub(super) fn complete(args: &[String]) -> Vec<SlashCommandArgumentCompletion> {
+    let typed = args.first().map(String::as_str).unwrap_or("");
+    RULES
+        .iter()
+        .filter(|(code, _)| code.to_ascii_lowercase().starts_with(&typed.to_ascii_lowercase()))
+        .map(|(code, description)| SlashCommandArgumentCompletion {
+            label: format!("{code}: {description}"),
+            new_text: code.to_string(),
+            run_command: true,
+        })
+        .collect()
+}
+
+/// Looks `code` up among the analyzer's codes, ignoring case.
+fn parse_code(code: &str) -> Result<(&'static str, &'static str), String> {
+    RULES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code.trim()))
+        .copied()
+        .ok_or_else(|| format!("unknown diagnostic code \"{code}\"; expected MTLOG001 to MTLOG{:03}", RULES.len()))
+}
+
+/// What suppressing a code would hide.
+#[derive(Debug, PartialEq, Eq)]
+struct Preview {
+    /// Diagnostics with the code.
+    hidden: usize,
+    /// Files those diagnostics are in.
+    files: usize,
+    /// All current diagnostics.
+    total: usize,
+}
+
+fn count(diagnostics: &[Diagnostic], code: &str) -> Preview {
+    let matching: Vec<&Diagnostic> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.code.as_deref() == Some(code))
+        .collect();
+    Preview {
+        hidden: matching.len(),
+        files: matching
+            .iter()
+            .map(|diagnostic| diagnostic.file.as_str())
+            .collect::<BTreeSet<_>>()
+            .len(),
+        total: diagnostics.len(),
+    }
+}
+
+fn render(code: &str, description: &str, preview: &Preview) -> String {
+    if preview.hidden == 0 {
+        return format!(
+            "Suppressing {code} ({description}) would hide none of the {} current diagnostics.\n",
+            preview.total
+        );
+    }
+    let plural = |n: usize, word: &str| if n == 1 { format!("{n} {word}") } else { format!("{n} {word}s") };
+    format!(
+        "Suppressing {code} ({description}) would hide {} of {} current diagnostics, in {}.\n\n\
+         To suppress it, add \"{code}\" to suppressedCodes in the mtlog-analyzer settings.\n",
+        preview.hidden,
+        preview.total,
+        plural(preview.files, "file")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Severity;
+
+    fn diagnostic(file: &str, code: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            file: file.to_string(),
+            line: 1,
+            column: 1,
+            code: code.map(str::to_string),
+            severity: Severity::Warning,
+            message: "finding".to_string(),
+        }
+    }
+
+    fn diagnostics() -> Vec<Diagnostic> {
+        vec![
+            diagnostic("/work/a.go", Some("MTLOG003")),
+            diagnostic("/work/a.go", Some("MTLOG003")),
+            diagnostic("/work/b.go", Some("MTLOG003")),
+            diagnostic("/work/b.go", Some("MTLOG004")),
+            diagnostic("/work/c.go", None),
+        ]
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(count(&diagnostics(), "MTLOG003"), Preview { hidden: 3, files: 2, total: 5 });
+        assert_eq!(count(&diagnostics(), "MTLOG004"), Preview { hidden: 1, files: 1, total: 5 });
+        assert_eq!(count(&diagnostics(), "MTLOG009"), Preview { hidden: 0, files: 0, total: 5 });
+        assert_eq!(count(&[], "MTLOG003"), Preview { hidden: 0, files: 0, total: 0 });
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = render("MTLOG003", "Duplicate property names", &count(&diagnostics(), "MTLOG003"));
+        assert!(rendered.starts_with(
+            "Suppressing MTLOG003 (Duplicate property names) would hide 3 of 5 current diagnostics, in 2 files."
+        ));
+
+        let one = render("MTLOG004", "Property names should be PascalCase", &count(&diagnostics(), "MTLOG004"));
+        assert!(one.contains("in 1 file."));
+
+        let none = render("MTLOG009", "With() has an odd number of arguments", &count(&diagnostics(), "MTLOG009"));
+        assert!(none.contains("would hide none of the 5 current diagnostics"));
+    }
+
+    #[test]
+    fn test_parse_code() {
+        assert_eq!(parse_code("mtlog003").map(|(code, _)| code), Ok("MTLOG003"));
+        assert!(parse_code("MTLOG099").is_err());
+        assert!(parse_code("").is_err());
+    }
+
+    #[test]
+    fn test_complete() {
+        let all = complete(&[]);
+        assert_eq!(all.len(), RULES.len());
+
+        let completions = complete(&["mtlog01".to_string()]);
+        let codes: Vec<&str> = completions.iter().map(|c| c.new_text.as_str()).collect();
+        assert_eq!(codes, vec!["MTLOG010", "MTLOG011", "MTLOG012", "MTLOG013"]);
+        assert!(completions[0].run_command);
+    }
+}
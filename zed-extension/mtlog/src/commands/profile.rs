@@ -0,0 +1,72 @@
+//! `/mtlog-profile <name>`: selects a profile for the rest of the session in
+//! place of the configured `profile`, without touching the settings files.
+//! `none` selects no preset.
+
+use zed_extension_api::{SlashCommandArgumentCompletion, SlashCommandOutput};
+
+use crate::session::SessionOverrides;
+use crate::settings;
+
+pub(super) fn run(args: &[String], session: &mut SessionOverrides) -> Result<SlashCommandOutput, String> {
+    let [profile] = args else {
+        return Err("usage: /mtlog-profile <name>".to_string());
+    };
+    let profile = session.set_profile(profile)?;
+
+    Ok(super::output(
+        "mtlog: profile",
+        format!(
+            "Profile \"{profile}\" is active for this session; options set explicitly in the settings keep \
+             their values. /mtlog-reset returns to the configured settings.\n\n{}\n",
+            super::RESTART_NOTE
+        ),
+    ))
+}
+
+/// Profile names matching the typed prefix, each run on selection.
+pub(super) fn complete(args: &[String]) -> Vec<SlashCommandArgumentCompletion> {
+    let typed = args.first().map(String::as_str).unwrap_or("").to_ascii_lowercase();
+    settings::profile_names()
+        .filter(|name| name.starts_with(&typed))
+        .map(|name| SlashCommandArgumentCompletion {
+            label: name.to_string(),
+            new_text: name.to_string(),
+            run_command: true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_run() {
+        let mut session = SessionOverrides::default();
+
+        let output = run(&args(&["STRICT"]), &mut session).unwrap();
+
+        assert!(output.text.starts_with("Profile \"strict\" is active for this session;"));
+        assert!(output.text.contains("restart language server"));
+        assert_eq!(session.clear(), vec!["profile \"strict\""]);
+
+        assert!(run(&args(&["paranoid"]), &mut session).unwrap_err().starts_with("unknown profile"));
+        assert!(run(&[], &mut session).unwrap_err().starts_with("usage:"));
+        assert!(run(&args(&["strict", "none"]), &mut session).is_err());
+        assert!(session.clear().is_empty());
+    }
+
+    #[test]
+    fn test_complete() {
+        let names =
+            |typed: &[&str]| -> Vec<String> { complete(&args(typed)).into_iter().map(|c| c.new_text).collect() };
+
+        assert_eq!(names(&[]), vec!["strict", "none"]);
+        assert_eq!(names(&["S"]), vec!["strict"]);
+        assert!(names(&["x"]).is_empty());
+    }
+}
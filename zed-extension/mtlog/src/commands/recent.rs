@@ -0,0 +1,114 @@
+//! `/mtlog-recent [N]`: lists the last `N` analyses with their timings.
+//!
+//! Zed does not pass mtlog-lsp's own activity to extensions, so this reports
+//! the analyses the extension ran itself (see [`crate::history`]).
+
+use std::time::Duration;
+
+use zed_extension_api::SlashCommandOutput;
+
+use crate::history::{Analysis, AnalysisHistory, CAPACITY};
+
+/// Number of analyses listed when no count is given.
+const DEFAULT_COUNT: usize = 10;
+
+pub(super) fn run(args: &[String], history: &AnalysisHistory) -> Result<SlashCommandOutput, String> {
+    let count = parse_count(args)?;
+    let analyses: Vec<&Analysis> = history.latest(count).collect();
+    Ok(super::output("mtlog: recent analyses", render(&analyses)))
+}
+
+/// Parses the optional count, capping it at the history's capacity.
+fn parse_count(args: &[String]) -> Result<usize, String> {
+    match args {
+        [] => Ok(DEFAULT_COUNT),
+        [arg] => match arg.parse::<usize>() {
+            Ok(count) if count > 0 => Ok(count.min(CAPACITY)),
+            _ => Err(format!("unexpected argument \"{arg}\"; expected a positive number")),
+        },
+        _ => Err("usage: /mtlog-recent [N]".to_string()),
+    }
+}
+
+fn render(analyses: &[&Analysis]) -> String {
+    let mut out = String::new();
+    if analyses.is_empty() {
+        out.push_str("No analyses recorded yet. Run /mtlog-sarif to analyze the project.\n");
+    } else {
+        out.push_str("| Target | Time | Findings |\n|--------|------|----------|\n");
+        for analysis in analyses {
+            out.push_str(&format!(
+                "| `{}` | {} | {} |\n",
+                analysis.target,
+                format_elapsed(analysis.elapsed),
+                analysis.findings
+            ));
+        }
+    }
+    out.push_str(
+        "\nThese are analyses run by the extension. mtlog-lsp does not share its per-file timings \
+         with extensions; enable enableMetrics to see them in the language server log.\n",
+    );
+    out
+}
+
+/// Milliseconds below a second, tenths of a second above.
+fn format_elapsed(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) {
+        format!("{} ms", elapsed.as_millis())
+    } else {
+        format!("{:.1} s", elapsed.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count(&[]), Ok(DEFAULT_COUNT));
+        assert_eq!(parse_count(&args(&["3"])), Ok(3));
+        assert_eq!(parse_count(&args(&["500"])), Ok(CAPACITY));
+        assert!(parse_count(&args(&["0"])).is_err());
+        assert!(parse_count(&args(&["-1"])).is_err());
+        assert!(parse_count(&args(&["ten"])).is_err());
+        assert!(parse_count(&args(&["1", "2"])).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let analyses = [
+            Analysis {
+                target: "./...".to_string(),
+                analyzer: "/go/bin/mtlog-analyzer".to_string(),
+                elapsed: Duration::from_millis(2_430),
+                findings: 7,
+            },
+            Analysis {
+                target: "./internal/db".to_string(),
+                analyzer: "/go/bin/mtlog-analyzer".to_string(),
+                elapsed: Duration::from_millis(85),
+                findings: 0,
+            },
+        ];
+
+        let rendered = render(&analyses.iter().collect::<Vec<_>>());
+
+        assert!(rendered.starts_with(
+            "| Target | Time | Findings |\n|--------|------|----------|\n\
+             | `./...` | 2.4 s | 7 |\n\
+             | `./internal/db` | 85 ms | 0 |\n"
+        ));
+        assert!(rendered.contains("enableMetrics"));
+    }
+
+    #[test]
+    fn test_render_empty() {
+        assert!(render(&[]).starts_with("No analyses recorded yet."));
+    }
+}
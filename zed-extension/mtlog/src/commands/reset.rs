@@ -0,0 +1,50 @@
+//! `/mtlog-reset`: drops every session override so mtlog-lsp is configured
+//! from the settings files alone again.
+
+use zed_extension_api::SlashCommandOutput;
+
+use crate::session::SessionOverrides;
+
+pub(super) fn run(session: &mut SessionOverrides) -> SlashCommandOutput {
+    super::output("mtlog: reset session overrides", render(&session.clear()))
+}
+
+fn render(cleared: &[String]) -> String {
+    if cleared.is_empty() {
+        return "No session overrides are active; mtlog-lsp already uses the configured settings.\n".to_string();
+    }
+
+    let mut text = String::from("Cleared session overrides:\n");
+    for key in cleared {
+        text.push_str(&format!("- {key}\n"));
+    }
+    text.push_str(&format!("\n{}\n", super::RESTART_NOTE));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reset lists the cleared keys and leaves no overrides behind.
+    #[test]
+    fn test_reset_clears_overrides() {
+        let mut session = SessionOverrides::default();
+        session.set_profile("strict").unwrap();
+
+        let output = run(&mut session);
+
+        assert!(output.text.contains("- profile \"strict\"\n"));
+        assert!(output.text.contains("restart language server"));
+        assert!(session.clear().is_empty());
+    }
+
+    /// Resetting without overrides says so instead of suggesting a restart.
+    #[test]
+    fn test_reset_without_overrides() {
+        let output = run(&mut SessionOverrides::default());
+
+        assert!(output.text.starts_with("No session overrides"));
+        assert!(!output.text.contains("restart"));
+    }
+}
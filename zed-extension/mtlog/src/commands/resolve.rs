@@ -0,0 +1,118 @@
+//! `/mtlog-resolve`: runs binary discovery without launching the server and
+//! lists every candidate in order, the one selected, and why the earlier
+//! ones were skipped.
+
+use zed_extension_api::{SlashCommandOutput, Worktree};
+
+use crate::discovery::{Candidate, Outcome};
+use crate::MtlogAnalyzerExtension;
+
+pub(super) fn run(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<SlashCommandOutput, String> {
+    Ok(super::output("mtlog: binary resolution", text(worktree, extension)))
+}
+
+/// The discovery trace, also included in `/mtlog-support-bundle`.
+pub(super) fn text(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> String {
+    let trace = extension.trace_mtlog_lsp(worktree);
+    render(&trace, extension.binary_in_use(worktree.id()))
+}
+
+/// Renders the trace; `in_use` is the binary the running server was started
+/// from, which stays in use until Zed restarts the extension.
+fn render(trace: &[(Candidate, Outcome)], in_use: Option<&str>) -> String {
+    let mut text = String::from("mtlog-analyzer binary resolution\n\n");
+
+    if trace.is_empty() {
+        text.push_str("No candidates: binary.path, extraBinDirs, PATH, $GOBIN, $GOPATH and $HOME are all unset.\n");
+    }
+    for (i, (candidate, outcome)) in trace.iter().enumerate() {
+        let outcome = match outcome {
+            Outcome::Selected if candidate.needs_check => "selected".to_string(),
+            Outcome::Selected => "selected without checking; Zed reports a failed launch if it is missing".to_string(),
+            Outcome::Skipped(reason) => format!("skipped, {reason}"),
+            Outcome::NotTried => "not tried".to_string(),
+        };
+        text.push_str(&format!("{}. {} ({}): {outcome}\n", i + 1, candidate.path, candidate.source));
+    }
+
+    let winner = trace
+        .iter()
+        .find(|(_, outcome)| *outcome == Outcome::Selected)
+        .map(|(candidate, _)| candidate.path.as_str());
+    match winner {
+        Some(path) => text.push_str(&format!("\nSelected: {path}\n")),
+        None => text.push_str("\nSelected: none; at launch the extension would try the install.method fallbacks\n"),
+    }
+    match in_use {
+        Some(path) if Some(path) != winner => text.push_str(&format!(
+            "In use:   {path}, chosen when the server started; restart Zed to switch\n"
+        )),
+        _ => {}
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::CandidateSource;
+
+    fn candidate(path: &str, source: CandidateSource, needs_check: bool) -> Candidate {
+        Candidate {
+            path: path.to_string(),
+            source,
+            needs_check,
+        }
+    }
+
+    fn trace() -> Vec<(Candidate, Outcome)> {
+        vec![
+            (
+                candidate("/opt/mtlog-lsp", CandidateSource::ExplicitPath, true),
+                Outcome::Skipped("could not be started: not found".to_string()),
+            ),
+            (candidate("/work/tools/mtlog-lsp", CandidateSource::ExtraBinDir, true), Outcome::Selected),
+            (candidate("/usr/bin/mtlog-lsp", CandidateSource::Path, false), Outcome::NotTried),
+        ]
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render(&trace(), Some("/work/tools/mtlog-lsp")),
+            "mtlog-analyzer binary resolution\n\n\
+             1. /opt/mtlog-lsp (binary.path): skipped, could not be started: not found\n\
+             2. /work/tools/mtlog-lsp (extraBinDirs): selected\n\
+             3. /usr/bin/mtlog-lsp (PATH): not tried\n\
+             \n\
+             Selected: /work/tools/mtlog-lsp\n"
+        );
+    }
+
+    /// A binary in use that discovery would no longer pick is pointed out.
+    #[test]
+    fn test_render_in_use_differs() {
+        let rendered = render(&trace(), Some("/usr/bin/mtlog-lsp"));
+
+        assert!(rendered.ends_with("In use:   /usr/bin/mtlog-lsp, chosen when the server started; restart Zed to switch\n"));
+    }
+
+    #[test]
+    fn test_render_nothing_selected() {
+        let skipped = vec![(
+            candidate("/opt/mtlog-lsp", CandidateSource::ExplicitPath, true),
+            Outcome::Skipped("not found".to_string()),
+        )];
+
+        assert!(render(&skipped, None).contains("Selected: none; at launch the extension would try"));
+        assert!(render(&[], None).contains("No candidates"));
+    }
+
+    #[test]
+    fn test_render_unchecked_winner() {
+        let unchecked = vec![(candidate("/gobin/mtlog-lsp", CandidateSource::GoBin, false), Outcome::Selected)];
+
+        assert!(render(&unchecked, None).contains("($GOBIN): selected without checking"));
+    }
+}
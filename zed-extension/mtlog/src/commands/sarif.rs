@@ -0,0 +1,79 @@
+//! `/mtlog-sarif <path>`: analyzes the whole project with mtlog-analyzer and
+//! writes the findings as SARIF to `path`, relative to the project root.
+
+use std::sync::Mutex;
+
+use zed_extension_api::{serde_json, SlashCommandOutput, Worktree};
+
+use crate::history::AnalysisHistory;
+use crate::report;
+
+/// File extensions accepted for the output path.
+const OUTPUT_EXTENSIONS: &[&str] = &[".sarif", ".sarif.json", ".json"];
+
+pub(super) fn run(
+    args: &[String],
+    worktree: &Worktree,
+    history: &Mutex<AnalysisHistory>,
+) -> Result<SlashCommandOutput, String> {
+    let path = match args {
+        [path] => validate_output_path(path)?,
+        _ => return Err("usage: /mtlog-sarif <path>".to_string()),
+    };
+
+    let diagnostics = super::analyze_project("mtlog-sarif", worktree, history)?;
+
+    let root = worktree.root_path();
+    let sarif = serde_json::to_string_pretty(&report::to_sarif(&diagnostics, &root)).unwrap_or_default();
+    let text = match std::fs::write(format!("{root}/{path}"), format!("{sarif}\n")) {
+        Ok(()) => format!("Wrote {} results to {path}.\n", diagnostics.len()),
+        Err(err) => format!(
+            "Could not write {path} ({err}); save the report below instead.\n\n```json\n{sarif}\n```\n"
+        ),
+    };
+
+    Ok(super::output("mtlog: SARIF report", text))
+}
+
+/// Checks that `path` is a project-relative SARIF or JSON file path, and
+/// returns it without a leading `./`.
+fn validate_output_path(path: &str) -> Result<&str, String> {
+    let path = path.trim();
+    let path = path.strip_prefix("./").unwrap_or(path);
+
+    if path.is_empty() {
+        return Err("the output path is empty".to_string());
+    }
+    if path.starts_with(['/', '\\']) || path.get(1..2) == Some(":") {
+        return Err(format!("{path} must be relative to the project root"));
+    }
+    if path.split(['/', '\\']).any(|component| component == "..") {
+        return Err(format!("{path} must stay inside the project"));
+    }
+    if path.ends_with(['/', '\\']) {
+        return Err(format!("{path} is a directory; name the report file"));
+    }
+    if !OUTPUT_EXTENSIONS.iter().any(|ext| path.to_ascii_lowercase().ends_with(ext)) {
+        return Err(format!("{path} should end in .sarif or .json"));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_output_path() {
+        assert_eq!(validate_output_path("mtlog.sarif"), Ok("mtlog.sarif"));
+        assert_eq!(validate_output_path("./reports/mtlog.SARIF"), Ok("reports/mtlog.SARIF"));
+        assert_eq!(validate_output_path("out/mtlog.sarif.json"), Ok("out/mtlog.sarif.json"));
+
+        assert!(validate_output_path("").is_err());
+        assert!(validate_output_path("/tmp/mtlog.sarif").is_err());
+        assert!(validate_output_path("C:\\reports\\mtlog.sarif").is_err());
+        assert!(validate_output_path("../mtlog.sarif").is_err());
+        assert!(validate_output_path("reports/").is_err());
+        assert!(validate_output_path("mtlog.txt").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! `/mtlog-support-bundle`: gathers the doctor report, the discovery trace,
+//! the effective configuration, and the Go environment into one block to
+//! paste into a GitHub issue.
+//!
+//! The sections keep the markdown of their own commands, so the bundle is
+//! fenced with four backticks to hold the configuration's code block. With
+//! `redactPaths` on, the home directory is replaced by `~` throughout.
+
+use zed_extension_api::{self as zed, settings::LspSettings, SlashCommandOutput, Worktree};
+
+use crate::download;
+use crate::settings;
+use crate::MtlogAnalyzerExtension;
+
+/// Fence around the bundle, longer than the fences inside it.
+const FENCE: &str = "````";
+
+pub(super) fn run(worktree: &Worktree, extension: &MtlogAnalyzerExtension) -> Result<SlashCommandOutput, String> {
+    let lsp_settings = LspSettings::for_worktree(settings::LANGUAGE_SERVER_NAME, worktree)?;
+    let sections = [
+        ("Doctor", super::doctor::text(worktree, extension)),
+        ("Binary resolution", Ok(super::resolve::text(worktree, extension))),
+        ("Effective configuration", super::config::text(worktree, extension)),
+        ("Go environment", Ok(super::goenv::text(worktree))),
+    ];
+    let (os, arch) = zed::current_platform();
+    let header = format!(
+        "mtlog-analyzer Zed extension {} on {}",
+        env!("CARGO_PKG_VERSION"),
+        download::platform_name(os, arch)
+    );
+
+    let mut text = assemble(&header, &sections);
+    if settings::redact_paths(&lsp_settings) {
+        let shell_env = worktree.shell_env();
+        let home = shell_env
+            .iter()
+            .find(|(key, value)| (key == "HOME" || key == "USERPROFILE") && !value.is_empty())
+            .map(|(_, home)| home.as_str());
+        if let Some(home) = home {
+            text = redact_home(&text, home);
+        }
+    }
+    Ok(super::output("mtlog: support bundle", text))
+}
+
+/// Joins the sections under `header`; a section that could not be gathered
+/// shows why instead.
+fn assemble(header: &str, sections: &[(&str, Result<String, String>)]) -> String {
+    let mut text = format!("{FENCE}markdown\n{header}\n");
+    for (title, section) in sections {
+        text.push_str(&format!("\n## {title}\n\n"));
+        match section {
+            Ok(section) => text.push_str(section.trim_end()),
+            Err(err) => text.push_str(&format!("Could not be gathered: {err}")),
+        }
+        text.push('\n');
+    }
+    text.push_str(FENCE);
+    text.push('\n');
+    text
+}
+
+/// Replaces `home` with `~` wherever it starts a path, including its
+/// JSON-escaped form with doubled backslashes on Windows. A longer name
+/// sharing the prefix, such as `/home/al` in `/home/alice`, is left alone.
+fn redact_home(text: &str, home: &str) -> String {
+    let home = home.trim_end_matches(['/', '\\']);
+    if home.is_empty() {
+        return text.to_string();
+    }
+    let mut redacted = text.to_string();
+    let escaped = home.replace('\\', "\\\\");
+    for form in [escaped.as_str(), home] {
+        let mut out = String::with_capacity(redacted.len());
+        let mut rest = redacted.as_str();
+        while let Some(at) = rest.find(form) {
+            let after = &rest[at + form.len()..];
+            let ends_name = after.chars().next().is_none_or(|c| !(c.is_alphanumeric() || "._-".contains(c)));
+            out.push_str(&rest[..at]);
+            out.push_str(if ends_name { "~" } else { form });
+            rest = after;
+        }
+        out.push_str(rest);
+        redacted = out;
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assemble() {
+        let sections = [
+            ("Doctor", Ok("mtlog-analyzer doctor\n\nBinary:   /usr/bin/mtlog-lsp (in use)\n".to_string())),
+            ("Effective configuration", Ok("```jsonc\n{}\n```\n".to_string())),
+            ("Go environment", Err("go is not on PATH".to_string())),
+        ];
+
+        assert_eq!(
+            assemble("mtlog-analyzer Zed extension 0.9.0 on linux-amd64", &sections),
+            "````markdown\n\
+             mtlog-analyzer Zed extension 0.9.0 on linux-amd64\n\
+             \n## Doctor\n\n\
+             mtlog-analyzer doctor\n\nBinary:   /usr/bin/mtlog-lsp (in use)\n\
+             \n## Effective configuration\n\n\
+             ```jsonc\n{}\n```\n\
+             \n## Go environment\n\n\
+             Could not be gathered: go is not on PATH\n\
+             ````\n"
+        );
+    }
+
+    #[test]
+    fn test_redact_home() {
+        let text = "1. /home/al/go/bin/mtlog-lsp (PATH)\nGOPATH /home/al, not /home/alice/go\n";
+
+        assert_eq!(
+            redact_home(text, "/home/al/"),
+            "1. ~/go/bin/mtlog-lsp (PATH)\nGOPATH ~, not /home/alice/go\n"
+        );
+        assert_eq!(redact_home(text, ""), text);
+    }
+
+    /// Windows homes are redacted both as written and inside JSON strings.
+    #[test]
+    fn test_redact_home_windows() {
+        let text = "Binary:   C:\\Users\\dev\\go\\bin\\mtlog-lsp.exe\n  \"cacheDir\": \"C:\\\\Users\\\\dev\\\\cache\"\n";
+
+        assert_eq!(
+            redact_home(text, "C:\\Users\\dev"),
+            "Binary:   ~\\go\\bin\\mtlog-lsp.exe\n  \"cacheDir\": \"~\\\\cache\"\n"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! `/mtlog-vs-gopls`: a short reference separating what mtlog-analyzer
+//! reports from what gopls already checks, since the two are often confused.
+
+use zed_extension_api::SlashCommandOutput;
+
+use crate::report::RULES;
+
+/// What gopls reports for the issue each code covers.
+const GOPLS_COVERAGE: &[(&str, &str)] = &[
+    ("MTLOG001", "No; its `printf` check counts `%` verbs, not `{Property}` placeholders"),
+    ("MTLOG002", "No; `printf` validates `%` verbs only, never `{Price:F2}` specifiers"),
+    ("MTLOG003", "No"),
+    ("MTLOG004", "No"),
+    ("MTLOG005", "No"),
+    ("MTLOG006", "No"),
+    ("MTLOG007", "No"),
+    ("MTLOG008", "Only for `fmt`-style calls, as \"non-constant format string\""),
+    ("MTLOG009", "Only for `log/slog` calls, through its `slog` check, not mtlog's `With()`"),
+    ("MTLOG010", "Only for `log/slog` calls, through its `slog` check"),
+    ("MTLOG011", "No"),
+    ("MTLOG012", "No"),
+    ("MTLOG013", "No"),
+];
+
+/// gopls checks on log calls that mtlog-analyzer leaves alone.
+const GOPLS_ONLY: &[&str] = &[
+    "`printf`: `%` verbs and their arguments in `fmt`-style calls such as `log.Printf`",
+    "`slog`: key/value pairs in `log/slog` calls",
+    "Type errors, unused variables, and the rest of `go vet`, on log calls like any other code",
+];
+
+pub(super) fn run() -> SlashCommandOutput {
+    super::output("mtlog: mtlog-analyzer and gopls", render())
+}
+
+fn render() -> String {
+    let mut text = String::from(
+        "mtlog templates use `{Property}` placeholders, which gopls does not parse, so the \
+         MTLOG codes do not duplicate gopls warnings.\n\n\
+         | Code | mtlog-analyzer reports | Does gopls report it? |\n\
+         |------|------------------------|-----------------------|\n",
+    );
+    for (code, description) in RULES {
+        let gopls = GOPLS_COVERAGE
+            .iter()
+            .find(|(covered, _)| covered == code)
+            .map_or("No", |(_, gopls)| gopls);
+        text.push_str(&format!("| {code} | {description} | {gopls} |\n"));
+    }
+
+    text.push_str("\ngopls, not mtlog-analyzer, reports:\n\n");
+    for check in GOPLS_ONLY {
+        text.push_str(&format!("- {check}\n"));
+    }
+    text.push_str("\nRun both: a `log.Printf` call is gopls's to check, an mtlog template is mtlog-analyzer's.\n");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every analyzer code is listed, with its own gopls entry.
+    #[test]
+    fn test_render_lists_every_code() {
+        let rendered = render();
+
+        for (code, description) in RULES {
+            assert!(rendered.contains(&format!("| {code} | {description} |")), "{code}");
+            assert!(GOPLS_COVERAGE.iter().any(|(covered, _)| covered == code), "{code}");
+        }
+        assert_eq!(GOPLS_COVERAGE.len(), RULES.len());
+        assert!(rendered.contains("`slog`: key/value pairs"));
+    }
+}
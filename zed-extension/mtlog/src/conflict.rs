@@ -0,0 +1,93 @@
+//! Heuristics for spotting a second mtlog extension, which would make
+//! diagnostics appear twice.
+//!
+//! Extensions cannot list each other, so the checks rely on what this one
+//! can see: Zed settings for the server ids other mtlog extensions register,
+//! and the same binary being launched under more than one server id.
+
+use std::collections::{BTreeSet, HashMap};
+
+use zed_extension_api::settings::LspSettings;
+
+/// Server ids registered by other or older mtlog extensions.
+pub(crate) const OTHER_SERVER_IDS: &[&str] = &["mtlog", "mtlog-lsp"];
+
+/// The server ids each binary has been launched as.
+#[derive(Debug, Default)]
+pub(crate) struct LaunchOwners {
+    owners: HashMap<String, BTreeSet<String>>,
+}
+
+impl LaunchOwners {
+    /// Records that `binary` is launched as `server`, returning a warning when
+    /// it was launched as another server id before.
+    pub(crate) fn record(&mut self, binary: &str, server: &str) -> Option<String> {
+        let owners = self.owners.entry(binary.to_string()).or_default();
+        owners.insert(server.to_string());
+        (owners.len() > 1).then(|| {
+            let ids: Vec<&str> = owners.iter().map(String::as_str).collect();
+            format!(
+                "{binary} is launched as several language servers ({}); diagnostics may appear more than once",
+                ids.join(", ")
+            )
+        })
+    }
+}
+
+/// Warns about each of `others` that has settings, a sign that another
+/// mtlog extension providing that server is, or was, installed.
+pub(crate) fn configured_elsewhere(others: &[(&str, LspSettings)]) -> Vec<String> {
+    others
+        .iter()
+        .filter(|(_, lsp_settings)| {
+            lsp_settings.binary.is_some()
+                || lsp_settings.settings.is_some()
+                || lsp_settings.initialization_options.is_some()
+        })
+        .map(|(id, _)| {
+            format!(
+                "found settings for language server \"{id}\", which another mtlog extension provides; \
+                 if it is installed too, diagnostics may appear twice"
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    /// Relaunching under the same id is fine; a second id is reported.
+    #[test]
+    fn test_same_binary_under_different_ids() {
+        let mut owners = LaunchOwners::default();
+
+        assert_eq!(owners.record("/usr/bin/mtlog-lsp", "mtlog-analyzer"), None);
+        assert_eq!(owners.record("/usr/bin/mtlog-lsp", "mtlog-analyzer"), None);
+        assert_eq!(owners.record("/opt/mtlog-lsp", "mtlog"), None);
+
+        let warning = owners.record("/usr/bin/mtlog-lsp", "mtlog").unwrap();
+        assert!(warning.contains("(mtlog, mtlog-analyzer)"));
+    }
+
+    /// Only server ids with any settings count as signs of another extension.
+    #[test]
+    fn test_configured_elsewhere() {
+        let others = [
+            ("mtlog", LspSettings::default()),
+            (
+                "mtlog-lsp",
+                LspSettings {
+                    settings: Some(json!({ "strictMode": true })),
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let warnings = configured_elsewhere(&others);
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("\"mtlog-lsp\""));
+    }
+}
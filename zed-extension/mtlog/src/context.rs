@@ -0,0 +1,190 @@
+//! Summaries of each project's latest mtlog analysis, for the assistant.
+//!
+//! Every whole-project analysis the extension runs writes a summary file for
+//! that project to the `mtlog-context` directory of the extension's work dir,
+//! where a context server configured in Zed can read it. The summary looks
+//! like:
+//!
+//! ```json
+//! {
+//!   "root": "/work/app",
+//!   "analyzedAt": 1760400000,
+//!   "findings": 5,
+//!   "byCode": { "MTLOG001": 2, "MTLOG004": 3 },
+//!   "topFiles": [{ "file": "internal/db.go", "findings": 3 }]
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use zed_extension_api::serde_json::{self, json, Value};
+
+use crate::discovery;
+use crate::report::Diagnostic;
+
+/// Directory of the summaries in the extension's work dir.
+const SUMMARY_DIR: &str = "mtlog-context";
+
+/// Number of files listed in `topFiles`.
+const TOP_FILES: usize = 10;
+
+/// Key under which findings without a code are counted.
+const UNCODED: &str = "uncoded";
+
+/// Summarizes `diagnostics` from an analysis of the project at `root`.
+/// Files are given relative to the root, most findings first.
+pub(crate) fn summary(diagnostics: &[Diagnostic], root: &str, analyzed_at: u64) -> Value {
+    let root = root.trim_end_matches(['/', '\\']);
+    let mut by_code: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut by_file: BTreeMap<String, usize> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        *by_code.entry(diagnostic.code.as_deref().unwrap_or(UNCODED)).or_default() += 1;
+        let file = diagnostic
+            .file
+            .strip_prefix(root)
+            .and_then(|rest| rest.strip_prefix(['/', '\\']))
+            .unwrap_or(&diagnostic.file)
+            .replace('\\', "/");
+        *by_file.entry(file).or_default() += 1;
+    }
+
+    // Sorting is stable, so files with equal counts stay in name order
+    let mut top_files: Vec<(String, usize)> = by_file.into_iter().collect();
+    top_files.sort_by(|(_, a), (_, b)| b.cmp(a));
+    top_files.truncate(TOP_FILES);
+
+    json!({
+        "root": root,
+        "analyzedAt": analyzed_at,
+        "findings": diagnostics.len(),
+        "byCode": by_code,
+        "topFiles": top_files
+            .into_iter()
+            .map(|(file, findings)| json!({ "file": file, "findings": findings }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Name of the summary file for the project at `root`: its directory name,
+/// for reading, and a hash of the whole path, so projects never share one.
+fn summary_file(root: &str) -> String {
+    let root = root.trim_end_matches(['/', '\\']);
+    let name: String = root
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}-{:016x}.json", if name.is_empty() { "root" } else { &name }, fnv1a(root.as_bytes()))
+}
+
+/// 64-bit FNV-1a, which unlike the std hashers is stable across Rust
+/// releases, so a project keeps its file name.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Path of the summary for the project at `root` inside `work_dir`.
+pub(crate) fn summary_path(work_dir: &str, root: &str) -> String {
+    discovery::join(&discovery::join(work_dir, SUMMARY_DIR), &summary_file(root))
+}
+
+/// Writes the summary of an analysis of the project at `root`. Failing to
+/// write it, or not knowing the work dir, only leaves the previous summary
+/// in place.
+pub(crate) fn record(diagnostics: &[Diagnostic], root: &str, work_dir: &str) {
+    if work_dir.is_empty() {
+        eprintln!("mtlog-analyzer: not writing the analysis summary: the extension work dir is unknown");
+        return;
+    }
+    let analyzed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let summary = summary(diagnostics, root, analyzed_at);
+    let text = serde_json::to_string_pretty(&summary).unwrap_or_default();
+    let path = summary_path(work_dir, root);
+    let written = std::fs::create_dir_all(discovery::join(work_dir, SUMMARY_DIR))
+        .and_then(|()| std::fs::write(&path, format!("{text}\n")));
+    if let Err(err) = written {
+        eprintln!("mtlog-analyzer: could not write {path}: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Severity;
+
+    fn diagnostic(file: &str, code: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            file: file.to_string(),
+            line: 1,
+            column: 1,
+            code: code.map(str::to_string),
+            severity: Severity::Warning,
+            message: "finding".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let diagnostics = vec![
+            diagnostic("/work/app/main.go", Some("MTLOG001")),
+            diagnostic("/work/app/internal/db.go", Some("MTLOG004")),
+            diagnostic("/work/app/internal/db.go", Some("MTLOG004")),
+            diagnostic("/work/app/internal/db.go", None),
+            diagnostic("/elsewhere/gen.go", Some("MTLOG001")),
+        ];
+
+        assert_eq!(
+            summary(&diagnostics, "/work/app/", 1_760_400_000),
+            json!({
+                "root": "/work/app",
+                "analyzedAt": 1_760_400_000,
+                "findings": 5,
+                "byCode": { "MTLOG001": 2, "MTLOG004": 2, "uncoded": 1 },
+                "topFiles": [
+                    { "file": "internal/db.go", "findings": 3 },
+                    { "file": "/elsewhere/gen.go", "findings": 1 },
+                    { "file": "main.go", "findings": 1 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_summary_top_files_limit() {
+        let diagnostics: Vec<Diagnostic> =
+            (0..15).map(|i| diagnostic(&format!("/work/app/f{i:02}.go"), None)).collect();
+
+        let summary = summary(&diagnostics, "/work/app", 0);
+
+        assert_eq!(summary["topFiles"].as_array().unwrap().len(), TOP_FILES);
+        assert_eq!(summary["topFiles"][0]["file"], "f00.go");
+        assert_eq!(summary["byCode"], json!({ "uncoded": 15 }));
+    }
+
+    /// Each project gets its own file, named after its directory.
+    #[test]
+    fn test_summary_path() {
+        let app = summary_path("/ext/work/", "/work/app/");
+
+        assert!(app.starts_with("/ext/work/mtlog-context/app-"));
+        assert!(app.ends_with(".json"));
+        assert_eq!(app, summary_path("/ext/work", "/work/app"));
+        assert_ne!(app, summary_path("/ext/work", "/other/app"));
+        assert!(summary_path("/ext/work", "C:\\src\\my app").starts_with("/ext/work/mtlog-context/my_app-"));
+        assert!(summary_path("/ext/work", "/").starts_with("/ext/work/mtlog-context/root-"));
+    }
+
+    /// The hash is fixed, so summaries keep their names across releases.
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,93 @@
+//! Decoding of the text that processes print.
+//!
+//! go, git, and the mtlog binaries print UTF-8, but a stray byte from a
+//! legacy locale or a damaged binary must not cost the rest of the output.
+//! Everything the extension reads from a process goes through
+//! [`decode_output`], which replaces invalid bytes with U+FFFD and logs one
+//! warning naming the process.
+
+use std::borrow::Cow;
+
+/// Process output decoded as UTF-8.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Decoded<'a> {
+    pub(crate) text: Cow<'a, str>,
+    /// Bytes that were not valid UTF-8, each group replaced by U+FFFD.
+    pub(crate) invalid_bytes: usize,
+}
+
+impl Decoded<'_> {
+    /// The warning to log for output from `source`, if any bytes were invalid.
+    pub(crate) fn warning(&self, source: &str) -> Option<String> {
+        match self.invalid_bytes {
+            0 => None,
+            1 => Some(format!("output of {source} is not valid UTF-8; replaced 1 invalid byte")),
+            n => Some(format!("output of {source} is not valid UTF-8; replaced {n} invalid bytes")),
+        }
+    }
+}
+
+/// Decodes `bytes`, replacing invalid sequences.
+pub(crate) fn decode(bytes: &[u8]) -> Decoded<'_> {
+    let invalid_bytes = bytes.utf8_chunks().map(|chunk| chunk.invalid().len()).sum();
+    Decoded {
+        text: String::from_utf8_lossy(bytes),
+        invalid_bytes,
+    }
+}
+
+/// Decodes what `source`, such as `go vet`, printed, logging a warning when
+/// the output was not valid UTF-8.
+pub(crate) fn decode_output<'a>(bytes: &'a [u8], source: &str) -> Cow<'a, str> {
+    let decoded = decode(bytes);
+    if let Some(warning) = decoded.warning(source) {
+        eprintln!("mtlog-analyzer: {warning}");
+    }
+    decoded.text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_clean() {
+        let decoded = decode("mtlog-lsp v0.9.0 — ok\n".as_bytes());
+
+        assert_eq!(decoded.text, "mtlog-lsp v0.9.0 — ok\n");
+        assert!(matches!(decoded.text, Cow::Borrowed(_)));
+        assert_eq!(decoded.invalid_bytes, 0);
+        assert_eq!(decoded.warning("go vet"), None);
+        assert_eq!(decode(b"").invalid_bytes, 0);
+    }
+
+    /// Invalid bytes are replaced and counted; the valid text around them is kept.
+    #[test]
+    fn test_decode_invalid_bytes() {
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend_from_slice(b" v0.9.0 ");
+        bytes.extend_from_slice(&[0xc3, 0x28]);
+
+        let decoded = decode(&bytes);
+
+        assert_eq!(decoded.text, "\u{fffd}\u{fffd} v0.9.0 \u{fffd}(");
+        assert_eq!(decoded.invalid_bytes, 3);
+        assert_eq!(
+            decoded.warning("mtlog-lsp --version").as_deref(),
+            Some("output of mtlog-lsp --version is not valid UTF-8; replaced 3 invalid bytes")
+        );
+        assert_eq!(
+            decode(&[b'a', 0x80]).warning("go env").as_deref(),
+            Some("output of go env is not valid UTF-8; replaced 1 invalid byte")
+        );
+    }
+
+    /// A multi-byte character cut off at the end counts as invalid.
+    #[test]
+    fn test_decode_truncated_character() {
+        let decoded = decode(&"é".as_bytes()[..1]);
+
+        assert_eq!(decoded.text, "\u{fffd}");
+        assert_eq!(decoded.invalid_bytes, 1);
+    }
+}
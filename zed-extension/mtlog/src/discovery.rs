@@ -0,0 +1,448 @@
+//! Discovery of the mtlog-lsp binary.
+//!
+//! Discovery is split into building the ordered list of candidate paths from
+//! a snapshot of the worktree (settings, `$PATH` lookup, shell environment)
+//! and picking the first usable one, so the ordering can be tested without a
+//! running Zed. The pick is recorded per candidate for `/mtlog-resolve`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Name of the language server binary.
+pub(crate) const BINARY_NAME: &str = "mtlog-lsp";
+
+/// Where a candidate path came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CandidateSource {
+    /// `binary.path` in the Zed settings.
+    ExplicitPath,
+    /// An entry of the `extraBinDirs` setting.
+    ExtraBinDir,
+    /// Where the download or `go install` fallback put the binary.
+    Installed,
+    /// The shell's `$PATH`.
+    Path,
+    /// `$GOBIN`.
+    GoBin,
+    /// `$GOPATH/bin`.
+    GoPath,
+    /// `$HOME/go/bin`, the default Go installation directory.
+    HomeGoBin,
+}
+
+impl fmt::Display for CandidateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CandidateSource::ExplicitPath => "binary.path",
+            CandidateSource::ExtraBinDir => "extraBinDirs",
+            CandidateSource::Installed => "extension install",
+            CandidateSource::Path => "PATH",
+            CandidateSource::GoBin => "$GOBIN",
+            CandidateSource::GoPath => "$GOPATH/bin",
+            CandidateSource::HomeGoBin => "~/go/bin",
+        })
+    }
+}
+
+/// A path that may hold the binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Candidate {
+    pub(crate) path: String,
+    pub(crate) source: CandidateSource,
+    /// Whether the path must be confirmed to exist before it is used.
+    /// Paths from the environment are handed to Zed as-is, as before.
+    pub(crate) needs_check: bool,
+}
+
+/// Everything discovery needs to know about the worktree.
+#[derive(Debug, Default)]
+pub(crate) struct DiscoveryInput<'a> {
+    pub(crate) explicit_path: Option<&'a str>,
+    /// Whether `explicit_path` is the only candidate, see `binary.pathStrict`.
+    pub(crate) explicit_path_strict: bool,
+    pub(crate) extra_bin_dirs: &'a [String],
+    pub(crate) worktree_root: &'a str,
+    /// Where the installation fallbacks put the binary, in preference order.
+    pub(crate) installed_paths: Vec<String>,
+    /// Result of looking the binary up on `$PATH`.
+    pub(crate) path_lookup: Option<String>,
+    pub(crate) env: HashMap<String, String>,
+    /// Whether the `$GOBIN`, `$GOPATH/bin` and `~/go/bin` guesses are
+    /// confirmed like the other candidates. Set while an installation
+    /// fallback is configured, so a missing binary reaches it.
+    pub(crate) confirm_env_paths: bool,
+}
+
+/// Returns the candidate paths in the order they are tried:
+///
+/// 1. Explicit path from Zed settings, the only candidate when strict
+/// 2. Each `extraBinDirs` entry, relative entries resolved against the worktree root
+/// 3. System PATH via `which`
+/// 4. Where the installation fallbacks put the binary
+/// 5. GOBIN environment variable
+/// 6. GOPATH/bin directory
+/// 7. HOME/go/bin (default Go installation)
+///
+/// The last three are only confirmed with `confirm_env_paths`.
+pub(crate) fn candidates(input: &DiscoveryInput) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let mut push = |path: String, source, needs_check| {
+        candidates.push(Candidate {
+            path,
+            source,
+            needs_check,
+        })
+    };
+
+    if let Some(path) = input.explicit_path {
+        push(path.to_string(), CandidateSource::ExplicitPath, true);
+        if input.explicit_path_strict {
+            return candidates;
+        }
+    }
+
+    for dir in input.extra_bin_dirs {
+        let dir = resolve_dir(dir, input.worktree_root);
+        push(join(&dir, BINARY_NAME), CandidateSource::ExtraBinDir, true);
+    }
+
+    // PATH goes first so that an older download of the latest release, which
+    // is never refreshed, does not hide a binary the user installed since
+    if let Some(path) = input.path_lookup.as_ref() {
+        push(path.clone(), CandidateSource::Path, false);
+    }
+
+    for path in &input.installed_paths {
+        push(path.clone(), CandidateSource::Installed, true);
+    }
+
+    // Since we can't check if these exist in WASM without running them, they
+    // are returned as-is and Zed reports a failed launch, unless running them
+    // is worth it because an installation would follow.
+    let needs_check = input.confirm_env_paths;
+    if let Some(gobin) = input.env.get("GOBIN") {
+        push(join(gobin, BINARY_NAME), CandidateSource::GoBin, needs_check);
+    }
+    if let Some(gopath) = input.env.get("GOPATH") {
+        push(join(&join(gopath, "bin"), BINARY_NAME), CandidateSource::GoPath, needs_check);
+    }
+    if let Some(home) = input.env.get("HOME") {
+        push(join(&join(home, "go/bin"), BINARY_NAME), CandidateSource::HomeGoBin, needs_check);
+    }
+
+    candidates
+}
+
+/// What resolution made of one candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    /// The candidate that is used.
+    Selected,
+    /// Passed over, for the given reason.
+    Skipped(String),
+    /// Comes after the selected candidate, so it was never looked at.
+    NotTried,
+}
+
+/// Resolves the candidates in order, recording the outcome for each one.
+/// `check` is called for the candidates that must be confirmed first and
+/// returns why an unusable one was skipped.
+pub(crate) fn trace(
+    candidates: Vec<Candidate>,
+    mut check: impl FnMut(&str) -> Result<(), String>,
+) -> Vec<(Candidate, Outcome)> {
+    let mut found = false;
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let outcome = if found {
+                Outcome::NotTried
+            } else if !candidate.needs_check {
+                Outcome::Selected
+            } else {
+                check(&candidate.path).map_or_else(Outcome::Skipped, |()| Outcome::Selected)
+            };
+            found |= outcome == Outcome::Selected;
+            (candidate, outcome)
+        })
+        .collect()
+}
+
+/// The selected candidate of a [`trace`], if any.
+pub(crate) fn selected(trace: Vec<(Candidate, Outcome)>) -> Option<Candidate> {
+    trace
+        .into_iter()
+        .find_map(|(candidate, outcome)| (outcome == Outcome::Selected).then_some(candidate))
+}
+
+/// Resolves `dir` against `root` unless it is already absolute.
+pub(crate) fn resolve_dir(dir: &str, root: &str) -> String {
+    if is_absolute(dir) || root.is_empty() {
+        dir.to_string()
+    } else {
+        join(root, dir.strip_prefix("./").unwrap_or(dir))
+    }
+}
+
+fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with('/')
+        || path.starts_with('\\')
+        || (bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'/' | b'\\'))
+}
+
+pub(crate) fn join(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches(['/', '\\']), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    /// The first usable candidate, where `exists` confirms the ones that need it.
+    fn resolve(candidates: Vec<Candidate>, mut exists: impl FnMut(&str) -> bool) -> Option<Candidate> {
+        selected(trace(candidates, |path| if exists(path) { Ok(()) } else { Err("not found".to_string()) }))
+    }
+
+    fn sources(candidates: &[Candidate]) -> Vec<CandidateSource> {
+        candidates.iter().map(|c| c.source).collect()
+    }
+
+    /// Extra bin dirs come right after the explicit path, then PATH, then the install directory.
+    #[test]
+    fn test_candidate_order() {
+        let extra = vec!["tools/bin".to_string()];
+        let input = DiscoveryInput {
+            explicit_path: Some("/opt/mtlog-lsp"),
+            explicit_path_strict: false,
+            extra_bin_dirs: &extra,
+            worktree_root: "/work/project",
+            installed_paths: vec!["/ext/work/go-bin/mtlog-lsp".to_string()],
+            path_lookup: Some("/usr/bin/mtlog-lsp".to_string()),
+            env: env(&[("GOBIN", "/gobin"), ("GOPATH", "/gopath"), ("HOME", "/home/me")]),
+            confirm_env_paths: false,
+        };
+
+        let candidates = candidates(&input);
+
+        assert_eq!(
+            sources(&candidates),
+            vec![
+                CandidateSource::ExplicitPath,
+                CandidateSource::ExtraBinDir,
+                CandidateSource::Path,
+                CandidateSource::Installed,
+                CandidateSource::GoBin,
+                CandidateSource::GoPath,
+                CandidateSource::HomeGoBin,
+            ]
+        );
+        assert_eq!(candidates[4].path, "/gobin/mtlog-lsp");
+        assert_eq!(candidates[5].path, "/gopath/bin/mtlog-lsp");
+        assert_eq!(candidates[6].path, "/home/me/go/bin/mtlog-lsp");
+    }
+
+    /// A binary on PATH wins over an earlier install, which is used without one.
+    #[test]
+    fn test_resolve_prefers_path_binary() {
+        let input = DiscoveryInput {
+            installed_paths: vec!["/ext/work/go-bin/mtlog-lsp".to_string()],
+            path_lookup: Some("/usr/bin/mtlog-lsp".to_string()),
+            ..Default::default()
+        };
+
+        let on_path = resolve(candidates(&input), |_| true).unwrap();
+        let installed = resolve(candidates(&DiscoveryInput { path_lookup: None, ..input }), |_| true).unwrap();
+
+        assert_eq!(on_path.source, CandidateSource::Path);
+        assert_eq!(installed.source, CandidateSource::Installed);
+    }
+
+    /// Relative extra dirs resolve against the worktree root; absolute ones are kept.
+    #[test]
+    fn test_extra_bin_dirs_resolution() {
+        let extra = vec![
+            "tools/bin".to_string(),
+            "./.bin/".to_string(),
+            "/opt/tools".to_string(),
+            "C:\\tools".to_string(),
+        ];
+        let input = DiscoveryInput {
+            extra_bin_dirs: &extra,
+            worktree_root: "/work/project",
+            ..Default::default()
+        };
+
+        let paths: Vec<String> = candidates(&input).into_iter().map(|c| c.path).collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "/work/project/tools/bin/mtlog-lsp",
+                "/work/project/.bin/mtlog-lsp",
+                "/opt/tools/mtlog-lsp",
+                "C:\\tools/mtlog-lsp",
+            ]
+        );
+    }
+
+    /// Extra dirs without the binary are skipped in order until one has it.
+    #[test]
+    fn test_resolve_skips_missing_extra_dirs() {
+        let extra = vec!["missing".to_string(), "present".to_string()];
+        let input = DiscoveryInput {
+            extra_bin_dirs: &extra,
+            worktree_root: "/work",
+            path_lookup: Some("/usr/bin/mtlog-lsp".to_string()),
+            ..Default::default()
+        };
+
+        let mut checked = Vec::new();
+        let resolved = resolve(candidates(&input), |path| {
+            checked.push(path.to_string());
+            path.contains("present")
+        });
+
+        assert_eq!(resolved.unwrap().path, "/work/present/mtlog-lsp");
+        assert_eq!(checked, vec!["/work/missing/mtlog-lsp", "/work/present/mtlog-lsp"]);
+    }
+
+    /// When no extra dir has the binary, discovery continues with PATH.
+    #[test]
+    fn test_resolve_falls_through_to_path() {
+        let extra = vec!["missing".to_string()];
+        let input = DiscoveryInput {
+            extra_bin_dirs: &extra,
+            worktree_root: "/work",
+            path_lookup: Some("/usr/bin/mtlog-lsp".to_string()),
+            ..Default::default()
+        };
+
+        let resolved = resolve(candidates(&input), |_| false).unwrap();
+
+        assert_eq!(resolved.source, CandidateSource::Path);
+    }
+
+    /// A missing explicit path falls back to the other locations unless strict.
+    #[test]
+    fn test_strict_explicit_path_disables_fallbacks() {
+        let input = |strict| DiscoveryInput {
+            explicit_path: Some("/opt/missing/mtlog-lsp"),
+            explicit_path_strict: strict,
+            installed_paths: vec!["/ext/work/go-bin/mtlog-lsp".to_string()],
+            path_lookup: Some("/usr/bin/mtlog-lsp".to_string()),
+            env: env(&[("GOBIN", "/gobin")]),
+            ..Default::default()
+        };
+        let exists = |path: &str| path != "/opt/missing/mtlog-lsp";
+
+        let lenient = resolve(candidates(&input(false)), exists).unwrap();
+        assert_eq!(lenient.source, CandidateSource::Path);
+
+        assert_eq!(sources(&candidates(&input(true))), vec![CandidateSource::ExplicitPath]);
+        assert_eq!(resolve(candidates(&input(true)), exists), None);
+        assert_eq!(
+            resolve(candidates(&input(true)), |_| true).map(|c| c.path),
+            Some("/opt/missing/mtlog-lsp".to_string())
+        );
+    }
+
+    /// Every candidate is reported in order, with why the earlier ones were
+    /// skipped and the later ones not tried.
+    #[test]
+    fn test_trace() {
+        let extra = vec!["missing".to_string()];
+        let input = DiscoveryInput {
+            explicit_path: Some("/opt/broken/mtlog-lsp"),
+            extra_bin_dirs: &extra,
+            worktree_root: "/work",
+            installed_paths: vec!["/ext/work/go-bin/mtlog-lsp".to_string()],
+            env: env(&[("HOME", "/home/me")]),
+            ..Default::default()
+        };
+
+        let trace = trace(candidates(&input), |path| match path {
+            "/opt/broken/mtlog-lsp" => Err("could not be started: exec format error".to_string()),
+            "/work/missing/mtlog-lsp" => Err("not found".to_string()),
+            _ => Ok(()),
+        });
+
+        let outcomes: Vec<(CandidateSource, Outcome)> =
+            trace.iter().map(|(candidate, outcome)| (candidate.source, outcome.clone())).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (
+                    CandidateSource::ExplicitPath,
+                    Outcome::Skipped("could not be started: exec format error".to_string())
+                ),
+                (CandidateSource::ExtraBinDir, Outcome::Skipped("not found".to_string())),
+                (CandidateSource::Installed, Outcome::Selected),
+                (CandidateSource::HomeGoBin, Outcome::NotTried),
+            ]
+        );
+        assert_eq!(selected(trace).unwrap().path, "/ext/work/go-bin/mtlog-lsp");
+    }
+
+    /// Unchecked candidates are selected without calling `check`.
+    #[test]
+    fn test_trace_unchecked_candidate() {
+        let input = DiscoveryInput {
+            installed_paths: vec!["/ext/work/go-bin/mtlog-lsp".to_string()],
+            env: env(&[("GOBIN", "/gobin")]),
+            ..Default::default()
+        };
+
+        let mut checked = Vec::new();
+        let trace = trace(candidates(&input), |path| {
+            checked.push(path.to_string());
+            Err("not found".to_string())
+        });
+
+        assert_eq!(trace[1].1, Outcome::Selected);
+        assert_eq!(checked, vec!["/ext/work/go-bin/mtlog-lsp"]);
+    }
+
+    /// With an installation fallback configured, a `~/go/bin` guess without
+    /// the binary is skipped instead of selected, leaving nothing selected.
+    #[test]
+    fn test_env_paths_confirmed_when_installing() {
+        let input = |confirm_env_paths| DiscoveryInput {
+            env: env(&[("HOME", "/home/me")]),
+            confirm_env_paths,
+            ..Default::default()
+        };
+
+        let unconfirmed = resolve(candidates(&input(false)), |_| false).unwrap();
+        assert_eq!(unconfirmed.path, "/home/me/go/bin/mtlog-lsp");
+
+        let trace = trace(candidates(&input(true)), |_| Err("could not be started: not found".to_string()));
+        assert_eq!(trace[0].1, Outcome::Skipped("could not be started: not found".to_string()));
+        assert_eq!(selected(trace), None);
+        assert_eq!(
+            resolve(candidates(&input(true)), |_| true).map(|c| c.source),
+            Some(CandidateSource::HomeGoBin)
+        );
+    }
+
+    /// The explicit path still wins over everything else.
+    #[test]
+    fn test_explicit_path_first() {
+        let extra = vec!["tools".to_string()];
+        let input = DiscoveryInput {
+            explicit_path: Some("/opt/mtlog-lsp"),
+            extra_bin_dirs: &extra,
+            worktree_root: "/work",
+            ..Default::default()
+        };
+
+        let resolved = resolve(candidates(&input), |_| true).unwrap();
+
+        assert_eq!(resolved.path, "/opt/mtlog-lsp");
+        assert_eq!(resolved.source, CandidateSource::ExplicitPath);
+    }
+}
@@ -0,0 +1,280 @@
+//! Download fallback: fetches a prebuilt mtlog-lsp from the latest GitHub
+//! release, or the one `lspVersion` pins, into the extension work dir.
+//!
+//! The work dir is shared by every worktree, so a pinned release is kept in
+//! a directory of its own, `mtlog-lsp-download/<tag>/`. Worktrees pinning
+//! different releases then never overwrite each other's binary.
+//!
+//! Assets follow the naming used for the mtlog-analyzer release binaries,
+//! `mtlog-lsp-<os>-<arch>` with `.exe` on Windows. A release without an asset
+//! for the current platform is reported as unavailable so `install.method =
+//! "auto"` can move on to `go install`.
+//!
+//! Zed's download API cannot resume, so a download that arrives shorter
+//! than the length the server announced is deleted and fetched again from
+//! the start, and never executed or hash-checked.
+
+use std::fmt;
+
+use zed_extension_api::{
+    self as zed,
+    serde_json::Value,
+    http_client::{HttpMethod, HttpRequest, RedirectPolicy},
+    Architecture, DownloadedFileType, GithubReleaseOptions, Os,
+};
+
+use crate::discovery::{self, BINARY_NAME};
+use crate::install::StepError;
+
+/// Repository whose releases carry the binaries.
+const REPOSITORY: &str = "willibrandon/mtlog";
+
+/// Directory under the extension work dir holding the downloaded binary.
+const DOWNLOAD_DIR: &str = "mtlog-lsp-download";
+
+/// `lspVersion` value that follows the latest release, as when unset.
+const LATEST: &str = "latest";
+
+/// Downloads tried before an incomplete one is reported as a failure.
+const ATTEMPTS: u32 = 2;
+
+/// How a downloaded file compares with the length announced for it.
+#[derive(Debug, PartialEq, Eq)]
+enum Completeness {
+    Complete,
+    /// No length was announced, so only an empty file can be told apart.
+    Unknown,
+    Empty,
+    Incomplete { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for Completeness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Completeness::Complete => write!(f, "complete"),
+            Completeness::Unknown => write!(f, "length unknown"),
+            Completeness::Empty => write!(f, "the file is empty"),
+            Completeness::Incomplete { expected, actual } => write!(f, "got {actual} of {expected} bytes"),
+        }
+    }
+}
+
+/// Compares the size of a download with the announced `Content-Length`.
+fn completeness(expected: Option<u64>, actual: u64) -> Completeness {
+    match expected {
+        _ if actual == 0 => Completeness::Empty,
+        None => Completeness::Unknown,
+        Some(expected) if actual == expected => Completeness::Complete,
+        Some(expected) => Completeness::Incomplete { expected, actual },
+    }
+}
+
+/// The `Content-Length` among `headers`, matched ignoring case.
+fn content_length(headers: &[(String, String)]) -> Option<u64> {
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+}
+
+/// Asks for the length of the asset at `url`; `None` when the server does
+/// not say.
+fn expected_size(url: &str) -> Option<u64> {
+    let request = HttpRequest::builder()
+        .method(HttpMethod::Head)
+        .url(url)
+        .redirect_policy(RedirectPolicy::FollowAll)
+        .build()
+        .ok()?;
+    content_length(&request.fetch().ok()?.headers)
+}
+
+/// Platform name in Go's `<os>-<arch>` form, e.g. `linux-amd64`.
+pub(crate) fn platform_name(os: Os, arch: Architecture) -> String {
+    let os_name = match os {
+        Os::Mac => "darwin",
+        Os::Linux => "linux",
+        Os::Windows => "windows",
+    };
+    let arch_name = match arch {
+        Architecture::Aarch64 => "arm64",
+        Architecture::X86 => "386",
+        Architecture::X8664 => "amd64",
+    };
+    format!("{os_name}-{arch_name}")
+}
+
+/// Returns the release tag `lspVersion` pins, such as `v0.10.0`, or `Ok(None)`
+/// when the setting is absent or `"latest"`. The leading `v` is optional.
+pub(crate) fn pinned_tag(setting: Option<&Value>) -> Result<Option<String>, String> {
+    let Some(setting) = setting else {
+        return Ok(None);
+    };
+    let expected = || format!("expected \"{LATEST}\" or a release version such as \"0.10.0\"");
+    let version = setting.as_str().map(str::trim).ok_or_else(expected)?;
+    if version.eq_ignore_ascii_case(LATEST) {
+        return Ok(None);
+    }
+    let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+    let (core, pre_release) = version.split_once('-').unwrap_or((version, ""));
+    let core_is_valid = core.split('.').count() == 3
+        && core.split('.').all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+    let pre_release_is_valid = pre_release.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'.' || b == b'-');
+    if !core_is_valid || !pre_release_is_valid || version.ends_with('-') {
+        return Err(expected());
+    }
+    Ok(Some(format!("v{version}")))
+}
+
+/// Name of the release asset for the given platform.
+pub(crate) fn asset_name(os: Os, arch: Architecture) -> String {
+    format!("{BINARY_NAME}-{}{}", platform_name(os, arch), exe_suffix(os))
+}
+
+/// Path of the binary inside the work dir, relative to it. A pinned `tag`
+/// gets its own directory; the latest release keeps the original location.
+fn relative_binary_path(os: Os, tag: Option<&str>) -> String {
+    match tag {
+        Some(tag) => format!("{DOWNLOAD_DIR}/{tag}/{BINARY_NAME}{}", exe_suffix(os)),
+        None => format!("{DOWNLOAD_DIR}/{BINARY_NAME}{}", exe_suffix(os)),
+    }
+}
+
+/// Absolute path a download of `tag`, or of the latest release, puts the binary at.
+pub(crate) fn binary_path(work_dir: &str, os: Os, tag: Option<&str>) -> String {
+    discovery::join(work_dir, &relative_binary_path(os, tag))
+}
+
+fn exe_suffix(os: Os) -> &'static str {
+    match os {
+        Os::Windows => ".exe",
+        Os::Mac | Os::Linux => "",
+    }
+}
+
+/// Downloads the binary for the current platform from the release `tag`, or
+/// the latest one, returning its path.
+pub(crate) fn download(work_dir: &str, tag: Option<&str>) -> Result<String, StepError> {
+    let (os, arch) = zed::current_platform();
+    let name = asset_name(os, arch);
+
+    let release = match tag {
+        Some(tag) => zed::github_release_by_tag_name(REPOSITORY, tag)
+            .map_err(|err| StepError::Failed(format!("could not fetch release {tag}: {err}")))?,
+        None => zed::latest_github_release(
+            REPOSITORY,
+            GithubReleaseOptions {
+                require_assets: true,
+                pre_release: false,
+            },
+        )
+        .map_err(|err| StepError::Failed(format!("could not fetch the latest release: {err}")))?,
+    };
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| StepError::Unavailable(format!("release {} has no {name} asset", release.version)))?;
+
+    let relative_path = relative_binary_path(os, tag);
+    let expected = expected_size(&asset.download_url);
+    for attempt in 1..=ATTEMPTS {
+        zed::download_file(&asset.download_url, &relative_path, DownloadedFileType::Uncompressed)
+            .map_err(|err| StepError::Failed(format!("downloading {name} failed: {err}")))?;
+        // The work dir is the current directory, so the relative path can be read
+        let actual = std::fs::metadata(&relative_path).map_or(0, |metadata| metadata.len());
+        match completeness(expected, actual) {
+            Completeness::Complete | Completeness::Unknown => break,
+            incomplete => {
+                let _ = std::fs::remove_file(&relative_path);
+                if attempt == ATTEMPTS {
+                    return Err(StepError::Failed(format!(
+                        "downloading {name} was interrupted ({incomplete}) {ATTEMPTS} times; try again later"
+                    )));
+                }
+                eprintln!("mtlog-analyzer: downloading {name} was interrupted ({incomplete}); starting over");
+            }
+        }
+    }
+    zed::make_file_executable(&relative_path).map_err(StepError::Failed)?;
+
+    Ok(binary_path(work_dir, os, tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    #[test]
+    fn test_asset_name() {
+        assert_eq!(asset_name(Os::Linux, Architecture::X8664), "mtlog-lsp-linux-amd64");
+        assert_eq!(asset_name(Os::Mac, Architecture::Aarch64), "mtlog-lsp-darwin-arm64");
+        assert_eq!(asset_name(Os::Windows, Architecture::X86), "mtlog-lsp-windows-386.exe");
+    }
+
+    #[test]
+    fn test_completeness() {
+        assert_eq!(completeness(Some(8_200_000), 8_200_000), Completeness::Complete);
+        assert_eq!(
+            completeness(Some(8_200_000), 3_145_728),
+            Completeness::Incomplete { expected: 8_200_000, actual: 3_145_728 }
+        );
+        assert_eq!(
+            completeness(Some(8_200_000), 8_300_000),
+            Completeness::Incomplete { expected: 8_200_000, actual: 8_300_000 }
+        );
+        assert_eq!(completeness(None, 8_200_000), Completeness::Unknown);
+        assert_eq!(completeness(None, 0), Completeness::Empty);
+        assert_eq!(completeness(Some(8_200_000), 0), Completeness::Empty);
+    }
+
+    #[test]
+    fn test_content_length() {
+        let headers = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+        };
+
+        let announced = headers(&[("Content-Type", "application/octet-stream"), ("content-length", " 8200000")]);
+
+        assert_eq!(content_length(&announced), Some(8_200_000));
+        assert_eq!(content_length(&headers(&[("Content-Length", "unknown")])), None);
+        assert_eq!(content_length(&headers(&[])), None);
+    }
+
+    #[test]
+    fn test_binary_path() {
+        assert_eq!(binary_path("/ext/work", Os::Linux, None), "/ext/work/mtlog-lsp-download/mtlog-lsp");
+        assert_eq!(binary_path("/ext/work", Os::Windows, None), "/ext/work/mtlog-lsp-download/mtlog-lsp.exe");
+        assert_eq!(
+            binary_path("/ext/work", Os::Windows, Some("v0.10.0")),
+            "/ext/work/mtlog-lsp-download/v0.10.0/mtlog-lsp.exe"
+        );
+    }
+
+    #[test]
+    fn test_pinned_tag() {
+        assert_eq!(pinned_tag(None), Ok(None));
+        assert_eq!(pinned_tag(Some(&json!("latest"))), Ok(None));
+        assert_eq!(pinned_tag(Some(&json!("0.10.0"))), Ok(Some("v0.10.0".to_string())));
+        assert_eq!(pinned_tag(Some(&json!(" v0.11.0-rc.1 "))), Ok(Some("v0.11.0-rc.1".to_string())));
+
+        for invalid in [json!(""), json!("0.10"), json!("v0.10.x"), json!("0.10.0-"), json!("../0.10.0"), json!(10)] {
+            assert!(pinned_tag(Some(&invalid)).is_err(), "{invalid}");
+        }
+    }
+
+    /// Worktrees pinning different releases download to, and resolve, different paths.
+    #[test]
+    fn test_pinned_versions_kept_apart() {
+        let first = pinned_tag(Some(&json!("0.9.0"))).unwrap();
+        let second = pinned_tag(Some(&json!("v0.10.0"))).unwrap();
+
+        let first_path = binary_path("/ext/work", Os::Linux, first.as_deref());
+        let second_path = binary_path("/ext/work", Os::Linux, second.as_deref());
+
+        assert_eq!(first_path, "/ext/work/mtlog-lsp-download/v0.9.0/mtlog-lsp");
+        assert_eq!(second_path, "/ext/work/mtlog-lsp-download/v0.10.0/mtlog-lsp");
+        assert_ne!(first_path, binary_path("/ext/work", Os::Linux, None));
+    }
+}
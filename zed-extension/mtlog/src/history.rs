@@ -0,0 +1,170 @@
+//! Analyses the extension has run itself, for `/mtlog-recent`.
+//!
+//! mtlog-lsp does not share what it analyzes with extensions, so the history
+//! only holds runs the extension started and timed, such as `/mtlog-sarif`
+//! and `/mtlog-preview-suppress`.
+
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+/// Number of analyses kept; older ones are dropped first.
+pub(crate) const CAPACITY: usize = 50;
+
+/// One analyzer run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Analysis {
+    /// What was analyzed, such as `./...` for the whole project.
+    pub(crate) target: String,
+    /// Path of the analyzer binary that ran.
+    pub(crate) analyzer: String,
+    pub(crate) elapsed: Duration,
+    pub(crate) findings: usize,
+}
+
+/// The most recent analyses, newest last.
+#[derive(Debug, Default)]
+pub(crate) struct AnalysisHistory {
+    analyses: VecDeque<Analysis>,
+}
+
+impl AnalysisHistory {
+    /// Records `analysis`, dropping the oldest one when full.
+    pub(crate) fn record(&mut self, analysis: Analysis) {
+        if self.analyses.len() == CAPACITY {
+            self.analyses.pop_front();
+        }
+        self.analyses.push_back(analysis);
+    }
+
+    /// Returns the analyzer binaries used, most recently used first.
+    pub(crate) fn analyzers(&self) -> Vec<&str> {
+        let mut analyzers: Vec<&str> = Vec::new();
+        for analysis in self.analyses.iter().rev() {
+            if !analyzers.contains(&analysis.analyzer.as_str()) {
+                analyzers.push(&analysis.analyzer);
+            }
+        }
+        analyzers
+    }
+
+    /// Returns up to `count` analyses, newest first.
+    pub(crate) fn latest(&self, count: usize) -> impl Iterator<Item = &Analysis> {
+        self.analyses.iter().rev().take(count)
+    }
+}
+
+/// Worktrees given their `firstRunReport` analysis this session. A
+/// worktree is claimed when its server starts, and analyzed later, so the
+/// analysis never delays that start.
+#[derive(Debug, Default)]
+pub(crate) struct FirstRuns {
+    reported: HashSet<u64>,
+    /// Claimed worktrees whose analysis has not run yet.
+    pending: HashSet<u64>,
+}
+
+impl FirstRuns {
+    /// Returns true the first time it is called for `worktree_id`, queueing
+    /// its analysis, and false after that.
+    pub(crate) fn claim(&mut self, worktree_id: u64) -> bool {
+        let claimed = self.reported.insert(worktree_id);
+        if claimed {
+            self.pending.insert(worktree_id);
+        }
+        claimed
+    }
+
+    /// Returns true once for each claimed worktree, when its analysis is due.
+    pub(crate) fn take_pending(&mut self, worktree_id: u64) -> bool {
+        self.pending.remove(&worktree_id)
+    }
+
+    /// Queues the analysis of a claimed worktree again after it failed.
+    pub(crate) fn retry(&mut self, worktree_id: u64) {
+        if self.reported.contains(&worktree_id) {
+            self.pending.insert(worktree_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analysis(target: &str) -> Analysis {
+        Analysis {
+            target: target.to_string(),
+            analyzer: "/go/bin/mtlog-analyzer".to_string(),
+            elapsed: Duration::from_millis(10),
+            findings: 0,
+        }
+    }
+
+    /// The newest analyses come first, and the oldest are dropped past capacity.
+    #[test]
+    fn test_latest_and_capacity() {
+        let mut history = AnalysisHistory::default();
+        for i in 0..CAPACITY + 2 {
+            history.record(analysis(&i.to_string()));
+        }
+
+        let targets: Vec<&str> = history.latest(3).map(|a| a.target.as_str()).collect();
+        assert_eq!(targets, vec!["51", "50", "49"]);
+        assert_eq!(history.latest(usize::MAX).count(), CAPACITY);
+        assert_eq!(history.latest(usize::MAX).last().unwrap().target, "2");
+    }
+
+    #[test]
+    fn test_analyzers() {
+        let mut history = AnalysisHistory::default();
+        for analyzer in ["/go/bin/mtlog-analyzer", "/tmp/a/mtlog-analyzer", "/go/bin/mtlog-analyzer"] {
+            history.record(Analysis {
+                analyzer: analyzer.to_string(),
+                ..analysis("./...")
+            });
+        }
+
+        assert_eq!(history.analyzers(), vec!["/go/bin/mtlog-analyzer", "/tmp/a/mtlog-analyzer"]);
+    }
+
+    /// Each worktree is reported once, however often its server restarts.
+    #[test]
+    fn test_first_runs_once_per_worktree() {
+        let mut first_runs = FirstRuns::default();
+
+        assert!(first_runs.claim(1));
+        assert!(!first_runs.claim(1));
+        assert!(first_runs.claim(2));
+        assert!(!first_runs.claim(2));
+        assert!(!first_runs.claim(1));
+    }
+
+    /// A claimed worktree's analysis is handed out once, and only once claimed.
+    #[test]
+    fn test_first_runs_pending() {
+        let mut first_runs = FirstRuns::default();
+        assert!(!first_runs.take_pending(1));
+
+        first_runs.claim(1);
+        first_runs.claim(1);
+
+        assert!(first_runs.take_pending(1));
+        assert!(!first_runs.take_pending(1));
+        assert!(!first_runs.take_pending(2));
+    }
+
+    /// A failed analysis is handed out again, but never for an unclaimed worktree.
+    #[test]
+    fn test_first_runs_retry() {
+        let mut first_runs = FirstRuns::default();
+        first_runs.claim(1);
+        assert!(first_runs.take_pending(1));
+
+        first_runs.retry(1);
+        first_runs.retry(2);
+
+        assert!(first_runs.take_pending(1));
+        assert!(!first_runs.take_pending(2));
+        assert!(!first_runs.claim(1));
+    }
+}
@@ -0,0 +1,118 @@
+//! Degraded operation on Zed hosts that turn some host calls away.
+//!
+//! An extension built against an API version the host does not provide is
+//! not loaded at all, so missing methods cannot be detected at runtime.
+//! What does vary is whether a call that exists is allowed: hosts let users
+//! withhold capabilities such as `process:exec` or `download_file`, and
+//! refuse calls that need them with an error of their own wording. Those
+//! refusals are recognized here by that wording alone, logged once, and the
+//! feature is skipped for the rest of the session, so everything that does
+//! not need it keeps working. Any other error, even one that reads like a
+//! refusal, is an ordinary failure of the call.
+
+use std::collections::BTreeMap;
+
+/// Host calls the extension can do without.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Feature {
+    /// Running processes: `--version` probes, `go install`, and the analyses
+    /// behind slash commands, which report the host's error themselves.
+    ProcessExec,
+    /// Downloading release assets.
+    Download,
+}
+
+impl Feature {
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Feature::ProcessExec => "running processes",
+            Feature::Download => "downloading files",
+        }
+    }
+
+    /// What the extension does without the feature.
+    fn fallback(self) -> &'static str {
+        match self {
+            Feature::ProcessExec => {
+                "binaries are used without a --version check and go install is skipped; slash commands that run go will fail"
+            }
+            Feature::Download => "mtlog-lsp is installed with go install or must be on PATH",
+        }
+    }
+}
+
+/// How Zed words a denied call, around the capability it needed:
+/// `capability for process:exec go ["version"] is not granted by the extension host`.
+const DENIED_PREFIX: &str = "capability for ";
+const DENIED_SUFFIX: &str = " is not granted by the extension host";
+
+/// Whether `err` says the host refused the call, as opposed to the call
+/// itself failing, like a download that timed out or an unsupported
+/// operation in the OS.
+pub(crate) fn is_refusal(err: &str) -> bool {
+    err.find(DENIED_PREFIX)
+        .is_some_and(|start| err[start + DENIED_PREFIX.len()..].contains(DENIED_SUFFIX))
+}
+
+/// Features the host refused this session, with the error it gave.
+#[derive(Debug, Default)]
+pub(crate) struct HostFeatures {
+    unavailable: BTreeMap<Feature, String>,
+}
+
+impl HostFeatures {
+    pub(crate) fn is_available(&self, feature: Feature) -> bool {
+        !self.unavailable.contains_key(&feature)
+    }
+
+    /// Records the error from a call that uses `feature`. Returns the line to
+    /// log the first time the host refuses it; ordinary failures and later
+    /// refusals return `None`.
+    pub(crate) fn observe(&mut self, feature: Feature, err: &str) -> Option<String> {
+        if !is_refusal(err) || !self.is_available(feature) {
+            return None;
+        }
+        self.unavailable.insert(feature, err.to_string());
+        Some(format!(
+            "this Zed does not allow {} ({err}); {}",
+            feature.description(),
+            feature.fallback()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_refusal() {
+        assert!(is_refusal("capability for process:exec go [\"version\"] is not granted by the extension host"));
+        assert!(is_refusal(
+            "downloading mtlog-lsp-linux-amd64 failed: capability for download_file \
+             https://github.com/willibrandon/mtlog/releases/x is not granted by the extension host"
+        ));
+        assert!(!is_refusal("Operation not supported (os error 95)"));
+        assert!(!is_refusal("unsupported operation"));
+        assert!(!is_refusal("permission denied: not allowed"));
+        assert!(!is_refusal("No such file or directory (os error 2)"));
+        assert!(!is_refusal("downloading mtlog-lsp-linux-amd64 failed: timed out"));
+    }
+
+    /// A refusal is logged once and turns the feature off; other errors change nothing.
+    #[test]
+    fn test_observe() {
+        let mut host = HostFeatures::default();
+
+        assert_eq!(host.observe(Feature::Download, "connection reset"), None);
+        assert!(host.is_available(Feature::Download));
+
+        let denied = "capability for download_file https://github.com/x is not granted by the extension host";
+        let logged = host.observe(Feature::Download, denied).unwrap();
+        assert!(logged.starts_with(&format!("this Zed does not allow downloading files ({denied})")));
+        assert!(!host.is_available(Feature::Download));
+        assert_eq!(host.observe(Feature::Download, denied), None);
+
+        assert!(host.is_available(Feature::ProcessExec));
+    }
+}
@@ -0,0 +1,425 @@
+//! Installation fallbacks for a missing mtlog-lsp binary.
+//!
+//! `install.method` selects what is attempted when discovery finds nothing:
+//! nothing at all (the default), a release download, `go install`, or both in
+//! turn. The decision tree lives in [`attempt`]; the download itself is in
+//! [`crate::download`].
+//!
+//! By default `go install` puts the binary into a directory under the extension's
+//! work dir rather than the user's `$GOBIN`, so the fallback never touches a
+//! directory the user manages. Discovery checks that directory on later
+//! launches. Setting `install.gobin` to `"system"` installs into the user's
+//! normal `$GOBIN` instead, and any other value names the directory to use.
+//!
+//! A release pinned with `lspVersion` is installed as `@<tag>` instead of
+//! `@latest`, and into a directory of its own under the default one.
+
+use std::fmt;
+
+use zed_extension_api::{process::Command, serde_json::Value};
+
+use crate::decode::decode_output;
+use crate::discovery::{self, Candidate, CandidateSource, BINARY_NAME};
+
+/// Package installed by the fallback, without the version.
+const PACKAGE: &str = "github.com/willibrandon/mtlog/cmd/mtlog-lsp";
+
+/// Directory under the extension work dir used when `install.gobin` is unset.
+const ISOLATED_DIR: &str = "go-bin";
+
+/// `install.gobin` value selecting the user's normal `$GOBIN`.
+const SYSTEM: &str = "system";
+
+/// What to attempt when no binary is found, from the `install.method` setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Method {
+    /// Report the missing binary without installing anything.
+    #[default]
+    None,
+    Download,
+    GoInstall,
+    /// Download, then `go install` if no release asset matches the platform.
+    Auto,
+}
+
+impl Method {
+    const ALL: &'static [Method] = &[Method::None, Method::Download, Method::GoInstall, Method::Auto];
+
+    /// Parses the `install.method` setting; unset means the default.
+    pub(crate) fn from_setting(setting: Option<&Value>) -> Result<Self, String> {
+        let Some(setting) = setting else {
+            return Ok(Method::default());
+        };
+        setting
+            .as_str()
+            .and_then(|given| Self::ALL.iter().find(|method| method.as_str().eq_ignore_ascii_case(given)))
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<String> = Self::ALL.iter().map(|method| format!("\"{}\"", method.as_str())).collect();
+                format!(
+                    "ignoring invalid install.method {setting}: expected one of {}; using \"{}\"",
+                    names.join(", "),
+                    Method::default().as_str()
+                )
+            })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Method::None => "none",
+            Method::Download => "download",
+            Method::GoInstall => "go-install",
+            Method::Auto => "auto",
+        }
+    }
+}
+
+/// A single installation attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    Download,
+    GoInstall,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Step::Download => "download",
+            Step::GoInstall => "go install",
+        })
+    }
+}
+
+/// Why a step did not produce a binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum StepError {
+    /// The step cannot apply here, e.g. no asset for this platform or no `go`.
+    Unavailable(String),
+    /// The step applied but failed.
+    Failed(String),
+}
+
+impl StepError {
+    fn reason(&self) -> &str {
+        match self {
+            StepError::Unavailable(reason) | StepError::Failed(reason) => reason,
+        }
+    }
+}
+
+/// The steps that were attempted, when none of them succeeded.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct InstallFailure {
+    method: Method,
+    attempts: Vec<(Step, StepError)>,
+}
+
+impl fmt::Display for InstallFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.attempts.is_empty() {
+            return write!(
+                f,
+                "Automatic installation is off; set install.method to \"download\", \"go-install\" or \"auto\" \
+                 to let the extension install mtlog-lsp."
+            );
+        }
+        write!(f, "Automatic installation (install.method \"{}\") failed:", self.method.as_str())?;
+        for (step, error) in &self.attempts {
+            write!(f, "\n- {step}: {}", error.reason())?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the steps `method` calls for until one yields a binary.
+///
+/// `auto` only moves on to `go install` when the download is unavailable for
+/// this platform; a download that fails outright is reported as is.
+pub(crate) fn attempt<T>(
+    method: Method,
+    mut run: impl FnMut(Step) -> Result<T, StepError>,
+) -> Result<T, InstallFailure> {
+    let steps: &[Step] = match method {
+        Method::None => &[],
+        Method::Download => &[Step::Download],
+        Method::GoInstall => &[Step::GoInstall],
+        Method::Auto => &[Step::Download, Step::GoInstall],
+    };
+
+    let mut attempts = Vec::new();
+    for &step in steps {
+        match run(step) {
+            Ok(found) => return Ok(found),
+            Err(error) => {
+                let fall_through = matches!(error, StepError::Unavailable(_));
+                attempts.push((step, error));
+                if !fall_through {
+                    break;
+                }
+            }
+        }
+    }
+    Err(InstallFailure { method, attempts })
+}
+
+/// The extension's work dir, where downloads and isolated installs live.
+pub(crate) fn work_dir() -> String {
+    std::env::current_dir()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Where `go install` puts the binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Gobin {
+    /// A directory owned by the extension, passed to `go install` as `GOBIN`.
+    Isolated(String),
+    /// Whatever `GOBIN` the user's environment already selects.
+    System,
+}
+
+impl Gobin {
+    /// Resolves the `install.gobin` setting. Relative directories are taken
+    /// relative to the extension work dir. Without a setting, a pinned `tag`
+    /// gets its own directory so versions do not replace each other.
+    pub(crate) fn from_setting(setting: Option<&str>, work_dir: &str, tag: Option<&str>) -> Self {
+        match setting.map(str::trim) {
+            Some(value) if value.eq_ignore_ascii_case(SYSTEM) => Gobin::System,
+            Some(dir) if !dir.is_empty() => Gobin::Isolated(discovery::resolve_dir(dir, work_dir)),
+            _ => {
+                let dir = discovery::join(work_dir, ISOLATED_DIR);
+                Gobin::Isolated(tag.map_or_else(|| dir.clone(), |tag| discovery::join(&dir, tag)))
+            }
+        }
+    }
+
+    /// Path the installed binary will have, when it is known up front.
+    pub(crate) fn binary_path(&self) -> Option<String> {
+        match self {
+            Gobin::Isolated(dir) => Some(discovery::join(dir, BINARY_NAME)),
+            Gobin::System => None,
+        }
+    }
+}
+
+/// The package to install: the pinned release `tag`, or the latest one.
+fn package(tag: Option<&str>) -> String {
+    format!("{PACKAGE}@{}", tag.unwrap_or("latest"))
+}
+
+/// Builds the `go install` command for `tag`, running under the shell
+/// environment with `GOBIN` replaced when installing into an isolated directory.
+pub(crate) fn command(go: &str, gobin: &Gobin, tag: Option<&str>, shell_env: &[(String, String)]) -> Command {
+    let mut env: Vec<(String, String)> = shell_env.to_vec();
+    if let Gobin::Isolated(dir) = gobin {
+        env.retain(|(key, _)| key != "GOBIN");
+        env.push(("GOBIN".to_string(), dir.clone()));
+    }
+
+    Command::new(go).args(["install".to_string(), package(tag)]).envs(env)
+}
+
+/// The binary a successful `go install` into `gobin` produced. An isolated
+/// install is at a known path; a system one is looked up again with `find`.
+pub(crate) fn installed_binary(
+    gobin: &Gobin,
+    find: impl FnOnce() -> Option<Candidate>,
+) -> Result<Candidate, StepError> {
+    match gobin.binary_path() {
+        Some(path) => Ok(Candidate {
+            path,
+            source: CandidateSource::Installed,
+            needs_check: false,
+        }),
+        // Installed into the user's GOBIN, which discovery already covers
+        None => find().ok_or_else(|| {
+            StepError::Failed("go install succeeded but mtlog-lsp is still not on PATH or in $GOBIN".to_string())
+        }),
+    }
+}
+
+/// Runs `go install`, returning the reason when it does not succeed.
+pub(crate) fn run(mut command: Command) -> Result<(), String> {
+    let output = command.output()?;
+    if output.status == Some(0) {
+        return Ok(());
+    }
+
+    let stderr = decode_output(&output.stderr, "go install");
+    let detail = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+    let status = output.status.map_or_else(|| "a signal".to_string(), |code| format!("status {code}"));
+    Err(format!("go install exited with {status}: {}", detail.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    fn run_log(method: Method, results: &[(Step, Result<&str, StepError>)]) -> (Vec<Step>, Result<String, InstallFailure>) {
+        let mut ran = Vec::new();
+        let result = attempt(method, |step| {
+            ran.push(step);
+            let (_, result) = results.iter().find(|(s, _)| *s == step).expect("unexpected step");
+            result.clone().map(str::to_string)
+        });
+        (ran, result)
+    }
+
+    #[test]
+    fn test_method_from_setting() {
+        assert_eq!(Method::from_setting(None), Ok(Method::None));
+        assert_eq!(Method::from_setting(Some(&json!("Go-Install"))), Ok(Method::GoInstall));
+        assert_eq!(Method::from_setting(Some(&json!("auto"))), Ok(Method::Auto));
+        assert!(Method::from_setting(Some(&json!("brew"))).unwrap_err().contains("\"auto\""));
+        assert!(Method::from_setting(Some(&json!(true))).is_err());
+    }
+
+    /// "none" attempts nothing and explains how to turn installation on.
+    #[test]
+    fn test_none_attempts_nothing() {
+        let (ran, result) = run_log(Method::None, &[]);
+
+        assert!(ran.is_empty());
+        assert!(result.unwrap_err().to_string().contains("install.method"));
+    }
+
+    /// "auto" falls back to go install when no asset matches the platform.
+    #[test]
+    fn test_auto_falls_back_when_no_asset() {
+        let (ran, result) = run_log(
+            Method::Auto,
+            &[
+                (Step::Download, Err(StepError::Unavailable("no asset".to_string()))),
+                (Step::GoInstall, Ok("/go-bin/mtlog-lsp")),
+            ],
+        );
+
+        assert_eq!(ran, vec![Step::Download, Step::GoInstall]);
+        assert_eq!(result.unwrap(), "/go-bin/mtlog-lsp");
+    }
+
+    /// "auto" stops after a download that fails for another reason.
+    #[test]
+    fn test_auto_stops_after_failed_download() {
+        let (ran, result) = run_log(
+            Method::Auto,
+            &[(Step::Download, Err(StepError::Failed("connection reset".to_string())))],
+        );
+
+        assert_eq!(ran, vec![Step::Download]);
+        assert!(result.unwrap_err().to_string().contains("connection reset"));
+    }
+
+    /// The error names every attempted method and why it failed.
+    #[test]
+    fn test_failure_lists_attempts() {
+        let (_, result) = run_log(
+            Method::Auto,
+            &[
+                (Step::Download, Err(StepError::Unavailable("release v0.9.0 has no mtlog-lsp-linux-amd64 asset".to_string()))),
+                (Step::GoInstall, Err(StepError::Unavailable("go not found on PATH".to_string()))),
+            ],
+        );
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Automatic installation (install.method \"auto\") failed:\n\
+             - download: release v0.9.0 has no mtlog-lsp-linux-amd64 asset\n\
+             - go install: go not found on PATH"
+        );
+    }
+
+    /// Single methods run only their own step.
+    #[test]
+    fn test_single_methods() {
+        let steps = [(Step::Download, Ok("d")), (Step::GoInstall, Ok("g"))];
+
+        assert_eq!(run_log(Method::Download, &steps).0, vec![Step::Download]);
+        assert_eq!(run_log(Method::GoInstall, &steps).0, vec![Step::GoInstall]);
+    }
+
+    fn env_value<'a>(command: &'a Command, key: &str) -> Vec<&'a str> {
+        command
+            .env
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Without a setting the binary goes into the extension work dir.
+    #[test]
+    fn test_default_is_isolated() {
+        let gobin = Gobin::from_setting(None, "/ext/work", None);
+
+        assert_eq!(gobin, Gobin::Isolated("/ext/work/go-bin".to_string()));
+        assert_eq!(gobin.binary_path().as_deref(), Some("/ext/work/go-bin/mtlog-lsp"));
+    }
+
+    /// A pinned release gets its own default directory; a chosen one is kept.
+    #[test]
+    fn test_pinned_gobin() {
+        assert_eq!(
+            Gobin::from_setting(None, "/ext/work", Some("v0.10.0")).binary_path().as_deref(),
+            Some("/ext/work/go-bin/v0.10.0/mtlog-lsp")
+        );
+        assert_eq!(
+            Gobin::from_setting(Some("/opt/tools"), "/ext/work", Some("v0.10.0")),
+            Gobin::Isolated("/opt/tools".to_string())
+        );
+        assert_eq!(Gobin::from_setting(Some("system"), "/ext/work", Some("v0.10.0")), Gobin::System);
+    }
+
+    #[test]
+    fn test_from_setting() {
+        assert_eq!(Gobin::from_setting(Some("System"), "/ext/work", None), Gobin::System);
+        assert_eq!(
+            Gobin::from_setting(Some("/opt/tools"), "/ext/work", None),
+            Gobin::Isolated("/opt/tools".to_string())
+        );
+        assert_eq!(
+            Gobin::from_setting(Some("tools"), "/ext/work", None),
+            Gobin::Isolated("/ext/work/tools".to_string())
+        );
+        assert_eq!(
+            Gobin::from_setting(Some("  "), "/ext/work", None),
+            Gobin::Isolated("/ext/work/go-bin".to_string())
+        );
+        assert_eq!(Gobin::System.binary_path(), None);
+    }
+
+    /// An isolated install replaces the user's GOBIN and keeps the rest of the environment.
+    #[test]
+    fn test_isolated_install_env() {
+        let shell_env = vec![
+            ("GOBIN".to_string(), "/home/me/bin".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ];
+
+        let command = command("/usr/bin/go", &Gobin::Isolated("/ext/work/go-bin".to_string()), None, &shell_env);
+
+        assert_eq!(command.command, "/usr/bin/go");
+        assert_eq!(command.args, vec!["install", "github.com/willibrandon/mtlog/cmd/mtlog-lsp@latest"]);
+        assert_eq!(env_value(&command, "GOBIN"), vec!["/ext/work/go-bin"]);
+        assert_eq!(env_value(&command, "PATH"), vec!["/usr/bin"]);
+    }
+
+    /// A system install passes the shell environment through untouched.
+    #[test]
+    fn test_system_install_env() {
+        let shell_env = vec![("GOBIN".to_string(), "/home/me/bin".to_string())];
+
+        let command = command("go", &Gobin::System, None, &shell_env);
+
+        assert_eq!(command.env, shell_env);
+    }
+
+    /// A pinned release is installed at its tag.
+    #[test]
+    fn test_pinned_install_package() {
+        let command = command("go", &Gobin::System, Some("v0.10.0"), &[]);
+
+        assert_eq!(command.args, vec!["install", "github.com/willibrandon/mtlog/cmd/mtlog-lsp@v0.10.0"]);
+    }
+}
@@ -0,0 +1,430 @@
+//! Verification of the resolved binary against a pinned SHA-256 hash.
+//!
+//! `binary.sha256` pins the expected hash, either as one string or as a map
+//! from platform (`linux-amd64`, `darwin-arm64`, ...) to hash. When it is set
+//! the binary is hashed before it is first run and the launch is refused on
+//! a mismatch.
+//!
+//! Zed's sandbox only lets the extension open files in its own work dir, so
+//! binaries it installed there are read in chunks, and their hashes cached by
+//! path, size, and modification time so an unchanged binary is only hashed
+//! once per session. Any other binary is hashed by `sha256sum`, or
+//! `shasum -a 256` where that is missing, on every launch, since the
+//! extension cannot tell whether the file has changed since.
+//!
+//! Verification fails closed: a binary that cannot be read, or a pin that
+//! has no entry for the current platform, also refuses the launch.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::time::SystemTime;
+
+use zed_extension_api::{process::Command, serde_json::Value};
+
+use crate::decode::decode_output;
+
+/// Size of the chunks the binary is read in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Programs that hash binaries outside the work dir, with the arguments
+/// that precede the path, in the order they are tried.
+const HASH_COMMANDS: [(&str, &[&str]); 2] = [("sha256sum", &[]), ("shasum", &["-a", "256"])];
+
+/// Returns the hash pinned for `platform`, or `None` when nothing is pinned.
+pub(crate) fn expected_hash(setting: Option<&Value>, platform: &str) -> Result<Option<String>, String> {
+    let hash = match setting {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(hash)) => hash,
+        Some(Value::Object(per_platform)) => match per_platform.get(platform) {
+            Some(Value::String(hash)) => hash,
+            Some(other) => return Err(format!("binary.sha256 entry for {platform} is not a string: {other}")),
+            None => return Err(format!("binary.sha256 has no entry for this platform ({platform})")),
+        },
+        Some(other) => return Err(format!("binary.sha256 must be a string or a map of platforms: {other}")),
+    };
+
+    let hash = hash.trim().to_ascii_lowercase();
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("binary.sha256 \"{hash}\" is not a 64-digit hex SHA-256 hash"));
+    }
+    Ok(Some(hash))
+}
+
+/// What identifies an unchanged file between launches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Hashes computed this session, keyed by path.
+#[derive(Debug, Default)]
+pub(crate) struct HashCache {
+    entries: HashMap<String, (FileStamp, String)>,
+}
+
+impl HashCache {
+    /// Checks that the file at `path` hashes to `expected`, reading it
+    /// directly only when it is inside `work_dir`.
+    pub(crate) fn verify(&mut self, path: &str, expected: &str, work_dir: &str) -> Result<(), String> {
+        let actual = if is_inside(path, work_dir) {
+            self.hash(path)?
+        } else {
+            hash_with_command(path, run_hash_command)?
+        };
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(format!(
+                "refusing to launch {path}: its SHA-256 does not match binary.sha256.\n\
+                 Expected: {expected}\n\
+                 Actual:   {actual}"
+            ))
+        }
+    }
+
+    /// Returns the hex SHA-256 of the file at `path`, reusing the cached
+    /// value while its size and modification time are unchanged.
+    fn hash(&mut self, path: &str) -> Result<String, String> {
+        let unreadable = |err: std::io::Error| format!("cannot verify {path} against binary.sha256: {err}");
+
+        let metadata = std::fs::metadata(path).map_err(unreadable)?;
+        let stamp = FileStamp {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        };
+        if let Some((cached_stamp, hash)) = self.entries.get(path) {
+            if *cached_stamp == stamp {
+                return Ok(hash.clone());
+            }
+        }
+
+        let hash = hash_file(path).map_err(unreadable)?;
+        self.entries.insert(path.to_string(), (stamp, hash.clone()));
+        Ok(hash)
+    }
+}
+
+/// Whether `path` is `dir` or lies below it. An unknown (empty) `dir`
+/// contains nothing.
+fn is_inside(path: &str, dir: &str) -> bool {
+    !dir.is_empty() && std::path::Path::new(path).starts_with(dir)
+}
+
+/// Hashes `path` with the first of [`HASH_COMMANDS`] that `run` succeeds
+/// with, failing with every command's reason when none does.
+fn hash_with_command(
+    path: &str,
+    mut run: impl FnMut(&str, &[&str]) -> Result<Vec<u8>, String>,
+) -> Result<String, String> {
+    let mut reasons = Vec::new();
+    for (program, args) in HASH_COMMANDS {
+        let args: Vec<&str> = args.iter().copied().chain([path]).collect();
+        let reason = match run(program, &args) {
+            Ok(stdout) => match parse_hash_output(&decode_output(&stdout, program)) {
+                Some(hash) => return Ok(hash),
+                None => "printed no SHA-256 hash".to_string(),
+            },
+            Err(reason) => reason,
+        };
+        reasons.push(format!("{program}: {reason}"));
+    }
+    Err(format!("cannot verify {path} against binary.sha256: {}", reasons.join("; ")))
+}
+
+/// Runs one of [`HASH_COMMANDS`], returning what it printed on success.
+fn run_hash_command(program: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new(program).args(args.iter().copied()).output()?;
+    if output.status == Some(0) {
+        return Ok(output.stdout);
+    }
+    let stderr = decode_output(&output.stderr, program);
+    let detail = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+    let status = output.status.map_or_else(|| "a signal".to_string(), |code| format!("status {code}"));
+    Err(format!("exited with {status}: {}", detail.trim()))
+}
+
+/// Reads the hash from `sha256sum`/`shasum` output (`<hash>  <path>`). A
+/// leading backslash marks a path that was escaped, not part of the hash.
+fn parse_hash_output(output: &str) -> Option<String> {
+    let hash = output.split_whitespace().next()?.trim_start_matches('\\');
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())).then(|| hash.to_ascii_lowercase())
+}
+
+fn hash_file(path: &str) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&chunk[..read]);
+    }
+    Ok(hasher.finish_hex())
+}
+
+/// Streaming SHA-256 (FIPS 180-4).
+struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5, 0xd807aa98,
+    0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786,
+    0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8,
+    0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+    0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819,
+    0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a,
+    0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+    0xc67178f2,
+];
+
+impl Sha256 {
+    fn new() -> Self {
+        Self {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+            ],
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        while !data.is_empty() {
+            let take = (64 - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&data[..take]);
+            self.block_len += take;
+            data = &data[take..];
+            if self.block_len == 64 {
+                let block = self.block;
+                self.compress(&block);
+                self.block_len = 0;
+            }
+        }
+    }
+
+    fn finish_hex(mut self) -> String {
+        let bit_len = self.total_len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.block_len != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bit_len.to_be_bytes());
+
+        let mut hex = String::with_capacity(64);
+        for word in self.state {
+            hex.push_str(&format!("{word:08x}"));
+        }
+        hex
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(ROUND_CONSTANTS[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn sha256(data: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.finish_hex()
+    }
+
+    fn temp_dir() -> String {
+        std::env::temp_dir().to_string_lossy().into_owned()
+    }
+
+    /// Writes `contents` to a fresh file in the temp dir and returns its path.
+    fn temp_file(name: &str, contents: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("mtlog-integrity-{}-{name}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Known-answer tests, including input spanning several blocks.
+    #[test]
+    fn test_sha256_vectors() {
+        assert_eq!(sha256(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256(b"abc"), ABC_SHA256);
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            sha256(&vec![b'a'; 1_000_000]),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    /// Feeding data in uneven pieces gives the same hash as one update.
+    #[test]
+    fn test_sha256_streaming() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        let mut hasher = Sha256::new();
+        for piece in data.chunks(37) {
+            hasher.update(piece);
+        }
+
+        assert_eq!(hasher.finish_hex(), sha256(&data));
+    }
+
+    #[test]
+    fn test_expected_hash() {
+        let upper = ABC_SHA256.to_ascii_uppercase();
+        let per_platform = json!({ "linux-amd64": ABC_SHA256 });
+
+        assert_eq!(expected_hash(None, "linux-amd64"), Ok(None));
+        assert_eq!(expected_hash(Some(&json!(upper)), "linux-amd64"), Ok(Some(ABC_SHA256.to_string())));
+        assert_eq!(expected_hash(Some(&per_platform), "linux-amd64"), Ok(Some(ABC_SHA256.to_string())));
+        assert!(expected_hash(Some(&per_platform), "darwin-arm64").unwrap_err().contains("darwin-arm64"));
+        assert!(expected_hash(Some(&json!("abc123")), "linux-amd64").is_err());
+        assert!(expected_hash(Some(&json!(42)), "linux-amd64").is_err());
+    }
+
+    #[test]
+    fn test_verify_matching_binary() {
+        let path = temp_file("match", b"abc");
+
+        assert_eq!(HashCache::default().verify(&path, ABC_SHA256, &temp_dir()), Ok(()));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// A mismatch names both the expected and the actual hash.
+    #[test]
+    fn test_verify_mismatching_binary() {
+        let path = temp_file("mismatch", b"abd");
+
+        let err = HashCache::default().verify(&path, ABC_SHA256, &temp_dir()).unwrap_err();
+
+        assert!(err.contains(&format!("Expected: {ABC_SHA256}")));
+        assert!(err.contains(&format!("Actual:   {}", sha256(b"abd"))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// A binary that cannot be read is refused rather than trusted.
+    #[test]
+    fn test_verify_unreadable_binary() {
+        let path = std::env::temp_dir().join("mtlog-integrity-does-not-exist");
+
+        let err = HashCache::default()
+            .verify(&path.to_string_lossy(), ABC_SHA256, &temp_dir())
+            .unwrap_err();
+
+        assert!(err.starts_with("cannot verify"));
+    }
+
+    /// The cached hash is reused until the file changes.
+    #[test]
+    fn test_cache_invalidated_by_change() {
+        let path = temp_file("cache", b"abc");
+        let mut cache = HashCache::default();
+        assert_eq!(cache.verify(&path, ABC_SHA256, &temp_dir()), Ok(()));
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.verify(&path, ABC_SHA256, &temp_dir()), Ok(()));
+
+        std::fs::write(&path, b"abcd").unwrap();
+
+        assert!(cache.verify(&path, ABC_SHA256, &temp_dir()).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_is_inside() {
+        assert!(is_inside("/work/mtlog/go-bin/mtlog-lsp", "/work/mtlog"));
+        assert!(is_inside("/work/mtlog/mtlog-lsp", "/work/mtlog/"));
+        assert!(!is_inside("/work/mtlog-other/mtlog-lsp", "/work/mtlog"));
+        assert!(!is_inside("/usr/local/bin/mtlog-lsp", "/work/mtlog"));
+        assert!(!is_inside("/usr/local/bin/mtlog-lsp", ""));
+    }
+
+    /// Binaries outside the work dir are hashed by `sha256sum`, falling
+    /// back to `shasum -a 256` when it cannot run.
+    #[test]
+    fn test_hash_with_command() {
+        let mut ran = Vec::new();
+        let hash = hash_with_command("/usr/local/bin/mtlog-lsp", |program, args| {
+            ran.push(format!("{program} {}", args.join(" ")));
+            match program {
+                "sha256sum" => Err("program not found".to_string()),
+                _ => Ok(format!("{}  /usr/local/bin/mtlog-lsp\n", ABC_SHA256.to_ascii_uppercase()).into_bytes()),
+            }
+        });
+
+        assert_eq!(hash, Ok(ABC_SHA256.to_string()));
+        assert_eq!(ran, ["sha256sum /usr/local/bin/mtlog-lsp", "shasum -a 256 /usr/local/bin/mtlog-lsp"]);
+    }
+
+    /// When neither command yields a hash the binary is refused, naming why.
+    #[test]
+    fn test_hash_with_command_fails_closed() {
+        let err = hash_with_command("/opt/mtlog-lsp", |program, _| match program {
+            "sha256sum" => Ok(b"sha256sum: /opt/mtlog-lsp: Permission denied\n".to_vec()),
+            _ => Err("exited with status 1: no such algorithm".to_string()),
+        })
+        .unwrap_err();
+
+        assert!(err.starts_with("cannot verify /opt/mtlog-lsp against binary.sha256"));
+        assert!(err.contains("sha256sum: printed no SHA-256 hash"));
+        assert!(err.contains("shasum: exited with status 1: no such algorithm"));
+    }
+
+    #[test]
+    fn test_parse_hash_output() {
+        let escaped = format!("\\{ABC_SHA256}  /opt/mtlog\\nlsp\n");
+
+        assert_eq!(parse_hash_output(&format!("{ABC_SHA256}  mtlog-lsp\n")), Some(ABC_SHA256.to_string()));
+        assert_eq!(parse_hash_output(&escaped), Some(ABC_SHA256.to_string()));
+        assert_eq!(parse_hash_output("abc123  mtlog-lsp\n"), None);
+        assert_eq!(parse_hash_output(""), None);
+    }
+}
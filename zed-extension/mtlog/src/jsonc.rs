@@ -0,0 +1,108 @@
+//! Parsing of JSON with comments, as used by editor settings files.
+//!
+//! Both VS Code and Zed accept `//` and `/* */` comments and trailing commas
+//! in their settings files, which `serde_json` rejects. These are stripped
+//! before handing the text to `serde_json`.
+
+use zed_extension_api::serde_json::{self, Value};
+
+/// Parses `text` as JSON, tolerating comments and trailing commas.
+pub(crate) fn parse(text: &str) -> Result<Value, String> {
+    serde_json::from_str(&strip(text)).map_err(|err| err.to_string())
+}
+
+/// Removes comments and trailing commas outside of string literals.
+fn strip(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            ']' | '}' => {
+                let trimmed_len = output.trim_end().len();
+                if output[..trimmed_len].ends_with(',') {
+                    output.truncate(trimmed_len - 1);
+                }
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    /// Comments and trailing commas are ignored.
+    #[test]
+    fn test_parse_with_comments_and_trailing_commas() {
+        let text = r#"
+            // Line comment
+            {
+                /* Block
+                   comment */
+                "mtlog.suppressedDiagnostics": ["MTLOG001",],
+                "editor.tabSize": 4, // Trailing comment
+            }
+        "#;
+
+        assert_eq!(
+            parse(text).unwrap(),
+            json!({ "mtlog.suppressedDiagnostics": ["MTLOG001"], "editor.tabSize": 4 })
+        );
+    }
+
+    /// Comment markers and commas inside strings are preserved.
+    #[test]
+    fn test_parse_preserves_string_contents() {
+        let text = r#"{ "url": "https://example.com/*x*/", "quote": "a \"//\" b,]" }"#;
+
+        assert_eq!(
+            parse(text).unwrap(),
+            json!({ "url": "https://example.com/*x*/", "quote": "a \"//\" b,]" })
+        );
+    }
+
+    /// Malformed input reports an error instead of panicking.
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("{ \"key\": }").is_err());
+    }
+}
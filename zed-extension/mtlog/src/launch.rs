@@ -0,0 +1,582 @@
+//! Crash-loop detection for mtlog-lsp launches.
+//!
+//! Zed asks for a new server command every time the server exits, so a
+//! binary that crashes on startup is relaunched indefinitely. Each worktree
+//! keeps a [`LaunchTracker`] that counts the servers started in quick
+//! succession; once the count passes the configured threshold the extension
+//! stops handing out commands and reports the problem instead. Only commands
+//! actually handed out count, and a pause of [`HEALTHY_PERIOD`] without one
+//! starts over.
+//!
+//! Extensions never see why the server exited. A crash loop from a binary
+//! whose `--version` probe succeeded is taken to be a configuration the
+//! server cannot start with, which `onFatalConfig = "disable"` turns into a
+//! stop for the rest of the session instead of a retry on the next edit. The
+//! stop only applies to the worktree and settings the loop happened with.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use zed_extension_api::serde_json::Value;
+
+use crate::discovery::{Candidate, Outcome};
+use crate::probe::VersionProbe;
+
+/// Default number of consecutive failed launches tolerated before giving up.
+pub(crate) const DEFAULT_MAX_FAILED_LAUNCHES: u32 = 5;
+
+/// A server that stayed up at least this long is considered a healthy session.
+const HEALTHY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Tracks consecutive launches of mtlog-lsp for a single worktree.
+#[derive(Debug, Default)]
+pub(crate) struct LaunchTracker {
+    /// Launches in the current streak, including the most recent one.
+    launches: u32,
+    last_launch: Option<Instant>,
+    settings_fingerprint: Option<String>,
+    /// Settings fingerprints a crash loop disabled for the session.
+    disabled: BTreeMap<String, CrashSignature>,
+}
+
+impl LaunchTracker {
+    /// Whether a launch at `now` with the given settings fingerprint
+    /// continues the current streak. The streak restarts when the settings
+    /// changed since the previous launch, or when the previous server ran
+    /// for a healthy period.
+    fn continues_streak(&self, now: Instant, settings_fingerprint: &str) -> bool {
+        self.settings_fingerprint.as_deref() == Some(settings_fingerprint)
+            && self
+                .last_launch
+                .is_some_and(|last| now.saturating_duration_since(last) < HEALTHY_PERIOD)
+    }
+
+    /// Records a server started at `now` with the given settings fingerprint.
+    /// Call it only once the command is handed out, so refused launches do
+    /// not extend the streak.
+    pub(crate) fn record_launch(&mut self, now: Instant, settings_fingerprint: &str) {
+        if !self.continues_streak(now, settings_fingerprint) {
+            self.launches = 0;
+            self.settings_fingerprint = Some(settings_fingerprint.to_string());
+        }
+
+        self.launches += 1;
+        self.last_launch = Some(now);
+    }
+
+    /// Number of launches in the current streak that were followed by another
+    /// launch before the server became healthy, counting a launch at `now`
+    /// with the given settings fingerprint as the next one.
+    pub(crate) fn failed_launches(&self, now: Instant, settings_fingerprint: &str) -> u32 {
+        if self.continues_streak(now, settings_fingerprint) {
+            self.launches
+        } else {
+            0
+        }
+    }
+
+    /// Launches in the current streak that were followed by another, leaving
+    /// out the latest server, which may still be running.
+    pub(crate) fn restarts(&self) -> u32 {
+        self.launches.saturating_sub(1)
+    }
+
+    /// Whether a launch at `now` would bring the failed launches to `threshold`.
+    pub(crate) fn is_crash_looping(&self, now: Instant, settings_fingerprint: &str, threshold: u32) -> bool {
+        self.failed_launches(now, settings_fingerprint) >= threshold
+    }
+
+    /// The crash loop that disabled launches with `settings_fingerprint`, if any.
+    pub(crate) fn disabled(&self, settings_fingerprint: &str) -> Option<&CrashSignature> {
+        self.disabled.get(settings_fingerprint)
+    }
+
+    /// Refuses every later launch with `settings_fingerprint` for the session.
+    pub(crate) fn disable(&mut self, settings_fingerprint: &str, signature: CrashSignature) {
+        self.disabled.insert(settings_fingerprint.to_string(), signature);
+    }
+}
+
+/// What to do about a crash loop caused by the configuration, from the
+/// `onFatalConfig` setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum FatalConfigPolicy {
+    /// Refuse to relaunch until the settings change or the project is reopened.
+    #[default]
+    Retry,
+    /// Stop launching mtlog-lsp for the rest of the session.
+    Disable,
+}
+
+impl FatalConfigPolicy {
+    const ALL: &'static [FatalConfigPolicy] = &[FatalConfigPolicy::Retry, FatalConfigPolicy::Disable];
+
+    /// Parses the `onFatalConfig` setting; unset means the default.
+    pub(crate) fn from_setting(setting: Option<&Value>) -> Result<Self, String> {
+        let Some(setting) = setting else {
+            return Ok(FatalConfigPolicy::default());
+        };
+        setting
+            .as_str()
+            .and_then(|given| Self::ALL.iter().find(|policy| policy.as_str().eq_ignore_ascii_case(given)))
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "ignoring invalid onFatalConfig {setting}: expected \"retry\" or \"disable\"; using \"{}\"",
+                    FatalConfigPolicy::default().as_str()
+                )
+            })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            FatalConfigPolicy::Retry => "retry",
+            FatalConfigPolicy::Disable => "disable",
+        }
+    }
+}
+
+/// The binary a crash loop happened with and how many launches failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CrashSignature {
+    pub(crate) binary_path: String,
+    pub(crate) failed_launches: u32,
+}
+
+/// What the extension does with a launch request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Decision {
+    Launch,
+    /// Refuse this launch; changed settings or a reopened project retry.
+    Refuse,
+    /// Refuse this and every later launch with the same settings in the session.
+    Disable(CrashSignature),
+}
+
+impl LaunchTracker {
+    /// Decides whether a launch at `now` with the given settings fingerprint
+    /// should go ahead. Only a crash loop from a binary that answered its
+    /// probe counts as fatal configuration; a binary that cannot run at all
+    /// is always retried.
+    pub(crate) fn decide(
+        &self,
+        now: Instant,
+        settings_fingerprint: &str,
+        threshold: u32,
+        binary_path: &str,
+        probe: &VersionProbe,
+        policy: FatalConfigPolicy,
+    ) -> Decision {
+        if !self.is_crash_looping(now, settings_fingerprint, threshold) {
+            return Decision::Launch;
+        }
+        let binary_runs = !matches!(probe, VersionProbe::Failed(_));
+        if policy == FatalConfigPolicy::Retry || !binary_runs {
+            return Decision::Refuse;
+        }
+        Decision::Disable(CrashSignature {
+            binary_path: binary_path.to_string(),
+            failed_launches: self.failed_launches(now, settings_fingerprint),
+        })
+    }
+}
+
+/// Worktrees that were given a server, capped by the `maxServers` setting.
+///
+/// A worktree only takes its slot once its server command is returned, so a
+/// launch that fails beforehand leaves the slot free. Zed does not tell
+/// extensions when a server stops or a worktree closes, so from then on the
+/// worktree keeps its slot for the rest of the session, and its own
+/// relaunches are always admitted.
+#[derive(Debug, Default)]
+pub(crate) struct ServerSlots {
+    /// Worktree ids with a server, and their root paths.
+    active: BTreeMap<u64, String>,
+    /// Worktrees turned away, so each is only logged once.
+    deferred: BTreeMap<u64, String>,
+}
+
+/// Whether a worktree may start a server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Admission {
+    Admit,
+    /// The `cap` is reached. `first` is set the first time this worktree is
+    /// turned away.
+    Defer { cap: u32, first: bool },
+}
+
+impl ServerSlots {
+    /// Decides whether worktree `id` at `root` may start a server when at
+    /// most `cap` may run; `None` means no cap. An admitted worktree only
+    /// holds a slot after [`take`](Self::take).
+    pub(crate) fn admit(&mut self, id: u64, root: &str, cap: Option<u32>) -> Admission {
+        match cap {
+            Some(cap) if !self.active.contains_key(&id) && self.active.len() >= cap as usize => {
+                let first = self.deferred.insert(id, root.to_string()).is_none();
+                Admission::Defer { cap, first }
+            }
+            _ => Admission::Admit,
+        }
+    }
+
+    /// Gives worktree `id` at `root` a slot, once its server is started.
+    pub(crate) fn take(&mut self, id: u64, root: &str) {
+        self.active.insert(id, root.to_string());
+        self.deferred.remove(&id);
+    }
+
+    /// Root paths of the worktrees holding a slot.
+    pub(crate) fn active_roots(&self) -> impl Iterator<Item = &str> {
+        self.active.values().map(String::as_str)
+    }
+
+    /// Root paths of the worktrees turned away.
+    pub(crate) fn deferred_roots(&self) -> impl Iterator<Item = &str> {
+        self.deferred.values().map(String::as_str)
+    }
+}
+
+/// Error returned to a worktree turned away by `maxServers`.
+pub(crate) fn deferred_message<'a>(root: &str, cap: u32, active: impl Iterator<Item = &'a str>) -> String {
+    let active: Vec<&str> = active.collect();
+    format!(
+        "mtlog-lsp was not started for {root}: maxServers allows {cap} and they already run for {}. \
+         Raise maxServers, or close another project and restart Zed.",
+        active.join(", ")
+    )
+}
+
+/// Formats the error returned instead of a command once a crash loop is
+/// detected, listing what discovery currently makes of each candidate.
+pub(crate) fn crash_loop_message(
+    failed_launches: u32,
+    binary_path: &str,
+    probe: &VersionProbe,
+    trace: &[(Candidate, Outcome)],
+    threshold_setting: &str,
+) -> String {
+    let healthy = HEALTHY_PERIOD.as_secs();
+    let mut discovery = String::new();
+    for (i, (candidate, outcome)) in trace.iter().enumerate() {
+        let outcome = match outcome {
+            Outcome::Selected => "selected".to_string(),
+            Outcome::Skipped(reason) => format!("skipped, {reason}"),
+            Outcome::NotTried => "not tried".to_string(),
+        };
+        discovery.push_str(&format!("{}. {} ({}): {outcome}\n", i + 1, candidate.path, candidate.source));
+    }
+    if discovery.is_empty() {
+        discovery.push_str("no candidates\n");
+    }
+    format!(
+        "mtlog-lsp exited {failed_launches} times in a row shortly after starting; \
+         not restarting it again.\n\
+         Binary: {binary_path}\n\
+         Probe: {probe}\n\
+         Discovery:\n{discovery}\
+         Check the server output in Zed's language server log (View → Toggle LSP Log), \
+         and verify the binary runs on this machine (for example, an incompatible glibc \
+         or a binary built for another platform).\n\
+         Changing the mtlog-analyzer settings or reopening the project retries the launch, \
+         and so does the next one once no server was started for {healthy} seconds; \
+         the limit is configurable with the \"{threshold_setting}\" setting.\n\
+         For the full report, run /mtlog-doctor"
+    )
+}
+
+/// Error returned for every launch with the settings a crash loop disabled.
+pub(crate) fn disabled_message(signature: &CrashSignature, probe: &VersionProbe) -> String {
+    format!(
+        "mtlog-lsp is disabled for this project: it exited {} times in a row right after starting \
+         with the current settings, which usually means a setting it cannot start with.\n\
+         Binary: {} ({probe})\n\
+         Check the server output in Zed's language server log (View → Toggle LSP Log), then fix the \
+         mtlog-analyzer settings or restart Zed. Set \"onFatalConfig\" to \"retry\" to retry \
+         after a pause instead.",
+        signature.failed_launches, signature.binary_path
+    )
+}
+
+/// Error shown when the binary speaks another extension protocol.
+pub(crate) fn protocol_mismatch_message(extension_protocol: u64, server_protocol: u64, binary_path: &str) -> String {
+    let update = if server_protocol > extension_protocol {
+        "update the mtlog-analyzer extension"
+    } else {
+        "update mtlog-lsp"
+    };
+    format!(
+        "The extension and mtlog-lsp protocol versions are incompatible; update one of them.\n\
+         Extension protocol: {extension_protocol}\n\
+         mtlog-lsp protocol: {server_protocol} ({binary_path})\n\
+         The binary is newer or older than this extension supports; {update}, or point binary.path at a \
+         matching mtlog-lsp."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::CandidateSource;
+    use zed_extension_api::serde_json::json;
+
+    fn launch_times(start: Instant, count: u32, gap: Duration) -> Vec<Instant> {
+        (0..count).map(|i| start + gap * i).collect()
+    }
+
+    /// Rapid relaunches with unchanged settings accumulate until the threshold.
+    #[test]
+    fn test_rapid_launches_trip_threshold() {
+        let mut tracker = LaunchTracker::default();
+        let start = Instant::now();
+        let gap = Duration::from_secs(2);
+
+        for (i, time) in launch_times(start, 5, gap).into_iter().enumerate() {
+            assert_eq!(tracker.failed_launches(time, "settings"), i as u32);
+            tracker.record_launch(time, "settings");
+        }
+
+        let next = start + gap * 5;
+        assert!(tracker.is_crash_looping(next, "settings", DEFAULT_MAX_FAILED_LAUNCHES));
+        assert!(!tracker.is_crash_looping(next, "settings", DEFAULT_MAX_FAILED_LAUNCHES + 1));
+    }
+
+    /// The first launch never counts as a failure.
+    #[test]
+    fn test_first_launch_is_not_a_failure() {
+        let tracker = LaunchTracker::default();
+
+        assert_eq!(tracker.failed_launches(Instant::now(), "settings"), 0);
+        assert!(!tracker.is_crash_looping(Instant::now(), "settings", 1));
+    }
+
+    /// A launch after a healthy period starts a new streak.
+    #[test]
+    fn test_healthy_session_resets_counter() {
+        let start = Instant::now();
+        let mut tracker = LaunchTracker::default();
+        for time in launch_times(start, 4, Duration::from_secs(1)) {
+            tracker.record_launch(time, "settings");
+        }
+        assert_eq!(tracker.failed_launches(start + Duration::from_secs(4), "settings"), 4);
+        assert_eq!(tracker.restarts(), 3);
+
+        assert_eq!(tracker.failed_launches(start + HEALTHY_PERIOD * 2, "settings"), 0);
+        tracker.record_launch(start + HEALTHY_PERIOD * 2, "settings");
+        assert_eq!(tracker.failed_launches(start + HEALTHY_PERIOD * 2 + Duration::from_secs(1), "settings"), 1);
+    }
+
+    /// Changing settings starts a new streak even when launches are rapid.
+    #[test]
+    fn test_settings_change_resets_counter() {
+        let start = Instant::now();
+        let mut tracker = LaunchTracker::default();
+        for time in launch_times(start, 6, Duration::from_secs(1)) {
+            tracker.record_launch(time, "old");
+        }
+        let next = start + Duration::from_secs(7);
+        assert!(tracker.is_crash_looping(next, "old", DEFAULT_MAX_FAILED_LAUNCHES));
+
+        assert_eq!(tracker.failed_launches(next, "new"), 0);
+        tracker.record_launch(next, "new");
+
+        assert_eq!(tracker.failed_launches(next + Duration::from_secs(1), "new"), 1);
+        assert!(!tracker.is_crash_looping(next + Duration::from_secs(1), "new", DEFAULT_MAX_FAILED_LAUNCHES));
+    }
+
+    /// Launches that are only asked for, and refused, never extend the streak.
+    #[test]
+    fn test_unrecorded_launches_do_not_count() {
+        let start = Instant::now();
+        let mut tracker = LaunchTracker::default();
+        tracker.record_launch(start, "settings");
+        let probe = VersionProbe::Version("0.11.0".to_string());
+
+        for time in launch_times(start + Duration::from_secs(1), 10, Duration::from_secs(1)) {
+            let decision = tracker.decide(time, "settings", 1, "/bin/mtlog-lsp", &probe, FatalConfigPolicy::Retry);
+            assert_eq!(decision, Decision::Refuse);
+        }
+
+        assert_eq!(tracker.failed_launches(start + Duration::from_secs(20), "settings"), 1);
+    }
+
+    /// The crash-loop message carries the binary, its probe result, the
+    /// discovery report, the setting to adjust, and a pointer to the doctor.
+    #[test]
+    fn test_crash_loop_message() {
+        let probe = VersionProbe::Failed("exec format error".to_string());
+        let candidate = |path: &str, source| Candidate {
+            path: path.to_string(),
+            source,
+            needs_check: true,
+        };
+        let trace = vec![
+            (candidate("/opt/mtlog-lsp", CandidateSource::ExplicitPath), Outcome::Skipped("not found".to_string())),
+            (candidate("/usr/local/bin/mtlog-lsp", CandidateSource::Path), Outcome::Selected),
+            (candidate("/home/me/go/bin/mtlog-lsp", CandidateSource::HomeGoBin), Outcome::NotTried),
+        ];
+        let message = crash_loop_message(5, "/usr/local/bin/mtlog-lsp", &probe, &trace, "maxFailedLaunches");
+
+        assert!(message.contains("5 times"));
+        assert!(message.contains("Binary: /usr/local/bin/mtlog-lsp\n"));
+        assert!(message.contains("exec format error"));
+        assert!(message.contains(
+            "Discovery:\n\
+             1. /opt/mtlog-lsp (binary.path): skipped, not found\n\
+             2. /usr/local/bin/mtlog-lsp (PATH): selected\n\
+             3. /home/me/go/bin/mtlog-lsp (~/go/bin): not tried\n"
+        ));
+        assert!(message.contains("maxFailedLaunches"));
+        assert!(message.contains("once no server was started for 60 seconds"));
+        assert!(message.ends_with("run /mtlog-doctor"));
+
+        let without_candidates = crash_loop_message(5, "/bin/mtlog-lsp", &probe, &[], "maxFailedLaunches");
+        assert!(without_candidates.contains("Discovery:\nno candidates\n"));
+    }
+
+    #[test]
+    fn test_fatal_config_policy_from_setting() {
+        assert_eq!(FatalConfigPolicy::from_setting(None), Ok(FatalConfigPolicy::Retry));
+        assert_eq!(FatalConfigPolicy::from_setting(Some(&json!("Disable"))), Ok(FatalConfigPolicy::Disable));
+        assert!(FatalConfigPolicy::from_setting(Some(&json!("stop"))).unwrap_err().contains("\"retry\""));
+        assert!(FatalConfigPolicy::from_setting(Some(&json!(false))).is_err());
+    }
+
+    /// A tracker with five quick launches, and the time of the next one.
+    fn crash_looping_tracker() -> (LaunchTracker, Instant) {
+        let start = Instant::now();
+        let mut tracker = LaunchTracker::default();
+        for time in launch_times(start, 5, Duration::from_secs(1)) {
+            tracker.record_launch(time, "settings");
+        }
+        (tracker, start + Duration::from_secs(5))
+    }
+
+    /// Below the threshold every policy launches.
+    #[test]
+    fn test_decide_launches_until_crash_loop() {
+        let mut tracker = LaunchTracker::default();
+        let start = Instant::now();
+        tracker.record_launch(start, "settings");
+        let probe = VersionProbe::Version("0.11.0".to_string());
+        let next = start + Duration::from_secs(1);
+
+        for policy in [FatalConfigPolicy::Retry, FatalConfigPolicy::Disable] {
+            let decision =
+                tracker.decide(next, "settings", DEFAULT_MAX_FAILED_LAUNCHES, "/bin/mtlog-lsp", &probe, policy);
+            assert_eq!(decision, Decision::Launch);
+        }
+    }
+
+    /// "disable" stops for the session, recording the binary the loop happened with.
+    #[test]
+    fn test_decide_disables_on_fatal_config() {
+        let (tracker, next) = crash_looping_tracker();
+        let probe = VersionProbe::Version("0.11.0".to_string());
+        let decide =
+            |policy| tracker.decide(next, "settings", DEFAULT_MAX_FAILED_LAUNCHES, "/bin/mtlog-lsp", &probe, policy);
+
+        assert_eq!(
+            decide(FatalConfigPolicy::Disable),
+            Decision::Disable(CrashSignature {
+                binary_path: "/bin/mtlog-lsp".to_string(),
+                failed_launches: 5,
+            })
+        );
+        assert_eq!(decide(FatalConfigPolicy::Retry), Decision::Refuse);
+    }
+
+    /// A binary that cannot run is a binary problem, not a configuration one, so it is never disabled.
+    #[test]
+    fn test_decide_retries_broken_binary() {
+        let (tracker, next) = crash_looping_tracker();
+        let probe = VersionProbe::Failed("exec format error".to_string());
+        let policy = FatalConfigPolicy::Disable;
+
+        assert_eq!(
+            tracker.decide(next, "settings", DEFAULT_MAX_FAILED_LAUNCHES, "/bin/mtlog-lsp", &probe, policy),
+            Decision::Refuse
+        );
+    }
+
+    #[test]
+    fn test_disabled_message() {
+        let signature = CrashSignature {
+            binary_path: "/bin/mtlog-lsp".to_string(),
+            failed_launches: 5,
+        };
+        let message = disabled_message(&signature, &VersionProbe::Version("0.11.0".to_string()));
+
+        assert!(message.starts_with("mtlog-lsp is disabled for this project: it exited 5 times"));
+        assert!(message.contains("Binary: /bin/mtlog-lsp (version 0.11.0)"));
+        assert!(message.contains("\"onFatalConfig\""));
+    }
+
+    /// Worktrees beyond the cap are deferred, logged once each, while admitted ones keep relaunching.
+    #[test]
+    fn test_server_cap() {
+        let mut slots = ServerSlots::default();
+
+        assert_eq!(slots.admit(1, "/work/a", Some(2)), Admission::Admit);
+        slots.take(1, "/work/a");
+        assert_eq!(slots.admit(2, "/work/b", Some(2)), Admission::Admit);
+        slots.take(2, "/work/b");
+        assert_eq!(slots.admit(3, "/work/c", Some(2)), Admission::Defer { cap: 2, first: true });
+        assert_eq!(slots.admit(3, "/work/c", Some(2)), Admission::Defer { cap: 2, first: false });
+        assert_eq!(slots.admit(1, "/work/a", Some(2)), Admission::Admit);
+
+        assert_eq!(slots.active_roots().collect::<Vec<_>>(), vec!["/work/a", "/work/b"]);
+        assert_eq!(slots.deferred_roots().collect::<Vec<_>>(), vec!["/work/c"]);
+    }
+
+    /// Raising or removing the cap admits a deferred worktree.
+    #[test]
+    fn test_server_cap_raised() {
+        let mut slots = ServerSlots::default();
+        slots.take(1, "/work/a");
+        assert_eq!(slots.admit(2, "/work/b", Some(1)), Admission::Defer { cap: 1, first: true });
+
+        assert_eq!(slots.admit(2, "/work/b", None), Admission::Admit);
+        slots.take(2, "/work/b");
+        assert_eq!(slots.deferred_roots().count(), 0);
+        assert_eq!(slots.admit(3, "/work/c", Some(3)), Admission::Admit);
+        slots.take(3, "/work/c");
+        assert_eq!(slots.admit(4, "/work/d", Some(3)), Admission::Defer { cap: 3, first: true });
+    }
+
+    /// A worktree whose launch failed after admission holds no slot, so
+    /// the next one is admitted in its place.
+    #[test]
+    fn test_failed_launch_leaves_slot_free() {
+        let mut slots = ServerSlots::default();
+
+        assert_eq!(slots.admit(1, "/work/a", Some(1)), Admission::Admit);
+        assert_eq!(slots.active_roots().count(), 0);
+
+        assert_eq!(slots.admit(2, "/work/b", Some(1)), Admission::Admit);
+        slots.take(2, "/work/b");
+        assert_eq!(slots.admit(1, "/work/a", Some(1)), Admission::Defer { cap: 1, first: true });
+        assert_eq!(slots.active_roots().collect::<Vec<_>>(), vec!["/work/b"]);
+    }
+
+    #[test]
+    fn test_deferred_message() {
+        let message = deferred_message("/work/c", 2, ["/work/a", "/work/b"].into_iter());
+
+        assert!(message.starts_with("mtlog-lsp was not started for /work/c: maxServers allows 2"));
+        assert!(message.contains("/work/a, /work/b"));
+    }
+
+    /// The mismatch message names both protocols and which side to update.
+    #[test]
+    fn test_protocol_mismatch_message() {
+        let newer_server = protocol_mismatch_message(1, 2, "/usr/bin/mtlog-lsp");
+        assert!(newer_server.starts_with(
+            "The extension and mtlog-lsp protocol versions are incompatible; update one of them.\n\
+             Extension protocol: 1\n\
+             mtlog-lsp protocol: 2 (/usr/bin/mtlog-lsp)\n"
+        ));
+        assert!(newer_server.contains("update the mtlog-analyzer extension"));
+
+        assert!(protocol_mismatch_message(3, 2, "/usr/bin/mtlog-lsp").contains("update mtlog-lsp,"));
+    }
+}
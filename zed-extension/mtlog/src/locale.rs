@@ -0,0 +1,165 @@
+//! The `locale` initialization option, telling mtlog-lsp which language to
+//! write its diagnostic messages in.
+//!
+//! The `locale` setting wins; otherwise the locale comes from the shell's
+//! `LC_ALL`, `LC_MESSAGES`, or `LANG`, in the order POSIX gives them, and
+//! falls back to English. It is forwarded as a BCP 47 tag such as `de-DE`,
+//! so POSIX spellings like `de_DE.UTF-8` are converted. Servers that do not
+//! localize their messages ignore the option.
+
+use std::collections::HashMap;
+
+use zed_extension_api::serde_json::Value;
+
+use crate::settings::{EffectiveSettings, Source};
+
+/// Setting, and initialization option, holding the locale.
+/// Forwarded only; requires mtlog-lsp support.
+pub(crate) const LOCALE_KEY: &str = "locale";
+
+/// Environment variables consulted when the setting is absent, most specific first.
+const LOCALE_ENV: &[&str] = &["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// Locale forwarded when neither the setting nor the environment names one.
+const DEFAULT_LOCALE: &str = "en";
+
+/// Returns the `locale` setting as a BCP 47 tag, or `Ok(None)` when absent.
+pub(crate) fn setting(setting: Option<&Value>) -> Result<Option<String>, String> {
+    let Some(setting) = setting else {
+        return Ok(None);
+    };
+    setting
+        .as_str()
+        .and_then(parse)
+        .map(Some)
+        .ok_or_else(|| "expected a locale such as \"de-DE\" or \"pt_BR\"".to_string())
+}
+
+/// Converts a locale such as `pt_BR.UTF-8`, `zh-Hans-CN`, or `fr` into a
+/// BCP 47 tag. The `C` and `POSIX` locales, which name no language, and
+/// anything malformed give `None`.
+fn parse(locale: &str) -> Option<String> {
+    // POSIX locales may end in .codeset and @modifier, which say nothing about language
+    let locale = locale.trim();
+    let locale = locale.split(['.', '@']).next().unwrap_or(locale);
+    let mut parts = locale.split(['-', '_']);
+
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut tag = language.to_ascii_lowercase();
+    let mut rest = parts.peekable();
+    if let Some(script) = rest.next_if(|part| part.len() == 4 && part.bytes().all(|b| b.is_ascii_alphabetic())) {
+        tag.push('-');
+        tag.push_str(&script[..1].to_ascii_uppercase());
+        tag.push_str(&script[1..].to_ascii_lowercase());
+    }
+    if let Some(region) = rest.next() {
+        let is_region = (region.len() == 2 && region.bytes().all(|b| b.is_ascii_alphabetic()))
+            || (region.len() == 3 && region.bytes().all(|b| b.is_ascii_digit()));
+        if !is_region {
+            return None;
+        }
+        tag.push('-');
+        tag.push_str(&region.to_ascii_uppercase());
+    }
+    rest.next().is_none().then_some(tag)
+}
+
+/// The locale the shell environment selects, if it names a language.
+fn from_env(env: &HashMap<String, String>) -> Option<String> {
+    // The first variable that is set decides, even when it is C
+    let locale = LOCALE_ENV
+        .iter()
+        .filter_map(|name| env.get(*name))
+        .find(|value| !value.trim().is_empty())?;
+    parse(locale)
+}
+
+/// Adds the locale to the initialization options: the setting when it is
+/// valid, else the environment's, else English.
+pub(crate) fn apply(
+    setting: Result<Option<String>, String>,
+    env: &HashMap<String, String>,
+    effective: &mut EffectiveSettings,
+) {
+    let configured = setting.unwrap_or_else(|err| {
+        effective.warnings.push(format!("ignoring invalid {LOCALE_KEY}: {err}"));
+        None
+    });
+    let (locale, source) = match configured {
+        Some(locale) => (locale, Source::Settings),
+        None => (from_env(env).unwrap_or_else(|| DEFAULT_LOCALE.to_string()), Source::Default),
+    };
+    if let Some(options) = effective.options.as_object_mut() {
+        options.insert(LOCALE_KEY.to_string(), Value::String(locale));
+        effective.sources.insert(LOCALE_KEY.to_string(), source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings;
+    use zed_extension_api::{serde_json::json, settings::LspSettings};
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("de_DE.UTF-8").as_deref(), Some("de-DE"));
+        assert_eq!(parse("pt-br").as_deref(), Some("pt-BR"));
+        assert_eq!(parse("zh_hans_cn").as_deref(), Some("zh-Hans-CN"));
+        assert_eq!(parse("es-419").as_deref(), Some("es-419"));
+        assert_eq!(parse("sr_RS@latin").as_deref(), Some("sr-RS"));
+        assert_eq!(parse(" FR ").as_deref(), Some("fr"));
+
+        for invalid in ["", "C", "POSIX", "C.UTF-8", "english", "de-Germany", "de-DE-x", "d3"] {
+            assert_eq!(parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_setting() {
+        assert_eq!(setting(None), Ok(None));
+        assert_eq!(setting(Some(&json!("ja_JP"))), Ok(Some("ja-JP".to_string())));
+        assert!(setting(Some(&json!("C"))).is_err());
+        assert!(setting(Some(&json!(["de"]))).is_err());
+    }
+
+    /// LC_ALL wins over LC_MESSAGES over LANG; a C locale there means the default.
+    #[test]
+    fn test_from_env() {
+        assert_eq!(from_env(&env(&[("LANG", "fr_FR.UTF-8")])).as_deref(), Some("fr-FR"));
+        assert_eq!(
+            from_env(&env(&[("LANG", "fr_FR.UTF-8"), ("LC_MESSAGES", "it_IT"), ("LC_ALL", "")])).as_deref(),
+            Some("it-IT")
+        );
+        assert_eq!(from_env(&env(&[("LANG", "fr_FR.UTF-8"), ("LC_ALL", "de_AT")])).as_deref(), Some("de-AT"));
+        assert_eq!(from_env(&env(&[("LANG", "fr_FR.UTF-8"), ("LC_ALL", "C")])), None);
+        assert_eq!(from_env(&env(&[])), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let lang = env(&[("LANG", "nl_NL.UTF-8")]);
+
+        let mut configured = settings::initialization_options(&LspSettings::default());
+        apply(Ok(Some("de-DE".to_string())), &lang, &mut configured);
+        assert_eq!(configured.options[LOCALE_KEY], json!("de-DE"));
+        assert_eq!(configured.sources[LOCALE_KEY], Source::Settings);
+
+        let mut derived = settings::initialization_options(&LspSettings::default());
+        apply(Ok(None), &lang, &mut derived);
+        assert_eq!(derived.options[LOCALE_KEY], json!("nl-NL"));
+        assert_eq!(derived.sources[LOCALE_KEY], Source::Default);
+
+        let mut fallback = settings::initialization_options(&LspSettings::default());
+        apply(Err("expected a locale".to_string()), &env(&[("LANG", "C")]), &mut fallback);
+        assert_eq!(fallback.options[LOCALE_KEY], json!("en"));
+        assert_eq!(fallback.warnings, vec!["ignoring invalid locale: expected a locale"]);
+    }
+}
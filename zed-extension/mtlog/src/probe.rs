@@ -0,0 +1,373 @@
+//! Probing of the resolved mtlog-lsp binary before it is launched.
+//!
+//! The probe runs `<binary> --version` and extracts a version from whatever
+//! it prints. Older binaries do not understand the flag and start the server
+//! loop instead, which ends at the closed stdin or, failing that, when the
+//! probe kills it after [`PROBE_TIMEOUT_SECS`]. A probe that finds no version
+//! is reported but never prevents the launch, and counts as an unknown
+//! version rather than an old one.
+//!
+//! Older binaries may also print deprecation notices while starting up. The
+//! probe collects them so the extension can surface each distinct notice
+//! once, instead of every time the binary is probed again.
+
+use std::collections::HashSet;
+
+use zed_extension_api::process::Command;
+use zed_extension_api::{self as zed, Os};
+
+use crate::decode::{decode, decode_output};
+
+/// Result of running `--version` against a binary.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum VersionProbe {
+    /// The binary reported this version.
+    Version(String),
+    /// The binary ran but printed nothing that looks like a version.
+    Unrecognized,
+    /// The binary could not be run.
+    Failed(String),
+}
+
+impl std::fmt::Display for VersionProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionProbe::Version(version) => write!(f, "version {version}"),
+            VersionProbe::Unrecognized => write!(f, "no version reported by --version"),
+            VersionProbe::Failed(err) => write!(f, "--version failed: {err}"),
+        }
+    }
+}
+
+/// Everything learned from running `--version` against a binary.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Probe {
+    pub(crate) version: VersionProbe,
+    /// Extension protocol the binary speaks, when it says so.
+    pub(crate) protocol: Option<u64>,
+    /// Deprecation notices the binary printed, in order.
+    pub(crate) notices: Vec<String>,
+}
+
+/// How long `--version` may run before the probe kills it.
+const PROBE_TIMEOUT_SECS: &str = "5";
+
+/// Runs `"$0" --version`, killing it after `$1` seconds. The timer writes to
+/// /dev/null so that it does not hold the captured output open once the
+/// binary has exited.
+const TIMED_VERSION_SCRIPT: &str = "\"$0\" --version & pid=$!; (sleep \"$1\"; kill \"$pid\") >/dev/null 2>&1 & \
+                                    timer=$!; wait \"$pid\"; status=$?; kill \"$timer\" 2>/dev/null; exit \"$status\"";
+
+/// Exit status of a binary killed by the timer's SIGTERM.
+const KILLED_STATUS: i32 = 128 + 15;
+
+/// The command that runs `binary --version`. Windows has no `sh` to run the
+/// timer, so there the binary runs directly and relies on its closed stdin.
+fn version_command(binary: &str, os: Os) -> Command {
+    match os {
+        Os::Windows => Command::new(binary).arg("--version"),
+        Os::Mac | Os::Linux => Command::new("sh").args(["-c", TIMED_VERSION_SCRIPT, binary, PROBE_TIMEOUT_SECS]),
+    }
+}
+
+/// Runs `binary --version`, extracting the reported version and any
+/// deprecation notices.
+pub(crate) fn probe(binary: &str) -> Probe {
+    let (os, _) = zed::current_platform();
+    match version_command(binary, os).output() {
+        Ok(output) if output.status == Some(KILLED_STATUS) && output.stdout.is_empty() => Probe {
+            version: VersionProbe::Failed(format!("timed out after {PROBE_TIMEOUT_SECS}s")),
+            protocol: None,
+            notices: Vec::new(),
+        },
+        Ok(output) => {
+            let source = format!("{binary} --version");
+            let stdout = decode_output(&output.stdout, &source);
+            let stderr = decode_output(&output.stderr, &source);
+            Probe {
+                version: parse_version_output(&output.stdout)
+                    .or_else(|| parse_version_output(&output.stderr))
+                    .map_or(VersionProbe::Unrecognized, VersionProbe::Version),
+                protocol: parse_protocol(&stdout).or_else(|| parse_protocol(&stderr)),
+                notices: [&stderr, &stdout].into_iter().flat_map(|text| deprecation_notices(text)).collect(),
+            }
+        }
+        Err(err) => Probe {
+            version: VersionProbe::Failed(err),
+            protocol: None,
+            notices: Vec::new(),
+        },
+    }
+}
+
+/// Returns the lines of process output that announce a deprecation, without
+/// the `[mtlog-lsp]` tag and timestamp the server's logger prefixes.
+pub(crate) fn deprecation_notices(text: &str) -> Vec<String> {
+    text.lines()
+        .filter(|line| line.to_ascii_lowercase().contains("deprecat"))
+        .map(|line| strip_log_prefix(line).to_string())
+        .collect()
+}
+
+fn strip_log_prefix(line: &str) -> &str {
+    let mut rest = line.trim();
+    if rest.starts_with('[') {
+        if let Some(end) = rest.find(']') {
+            rest = rest[end + 1..].trim_start();
+        }
+    }
+    // Date and time tokens such as `2025/01/15` and `10:30:00.123`
+    while let Some((token, tail)) = rest.split_once(' ') {
+        let is_timestamp = token.starts_with(|c: char| c.is_ascii_digit())
+            && token.chars().all(|c| c.is_ascii_digit() || matches!(c, '/' | '-' | ':' | '.'));
+        if !is_timestamp {
+            break;
+        }
+        rest = tail.trim_start();
+    }
+    rest
+}
+
+/// Notices already surfaced, so each distinct message is shown once.
+#[derive(Debug, Default)]
+pub(crate) struct NoticeLog {
+    shown: HashSet<String>,
+}
+
+impl NoticeLog {
+    /// Returns the notices not shown before, marking them as shown.
+    pub(crate) fn unseen<'a>(&mut self, notices: &'a [String]) -> Vec<&'a str> {
+        notices
+            .iter()
+            .filter(|notice| self.shown.insert(notice.to_string()))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Extracts the first semver-looking token (`1.2.3`, `v1.2.3-rc.1`) from
+/// the bytes a process printed. A leading `v` is dropped. Invalid UTF-8 is
+/// replaced rather than rejected, so a stray byte does not hide the version;
+/// [`probe`] logs the warning for it when decoding the rest of the output.
+pub(crate) fn parse_version_output(output: &[u8]) -> Option<String> {
+    decode(output)
+        .text
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')' | '[' | ']' | '"' | '\''))
+        .map(|token| token.strip_prefix('v').unwrap_or(token))
+        .find(|token| is_semver(token))
+        .map(str::to_string)
+}
+
+/// Extracts the protocol number from a `protocol 2` or `protocol=2` token in
+/// the `--version` output. Binaries that predate the protocol print none.
+pub(crate) fn parse_protocol(text: &str) -> Option<u64> {
+    let text = text.to_ascii_lowercase();
+    let mut tokens = text.split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')'));
+    while let Some(token) = tokens.next() {
+        if let Some(number) = token.strip_prefix("protocol=") {
+            return number.parse().ok();
+        }
+        if token == "protocol" {
+            return tokens.find(|token| !token.is_empty())?.parse().ok();
+        }
+    }
+    None
+}
+
+/// Whether the probed version is known to predate `minimum`. A binary that
+/// reports no version, like a failed probe, tells nothing either way: it may
+/// be an old one or a development build.
+pub(crate) fn is_older_than(version: &VersionProbe, minimum: &str) -> bool {
+    match version {
+        VersionProbe::Version(version) => match (version_core(version), version_core(minimum)) {
+            (Some(version), Some(minimum)) => version < minimum,
+            _ => false,
+        },
+        VersionProbe::Unrecognized | VersionProbe::Failed(_) => false,
+    }
+}
+
+/// The documentation set matching the probed binary. Rule docs change only
+/// with minor releases, so `0.11.3` and `0.11.0-rc.1` both select `0.11`.
+/// Without a version there is nothing to match, and the server picks.
+pub(crate) fn docs_version(version: &VersionProbe) -> Option<String> {
+    match version {
+        VersionProbe::Version(version) => {
+            let (major, minor, _) = version_core(version)?;
+            Some(format!("{major}.{minor}"))
+        }
+        VersionProbe::Unrecognized | VersionProbe::Failed(_) => None,
+    }
+}
+
+/// The numeric `MAJOR.MINOR.PATCH` of a version, ignoring any suffix.
+fn version_core(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
+/// Whether `token` is `MAJOR.MINOR.PATCH` with an optional pre-release or
+/// build suffix.
+fn is_semver(token: &str) -> bool {
+    let core_end = token.find(['-', '+']).unwrap_or(token.len());
+    let (core, suffix) = token.split_at(core_end);
+
+    let parts: Vec<&str> = core.split('.').collect();
+    let core_valid = parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    let suffix_valid = suffix.chars().skip(1).all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+        && suffix.len() != 1;
+
+    core_valid && suffix_valid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clean output in the usual formats yields the version.
+    #[test]
+    fn test_parse_clean_output() {
+        assert_eq!(parse_version_output(b"mtlog-lsp v0.9.0\n"), Some("0.9.0".to_string()));
+        assert_eq!(parse_version_output(b"0.10.2"), Some("0.10.2".to_string()));
+        assert_eq!(
+            parse_version_output(b"mtlog-lsp version 1.0.0-rc.1 (linux/amd64)"),
+            Some("1.0.0-rc.1".to_string())
+        );
+    }
+
+    /// Surrounding noise such as log prefixes and dates is skipped.
+    #[test]
+    fn test_parse_noisy_output() {
+        let output = "[mtlog-lsp] 2025/01/15 10:30:00 starting\nbuild: go1.22.1, version=ignored\nmtlog-lsp (v0.9.1)\n";
+
+        assert_eq!(parse_version_output(output.as_bytes()), Some("0.9.1".to_string()));
+    }
+
+    /// Invalid UTF-8 around the version does not prevent extraction.
+    #[test]
+    fn test_parse_non_utf8_output() {
+        let mut output = vec![0xff, 0xfe, b' '];
+        output.extend_from_slice(b"mtlog-lsp v0.9.0 ");
+        output.extend_from_slice(&[0xc3, 0x28]);
+
+        assert_eq!(parse_version_output(&output), Some("0.9.0".to_string()));
+        assert_eq!(parse_version_output(b"mtlog-lsp v0.9.\xff1"), None);
+        assert_eq!(parse_version_output(b"mtlog-lsp \xff\xfe v1.2.3\n"), Some("1.2.3".to_string()));
+    }
+
+    /// Deprecation lines are picked out and stripped of the log prefix.
+    #[test]
+    fn test_deprecation_notices() {
+        let output = "[mtlog-lsp] 2025/01/15 10:30:00 option \"strictMode\" is deprecated; use \"strict\"\n\
+                       [mtlog-lsp] 2025/01/15 10:30:00 starting\nmtlog-lsp v0.8.0\nDEPRECATION: -json flag\n";
+
+        assert_eq!(
+            deprecation_notices(output),
+            vec!["option \"strictMode\" is deprecated; use \"strict\"", "DEPRECATION: -json flag"]
+        );
+        assert!(deprecation_notices("mtlog-lsp v0.9.0\n").is_empty());
+    }
+
+    /// Each distinct notice is surfaced once, however often it is reported.
+    #[test]
+    fn test_notice_log_dedups_by_message() {
+        let mut log = NoticeLog::default();
+        let first = vec!["a is deprecated".to_string(), "b is deprecated".to_string(), "a is deprecated".to_string()];
+        let second = vec!["b is deprecated".to_string(), "c is deprecated".to_string()];
+
+        assert_eq!(log.unseen(&first), vec!["a is deprecated", "b is deprecated"]);
+        assert_eq!(log.unseen(&second), vec!["c is deprecated"]);
+        assert!(log.unseen(&first).is_empty());
+    }
+
+    /// The protocol is read in either spelling, and is absent from older output.
+    #[test]
+    fn test_parse_protocol() {
+        assert_eq!(parse_protocol("mtlog-lsp v0.11.0 (protocol 2)\n"), Some(2));
+        assert_eq!(parse_protocol("mtlog-lsp 0.11.0 protocol=1"), Some(1));
+        assert_eq!(parse_protocol("mtlog-lsp v0.9.0\n"), None);
+        assert_eq!(parse_protocol("protocol next"), None);
+    }
+
+    /// mtlog-lsp's own `--version` line yields its version and protocol, and
+    /// a source build yields the protocol alone.
+    #[test]
+    fn test_parse_mtlog_lsp_output() {
+        let release = "mtlog-lsp v0.11.0 protocol 1\n";
+        let devel = "mtlog-lsp (devel) protocol 1\n";
+
+        assert_eq!(parse_version_output(release.as_bytes()), Some("0.11.0".to_string()));
+        assert_eq!(parse_protocol(release), Some(1));
+        assert_eq!(parse_version_output(devel.as_bytes()), None);
+        assert_eq!(parse_protocol(devel), Some(1));
+    }
+
+    /// mtlog-lsp prints `--version` in the format parsed above, with the
+    /// protocol this extension sends.
+    #[test]
+    fn test_mtlog_lsp_version_format() {
+        let server = include_str!("../../../cmd/mtlog-lsp/main.go");
+
+        assert!(server.contains(r#"fmt.Sprintf("mtlog-lsp %s protocol %d", v, protocolVersion)"#));
+        assert!(server.contains(&format!("protocolVersion = {}\n", crate::settings::PROTOCOL_VERSION)));
+    }
+
+    /// Versions compare numerically, and unversioned binaries are not assumed older.
+    #[test]
+    fn test_is_older_than() {
+        let version = |v: &str| VersionProbe::Version(v.to_string());
+
+        assert!(is_older_than(&version("0.10.2"), "0.11.0"));
+        assert!(is_older_than(&version("0.9.10"), "0.10.0"));
+        assert!(!is_older_than(&version("0.11.0"), "0.11.0"));
+        assert!(!is_older_than(&version("0.11.0-rc.1"), "0.11.0"));
+        assert!(!is_older_than(&version("1.0.0"), "0.11.0"));
+        assert!(!is_older_than(&VersionProbe::Unrecognized, "0.11.0"));
+        assert!(!is_older_than(&VersionProbe::Failed("no such file".to_string()), "0.11.0"));
+    }
+
+    #[test]
+    fn test_docs_version() {
+        let version = |v: &str| VersionProbe::Version(v.to_string());
+
+        assert_eq!(docs_version(&version("0.11.3")), Some("0.11".to_string()));
+        assert_eq!(docs_version(&version("0.12.0-rc.1")), Some("0.12".to_string()));
+        assert_eq!(docs_version(&version("1.0.0+build.7")), Some("1.0".to_string()));
+        assert_eq!(docs_version(&version("nightly")), None);
+        assert_eq!(docs_version(&VersionProbe::Unrecognized), None);
+        assert_eq!(docs_version(&VersionProbe::Failed("no such file".to_string())), None);
+    }
+
+    /// `--version` runs under a timer where there is a shell, and directly on Windows.
+    #[test]
+    fn test_version_command() {
+        let timed = version_command("/usr/bin/mtlog-lsp", Os::Linux);
+        assert_eq!(timed.command, "sh");
+        assert_eq!(timed.args, ["-c", TIMED_VERSION_SCRIPT, "/usr/bin/mtlog-lsp", PROBE_TIMEOUT_SECS]);
+
+        let direct = version_command("C:\\bin\\mtlog-lsp.exe", Os::Windows);
+        assert_eq!(direct.command, "C:\\bin\\mtlog-lsp.exe");
+        assert_eq!(direct.args, ["--version"]);
+    }
+
+    /// The timed probe is granted in `extension.toml` exactly as it is run.
+    #[test]
+    fn test_version_script_is_granted() {
+        let granted = format!("\"{}\", \"*\", \"{PROBE_TIMEOUT_SECS}\"]", TIMED_VERSION_SCRIPT.replace('"', "\\\""));
+
+        assert!(include_str!("../extension.toml").contains(&granted));
+    }
+
+    /// Output without a version token yields nothing.
+    #[test]
+    fn test_parse_output_without_version() {
+        assert_eq!(parse_version_output(b""), None);
+        assert_eq!(parse_version_output(b"[mtlog-lsp] Error reading header: EOF"), None);
+        assert_eq!(parse_version_output(b"1.2 1.2.x 1.2.3.4 v1.2.3-"), None);
+        assert_eq!(parse_version_output(&[0xff, 0xfe, 0xfd]), None);
+    }
+}
@@ -0,0 +1,198 @@
+//! The `propertyDictionary` setting: the project's canonical property
+//! names and the type each should hold, so the analyzer can flag a
+//! property logged as a string in one place and a number in another.
+//!
+//! The dictionary is given inline, or as the path of a JSON file relative
+//! to the worktree root:
+//!
+//! ```json
+//! { "UserId": "int64", "RequestPath": "string", "Elapsed": "time.Duration" }
+//! ```
+
+use std::collections::BTreeMap;
+
+use zed_extension_api::serde_json::{Map, Value};
+
+use crate::jsonc;
+use crate::settings::{self, EffectiveSettings, Source};
+
+/// Setting, and initialization option, holding the dictionary.
+/// Forwarded only; requires mtlog-lsp support.
+pub(crate) const PROPERTY_DICTIONARY_KEY: &str = "propertyDictionary";
+
+/// Types a property can be declared with.
+const TYPES: &[&str] = &[
+    "string",
+    "bool",
+    "int",
+    "int64",
+    "uint",
+    "uint64",
+    "float64",
+    "time.Time",
+    "time.Duration",
+    "error",
+    "any",
+];
+
+/// Where the dictionary is configured.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Reference<'a> {
+    Inline(&'a Value),
+    /// A file relative to the worktree root.
+    File(&'a str),
+}
+
+/// Property types read from the setting, with warnings for entries that
+/// were skipped.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Dictionary {
+    pub(crate) types: BTreeMap<String, &'static str>,
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Returns how the setting is given, or `Ok(None)` when it is absent.
+pub(crate) fn reference(setting: Option<&Value>) -> Result<Option<Reference<'_>>, String> {
+    match setting {
+        None => Ok(None),
+        Some(Value::Object(_)) => Ok(setting.map(Reference::Inline)),
+        Some(Value::String(path)) if !path.trim().is_empty() => Ok(Some(Reference::File(path.trim()))),
+        Some(_) => Err("expected an object of property types, or the path of a JSON file holding one".to_string()),
+    }
+}
+
+/// Parses the contents of a dictionary file.
+pub(crate) fn parse_file(text: &str) -> Result<Dictionary, String> {
+    let value = jsonc::parse(text)?;
+    match value.as_object() {
+        Some(_) => Ok(parse(&value)),
+        None => Err("expected a JSON object of property types".to_string()),
+    }
+}
+
+/// Parses a dictionary object, keeping the entries whose name is an
+/// identifier and whose type is one of [`TYPES`].
+pub(crate) fn parse(value: &Value) -> Dictionary {
+    let mut dictionary = Dictionary::default();
+    for (name, kind) in value.as_object().into_iter().flatten() {
+        if !settings::is_identifier(name) {
+            dictionary.warnings.push(format!("\"{name}\" is not a property name"));
+            continue;
+        }
+        match TYPES.iter().find(|known| kind.as_str() == Some(**known)) {
+            Some(known) => {
+                dictionary.types.insert(name.clone(), known);
+            }
+            None => dictionary
+                .warnings
+                .push(format!("{name} has unknown type {kind}; expected one of {}", TYPES.join(", "))),
+        }
+    }
+    dictionary
+}
+
+/// Adds the dictionary to the initialization options, or records a warning
+/// when the setting was invalid or its file could not be read. `origin`
+/// names the file the dictionary came from, if any.
+pub(crate) fn apply(loaded: Result<Option<(Option<String>, Dictionary)>, String>, effective: &mut EffectiveSettings) {
+    let (origin, dictionary) = match loaded {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) => return,
+        Err(err) => {
+            effective.warnings.push(format!("ignoring {PROPERTY_DICTIONARY_KEY}: {err}"));
+            return;
+        }
+    };
+    let prefix = match &origin {
+        Some(file) => format!("{PROPERTY_DICTIONARY_KEY} {file}"),
+        None => PROPERTY_DICTIONARY_KEY.to_string(),
+    };
+    effective
+        .warnings
+        .extend(dictionary.warnings.into_iter().map(|warning| format!("{prefix}: skipping {warning}")));
+
+    if let Some(options) = effective.options.as_object_mut() {
+        let types: Map<String, Value> = dictionary
+            .types
+            .into_iter()
+            .map(|(name, kind)| (name, Value::String(kind.to_string())))
+            .collect();
+        options.insert(PROPERTY_DICTIONARY_KEY.to_string(), Value::Object(types));
+        effective.sources.insert(PROPERTY_DICTIONARY_KEY.to_string(), Source::Settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::{serde_json::json, settings::LspSettings};
+
+    #[test]
+    fn test_reference() {
+        let inline = json!({ "UserId": "int64" });
+
+        assert_eq!(reference(None), Ok(None));
+        assert_eq!(reference(Some(&inline)), Ok(Some(Reference::Inline(&inline))));
+        assert_eq!(
+            reference(Some(&json!(" .mtlog/properties.json "))),
+            Ok(Some(Reference::File(".mtlog/properties.json")))
+        );
+        assert!(reference(Some(&json!(""))).is_err());
+        assert!(reference(Some(&json!(["UserId"]))).is_err());
+    }
+
+    /// Invalid names and unknown types are skipped with a warning each.
+    #[test]
+    fn test_parse() {
+        let dictionary = parse(&json!({
+            "UserId": "int64",
+            "Elapsed": "time.Duration",
+            "user id": "string",
+            "Count": "integer",
+            "Tags": ["string"]
+        }));
+
+        assert_eq!(
+            dictionary.types,
+            BTreeMap::from([("Elapsed".to_string(), "time.Duration"), ("UserId".to_string(), "int64")])
+        );
+        assert_eq!(dictionary.warnings.len(), 3);
+        assert!(dictionary.warnings.iter().any(|warning| warning == "\"user id\" is not a property name"));
+        assert!(dictionary.warnings.iter().any(|warning| warning.starts_with("Count has unknown type \"integer\"")));
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let text = "{\n  // shared with the backend team\n  \"RequestPath\": \"string\",\n}\n";
+
+        assert_eq!(parse_file(text).unwrap().types, BTreeMap::from([("RequestPath".to_string(), "string")]));
+        assert!(parse_file("[\"RequestPath\"]").is_err());
+        assert!(parse_file("{ \"RequestPath\": ").is_err());
+    }
+
+    #[test]
+    fn test_apply_forwards() {
+        let mut effective = settings::initialization_options(&LspSettings::default());
+        let dictionary = parse(&json!({ "UserId": "int64", "Count": "integer" }));
+
+        apply(Ok(Some((Some("props.json".to_string()), dictionary))), &mut effective);
+
+        assert_eq!(effective.options[PROPERTY_DICTIONARY_KEY], json!({ "UserId": "int64" }));
+        assert_eq!(effective.sources[PROPERTY_DICTIONARY_KEY], Source::Settings);
+        assert_eq!(effective.warnings.len(), 1);
+        assert!(effective.warnings[0].starts_with("propertyDictionary props.json: skipping Count"));
+    }
+
+    /// Without the setting nothing is forwarded, so the server keeps its own behavior.
+    #[test]
+    fn test_apply_absent_or_unreadable() {
+        let mut effective = settings::initialization_options(&LspSettings::default());
+
+        apply(Ok(None), &mut effective);
+        assert!(effective.options.get(PROPERTY_DICTIONARY_KEY).is_none());
+
+        apply(Err("could not read props.json: no such file".to_string()), &mut effective);
+        assert!(effective.options.get(PROPERTY_DICTIONARY_KEY).is_none());
+        assert_eq!(effective.warnings, vec!["ignoring propertyDictionary: could not read props.json: no such file"]);
+    }
+}
@@ -0,0 +1,160 @@
+//! The `quietHours` setting: a daily range during which mtlog-lsp writes
+//! `notifyOnError` notifications to its log instead of showing them.
+//!
+//! Notifications are sent by the server, so the range is validated here,
+//! forwarded in a normalized form, and applied by mtlog-lsp against its own
+//! local clock. `/mtlog-doctor` uses the same decision to say whether the
+//! range is in effect right now.
+
+use std::fmt;
+
+use zed_extension_api::{
+    process::Command,
+    serde_json::{json, Value},
+};
+
+use crate::decode::decode_output;
+
+/// Setting, and initialization option, holding the range.
+/// Forwarded only; requires mtlog-lsp support.
+pub(crate) const QUIET_HOURS_KEY: &str = "quietHours";
+
+/// A range of local time, in minutes since midnight. `start` is inclusive
+/// and `end` exclusive; a range whose end is earlier than its start runs
+/// past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct QuietHours {
+    start: u16,
+    end: u16,
+}
+
+impl QuietHours {
+    /// Parses `{"start": "22:00", "end": "07:00"}`.
+    pub(crate) fn parse(value: &Value) -> Result<Self, String> {
+        let object = value.as_object().ok_or("expected an object with start and end times")?;
+        if let Some(unknown) = object.keys().find(|key| !matches!(key.as_str(), "start" | "end")) {
+            return Err(format!("unknown key \"{unknown}\", expected start or end"));
+        }
+        let time = |key: &str| {
+            let given = object.get(key).ok_or_else(|| format!("missing {key}"))?;
+            given
+                .as_str()
+                .and_then(parse_time)
+                .ok_or_else(|| format!("expected {key} to be a 24-hour HH:MM time, such as \"07:30\""))
+        };
+        let quiet_hours = QuietHours {
+            start: time("start")?,
+            end: time("end")?,
+        };
+        if quiet_hours.start == quiet_hours.end {
+            return Err("start and end are the same time".to_string());
+        }
+        Ok(quiet_hours)
+    }
+
+    /// Whether `minute`, counted from midnight, falls inside the range.
+    pub(crate) fn contains(&self, minute: u16) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// The normalized value forwarded to mtlog-lsp.
+    pub(crate) fn to_value(self) -> Value {
+        json!({ "start": format_time(self.start), "end": format_time(self.end) })
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}–{}", format_time(self.start), format_time(self.end))
+    }
+}
+
+/// Parses `H:MM` or `HH:MM` into minutes since midnight.
+fn parse_time(time: &str) -> Option<u16> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if !(1..=2).contains(&hours.len()) || minutes.len() != 2 || !digits(hours) || !digits(minutes) {
+        return None;
+    }
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+fn format_time(minute: u16) -> String {
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+/// The current local time in minutes since midnight, read from `date`,
+/// since WASI clocks only give UTC. `None` where `date` is unavailable.
+pub(crate) fn local_minute() -> Option<u16> {
+    let output = Command::new("date").arg("+%H:%M").output().ok()?;
+    parse_time(&decode_output(&output.stdout, "date"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet_hours(start: &str, end: &str) -> QuietHours {
+        QuietHours::parse(&json!({ "start": start, "end": end })).unwrap()
+    }
+
+    fn at(time: &str) -> u16 {
+        parse_time(time).unwrap()
+    }
+
+    #[test]
+    fn test_contains_same_day() {
+        let lunch = quiet_hours("12:00", "13:30");
+
+        assert!(!lunch.contains(at("11:59")));
+        assert!(lunch.contains(at("12:00")));
+        assert!(lunch.contains(at("13:29")));
+        assert!(!lunch.contains(at("13:30")));
+    }
+
+    /// A range ending before it starts covers both sides of midnight.
+    #[test]
+    fn test_contains_wraparound() {
+        let night = quiet_hours("22:00", "7:00");
+
+        assert!(night.contains(at("22:00")));
+        assert!(night.contains(at("23:59")));
+        assert!(night.contains(at("00:00")));
+        assert!(night.contains(at("06:59")));
+        assert!(!night.contains(at("07:00")));
+        assert!(!night.contains(at("12:00")));
+        assert!(!night.contains(at("21:59")));
+    }
+
+    #[test]
+    fn test_parse_normalizes() {
+        let night = quiet_hours(" 22:00", "7:05");
+
+        assert_eq!(night.to_value(), json!({ "start": "22:00", "end": "07:05" }));
+        assert_eq!(night.to_string(), "22:00–07:05");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for value in [
+            json!("22:00-07:00"),
+            json!({ "start": "22:00" }),
+            json!({ "start": "24:00", "end": "07:00" }),
+            json!({ "start": "22:60", "end": "07:00" }),
+            json!({ "start": "22:00", "end": "7" }),
+            json!({ "start": "22:00", "end": "007:00" }),
+            json!({ "start": "+2:00", "end": "07:00" }),
+            json!({ "start": 22, "end": 7 }),
+            json!({ "start": "09:00", "end": "09:00" }),
+            json!({ "start": "22:00", "end": "07:00", "days": "weekdays" }),
+        ] {
+            assert!(QuietHours::parse(&value).is_err(), "{value}");
+        }
+    }
+}
@@ -0,0 +1,424 @@
+//! Conversion of mtlog-analyzer findings into reports.
+//!
+//! Findings are read from the JSON that `go vet -json` (or mtlog-analyzer
+//! with `-json`) prints, and written out as SARIF 2.1.0 so they can be
+//! uploaded to code-scanning dashboards, or compared between two analyzer
+//! builds.
+
+use std::collections::BTreeSet;
+
+use zed_extension_api::serde_json::{self, json, Value};
+
+/// The analyzer's diagnostic codes and what each one checks.
+pub(crate) const RULES: &[(&str, &str)] = &[
+    ("MTLOG001", "Template/argument count mismatch"),
+    ("MTLOG002", "Invalid format specifier"),
+    ("MTLOG003", "Duplicate property names"),
+    ("MTLOG004", "Property names should be PascalCase"),
+    ("MTLOG005", "Missing capturing hints"),
+    ("MTLOG006", "Error logging without an error value"),
+    ("MTLOG007", "Context key should be a constant"),
+    ("MTLOG008", "Dynamic template"),
+    ("MTLOG009", "With() has an odd number of arguments"),
+    ("MTLOG010", "With() key is not a string"),
+    ("MTLOG011", "With() property duplicated across calls"),
+    ("MTLOG012", "With() uses a reserved property name"),
+    ("MTLOG013", "With() key is empty"),
+];
+
+/// Rule used for findings that carry no diagnostic code.
+const UNCODED_RULE: &str = "mtlog";
+
+/// Severity prefixed to non-error analyzer messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+    Suggestion,
+}
+
+impl Severity {
+    fn sarif_level(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Suggestion => "note",
+        }
+    }
+}
+
+/// One analyzer finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Diagnostic {
+    pub(crate) file: String,
+    pub(crate) line: u64,
+    pub(crate) column: u64,
+    /// `MTLOG001` and so on, when the message carries one.
+    pub(crate) code: Option<String>,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+}
+
+/// Parses vet-style JSON output: one `{package: {analyzer: [finding]}}`
+/// object per package, optionally preceded by `# package` comment lines.
+pub(crate) fn parse_vet_json(output: &str) -> Result<Vec<Diagnostic>, String> {
+    let json: String = output
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut diagnostics = Vec::new();
+    for package in serde_json::Deserializer::from_str(&json).into_iter::<Value>() {
+        let package = package.map_err(|err| format!("unexpected analyzer output: {err}"))?;
+        let findings = package
+            .as_object()
+            .into_iter()
+            .flat_map(|packages| packages.values())
+            .filter_map(Value::as_object)
+            .flat_map(|analyzers| analyzers.values())
+            .filter_map(Value::as_array)
+            .flatten();
+        for finding in findings {
+            let (Some(posn), Some(message)) = (
+                finding.get("posn").and_then(Value::as_str),
+                finding.get("message").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            let Some((file, line, column)) = parse_posn(posn) else {
+                continue;
+            };
+            let (code, severity, message) = parse_message(message);
+            diagnostics.push(Diagnostic {
+                file,
+                line,
+                column,
+                code,
+                severity,
+                message,
+            });
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// Splits `file:line:col` from the right, so Windows drive letters survive.
+fn parse_posn(posn: &str) -> Option<(String, u64, u64)> {
+    let mut parts = posn.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let file = parts.next()?.to_string();
+    Some((file, line, column))
+}
+
+/// Splits `[MTLOG004] warning: text` into its code, severity, and text.
+fn parse_message(message: &str) -> (Option<String>, Severity, String) {
+    let (code, rest) = match message.strip_prefix('[').and_then(|rest| rest.split_once("] ")) {
+        Some((code, rest)) => (Some(code.to_string()), rest),
+        None => (None, message),
+    };
+    let (severity, text) = if let Some(text) = rest.strip_prefix("warning: ") {
+        (Severity::Warning, text)
+    } else if let Some(text) = rest.strip_prefix("suggestion: ") {
+        (Severity::Suggestion, text)
+    } else {
+        (Severity::Error, rest)
+    };
+    (code, severity, text.to_string())
+}
+
+/// Builds a SARIF 2.1.0 log. Files under `root` are given relative to the
+/// `%SRCROOT%` base so the report is portable between checkouts.
+pub(crate) fn to_sarif(diagnostics: &[Diagnostic], root: &str) -> Value {
+    let rule_ids: Vec<&str> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code.as_deref().unwrap_or(UNCODED_RULE))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let rules: Vec<Value> = rule_ids
+        .iter()
+        .map(|id| {
+            let description = RULES
+                .iter()
+                .find(|(code, _)| code == id)
+                .map_or("mtlog-analyzer finding", |(_, description)| description);
+            json!({ "id": id, "shortDescription": { "text": description } })
+        })
+        .collect();
+
+    let root = root.trim_end_matches(['/', '\\']);
+    let results: Vec<Value> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let rule_id = diagnostic.code.as_deref().unwrap_or(UNCODED_RULE);
+            let artifact = match diagnostic.file.strip_prefix(root).and_then(|rest| rest.strip_prefix(['/', '\\'])) {
+                Some(relative) => json!({ "uri": relative.replace('\\', "/"), "uriBaseId": "%SRCROOT%" }),
+                None => json!({ "uri": file_uri(&diagnostic.file) }),
+            };
+            json!({
+                "ruleId": rule_id,
+                "ruleIndex": rule_ids.iter().position(|id| *id == rule_id),
+                "level": diagnostic.severity.sarif_level(),
+                "message": { "text": diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": artifact,
+                        "region": { "startLine": diagnostic.line, "startColumn": diagnostic.column }
+                    }
+                }]
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "mtlog-analyzer",
+                    "informationUri": "https://github.com/willibrandon/mtlog",
+                    "rules": rules
+                }
+            },
+            "originalUriBaseIds": { "%SRCROOT%": { "uri": format!("{}/", file_uri(root)) } },
+            "results": results
+        }]
+    })
+}
+
+/// How the findings of a candidate analyzer differ from a baseline's.
+/// Findings match on file, position, code, and message.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct DiagnosticDiff {
+    /// Reported only by the candidate.
+    pub(crate) added: Vec<Diagnostic>,
+    /// Reported only by the baseline.
+    pub(crate) removed: Vec<Diagnostic>,
+    /// Reported by both at different severities, as `(baseline, candidate)`.
+    pub(crate) changed: Vec<(Diagnostic, Diagnostic)>,
+    pub(crate) unchanged: usize,
+}
+
+/// Compares two sets of findings. A finding reported twice by one side
+/// only matches twice on the other; each list is sorted by position.
+pub(crate) fn diff(baseline: &[Diagnostic], candidate: &[Diagnostic]) -> DiagnosticDiff {
+    let same_finding = |a: &Diagnostic, b: &Diagnostic| {
+        a.file == b.file && a.line == b.line && a.column == b.column && a.code == b.code && a.message == b.message
+    };
+    let mut unmatched: Vec<Option<&Diagnostic>> = baseline.iter().map(Some).collect();
+    let mut take = |found: &Diagnostic, same_severity: bool| {
+        unmatched
+            .iter_mut()
+            .find(|slot| {
+                slot.is_some_and(|b| same_finding(b, found) && (b.severity == found.severity) == same_severity)
+            })
+            .and_then(Option::take)
+    };
+
+    let mut diff = DiagnosticDiff::default();
+    let mut rest = Vec::new();
+    for found in candidate {
+        match take(found, true) {
+            Some(_) => diff.unchanged += 1,
+            None => rest.push(found),
+        }
+    }
+    for found in rest {
+        match take(found, false) {
+            Some(before) => diff.changed.push((before.clone(), found.clone())),
+            None => diff.added.push(found.clone()),
+        }
+    }
+    diff.removed = unmatched.into_iter().flatten().cloned().collect();
+
+    let position = |d: &Diagnostic| (d.file.clone(), d.line, d.column);
+    diff.added.sort_by_key(position);
+    diff.removed.sort_by_key(position);
+    diff.changed.sort_by_key(|(before, _)| position(before));
+    diff
+}
+
+fn file_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{path}")
+    } else {
+        format!("file:///{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VET_OUTPUT: &str = r#"# example.com/app
+{
+  "example.com/app": {
+    "mtlog": [
+      { "posn": "/work/app/main.go:12:2", "message": "[MTLOG001] template has 2 properties but 1 argument provided" },
+      { "posn": "/work/app/main.go:20:14", "message": "[MTLOG004] warning: property 'userId' should be PascalCase" }
+    ]
+  }
+}
+# example.com/app/internal
+{
+  "example.com/app/internal": {
+    "mtlog": [
+      { "posn": "/work/app/internal/db.go:7:3", "message": "[MTLOG007] suggestion: consider defining a constant for key 'user_id'" },
+      { "posn": "/elsewhere/gen.go:1:1", "message": "dynamic template" }
+    ]
+  }
+}
+"#;
+
+    fn diagnostics() -> Vec<Diagnostic> {
+        parse_vet_json(VET_OUTPUT).unwrap()
+    }
+
+    #[test]
+    fn test_parse_vet_json() {
+        let diagnostics = diagnostics();
+
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(
+            diagnostics[1],
+            Diagnostic {
+                file: "/work/app/main.go".to_string(),
+                line: 20,
+                column: 14,
+                code: Some("MTLOG004".to_string()),
+                severity: Severity::Warning,
+                message: "property 'userId' should be PascalCase".to_string(),
+            }
+        );
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[2].severity, Severity::Suggestion);
+        assert_eq!(diagnostics[3].code, None);
+    }
+
+    #[test]
+    fn test_parse_posn_windows_path() {
+        assert_eq!(parse_posn("C:\\work\\main.go:3:9"), Some(("C:\\work\\main.go".to_string(), 3, 9)));
+        assert_eq!(parse_posn("main.go"), None);
+    }
+
+    #[test]
+    fn test_parse_vet_json_rejects_garbage() {
+        assert!(parse_vet_json("not json").is_err());
+        assert_eq!(parse_vet_json(""), Ok(Vec::new()));
+    }
+
+    /// Results are mapped to rules, SARIF levels, and root-relative locations.
+    #[test]
+    fn test_to_sarif() {
+        let sarif = to_sarif(&diagnostics(), "/work/app/");
+        let run = &sarif["runs"][0];
+
+        assert_eq!(sarif["version"], "2.1.0");
+        let rule_ids: Vec<&str> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(rule_ids, vec!["MTLOG001", "MTLOG004", "MTLOG007", "mtlog"]);
+        assert_eq!(
+            run["tool"]["driver"]["rules"][1]["shortDescription"]["text"],
+            "Property names should be PascalCase"
+        );
+        assert_eq!(run["originalUriBaseIds"]["%SRCROOT%"]["uri"], "file:///work/app/");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            results[1],
+            json!({
+                "ruleId": "MTLOG004",
+                "ruleIndex": 1,
+                "level": "warning",
+                "message": { "text": "property 'userId' should be PascalCase" },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "main.go", "uriBaseId": "%SRCROOT%" },
+                        "region": { "startLine": 20, "startColumn": 14 }
+                    }
+                }]
+            })
+        );
+        assert_eq!(results[2]["level"], "note");
+        assert_eq!(
+            results[2]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "internal/db.go"
+        );
+        assert_eq!(
+            results[3]["locations"][0]["physicalLocation"]["artifactLocation"],
+            json!({ "uri": "file:///elsewhere/gen.go" })
+        );
+    }
+
+    fn finding(line: u64, code: &str, severity: Severity, message: &str) -> Diagnostic {
+        Diagnostic {
+            file: "/work/app/main.go".to_string(),
+            line,
+            column: 2,
+            code: Some(code.to_string()),
+            severity,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let baseline = vec![
+            finding(12, "MTLOG001", Severity::Error, "template has 2 properties but 1 argument provided"),
+            finding(20, "MTLOG004", Severity::Warning, "property 'userId' should be PascalCase"),
+            finding(31, "MTLOG007", Severity::Suggestion, "context key should be a constant"),
+        ];
+        let candidate = vec![
+            finding(40, "MTLOG003", Severity::Warning, "duplicate property 'Id'"),
+            finding(20, "MTLOG004", Severity::Error, "property 'userId' should be PascalCase"),
+            finding(12, "MTLOG001", Severity::Error, "template has 2 properties but 1 argument provided"),
+            finding(9, "MTLOG002", Severity::Error, "invalid format specifier 'Z'"),
+        ];
+
+        let diff = diff(&baseline, &candidate);
+
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.added, vec![candidate[3].clone(), candidate[0].clone()]);
+        assert_eq!(diff.removed, vec![baseline[2].clone()]);
+        assert_eq!(diff.changed, vec![(baseline[1].clone(), candidate[1].clone())]);
+    }
+
+    /// Repeated findings match one for one, and an exact match wins over a severity change.
+    #[test]
+    fn test_diff_repeated_findings() {
+        let warning = finding(20, "MTLOG004", Severity::Warning, "property 'userId' should be PascalCase");
+        let error = Diagnostic { severity: Severity::Error, ..warning.clone() };
+
+        let diff = diff(&[warning.clone(), error.clone()], &[error.clone(), error.clone(), error.clone()]);
+
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.changed, vec![(warning, error.clone())]);
+        assert_eq!(diff.added, vec![error]);
+        assert!(diff.removed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let findings = vec![finding(12, "MTLOG001", Severity::Error, "mismatch")];
+
+        assert_eq!(diff(&findings, &findings), DiagnosticDiff { unchanged: 1, ..Default::default() });
+        assert_eq!(diff(&[], &[]), DiagnosticDiff::default());
+    }
+
+    #[test]
+    fn test_to_sarif_empty() {
+        let sarif = to_sarif(&[], "/work/app");
+
+        assert_eq!(sarif["runs"][0]["results"], json!([]));
+        assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"], json!([]));
+    }
+}
@@ -0,0 +1,167 @@
+//! Option overrides that last for the current Zed session.
+//!
+//! Interactive commands such as `/mtlog-profile` record their changes here
+//! instead of editing settings files. The overrides are layered on top of the
+//! configured options whenever mtlog-lsp is (re)started, and `/mtlog-reset`
+//! returns to the configured settings by clearing them in one go.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::settings::{self, EffectiveSettings, Source};
+
+/// Overrides of the configured settings for this session.
+#[derive(Debug, Default)]
+pub(crate) struct SessionOverrides {
+    /// Profile selected in place of the configured `profile`, by its canonical name.
+    profile: Option<&'static str>,
+}
+
+impl SessionOverrides {
+    /// Selects `profile` until the next reset, matching its name regardless
+    /// of case. Fails for a name that is not a profile.
+    pub(crate) fn set_profile(&mut self, profile: &str) -> Result<&'static str, String> {
+        let name = settings::profile_names()
+            .find(|name| name.eq_ignore_ascii_case(profile.trim()))
+            .ok_or_else(|| {
+                let names: Vec<&str> = settings::profile_names().collect();
+                format!("unknown profile \"{profile}\"; expected one of {}", names.join(", "))
+            })?;
+        self.profile = Some(name);
+        Ok(name)
+    }
+
+    /// Removes every override, returning a description of each one.
+    pub(crate) fn clear(&mut self) -> Vec<String> {
+        self.profile.take().map(|profile| format!("profile \"{profile}\"")).into_iter().collect()
+    }
+
+    /// Layers the overrides on top of `effective`, marking each as coming
+    /// from the session. As with a configured profile, options set
+    /// explicitly in the settings keep their values.
+    pub(crate) fn apply(&self, effective: &mut EffectiveSettings) {
+        let Some(values) = self.profile.and_then(settings::profile_overrides) else {
+            return;
+        };
+        let Some(options) = effective.options.as_object_mut() else {
+            effective
+                .warnings
+                .push("session overrides ignored: initialization_options is not an object".to_string());
+            return;
+        };
+        for (key, value) in values {
+            let explicit = matches!(
+                effective.sources.get(key),
+                Some(Source::Settings | Source::InitializationOptions)
+            );
+            if !explicit {
+                options.insert(key.to_string(), value);
+                effective.sources.insert(key.to_string(), Source::Session);
+            }
+        }
+    }
+
+    /// A stable string that changes whenever the overrides do.
+    pub(crate) fn fingerprint(&self) -> String {
+        self.profile.unwrap_or_default().to_string()
+    }
+}
+
+/// Locks state shared with the slash commands. Updates are single map or
+/// queue operations, so a poisoned lock still holds consistent state.
+pub(crate) fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::{serde_json::json, settings::LspSettings};
+
+    fn configured() -> EffectiveSettings {
+        settings::initialization_options(&LspSettings {
+            settings: Some(json!({ "profile": "strict", "samplingChecks": false, "suppressedCodes": ["MTLOG004"] })),
+            ..Default::default()
+        })
+    }
+
+    /// The session profile replaces the configured one and is attributed to
+    /// the session, while explicit settings keep their values.
+    #[test]
+    fn test_apply_profile() {
+        let mut overrides = SessionOverrides::default();
+        assert_eq!(overrides.set_profile("None"), Ok("none"));
+
+        let mut effective = configured();
+        overrides.apply(&mut effective);
+
+        assert_eq!(effective.options["strictMode"], json!(false));
+        assert_eq!(effective.sources["strictMode"], Source::Session);
+        assert_eq!(effective.options["samplingChecks"], json!(false));
+        assert_eq!(effective.sources["samplingChecks"], Source::Settings);
+        assert_eq!(effective.options["suppressedCodes"], json!(["MTLOG004"]));
+    }
+
+    /// Forwarded initialization options gain the profile's values unless they set them.
+    #[test]
+    fn test_apply_profile_to_initialization_options() {
+        let mut overrides = SessionOverrides::default();
+        overrides.set_profile("strict").unwrap();
+        let mut effective = settings::initialization_options(&LspSettings {
+            initialization_options: Some(json!({ "strictMode": false })),
+            ..Default::default()
+        });
+
+        overrides.apply(&mut effective);
+
+        assert_eq!(effective.options["strictMode"], json!(false));
+        assert_eq!(effective.options["samplingChecks"], json!(true));
+        assert_eq!(effective.sources["samplingChecks"], Source::Session);
+    }
+
+    #[test]
+    fn test_set_unknown_profile() {
+        let mut overrides = SessionOverrides::default();
+
+        let err = overrides.set_profile("paranoid").unwrap_err();
+
+        assert_eq!(err, "unknown profile \"paranoid\"; expected one of strict, none");
+        assert!(overrides.clear().is_empty());
+    }
+
+    /// Reset removes every override from the effective configuration.
+    #[test]
+    fn test_clear_restores_configured_settings() {
+        let mut overrides = SessionOverrides::default();
+        overrides.set_profile("none").unwrap();
+        let before = overrides.fingerprint();
+
+        assert_eq!(overrides.clear(), vec!["profile \"none\""]);
+
+        let mut effective = configured();
+        overrides.apply(&mut effective);
+        let expected = configured();
+        assert_eq!(effective.options, expected.options);
+        assert_eq!(effective.sources, expected.sources);
+        assert_ne!(overrides.fingerprint(), before);
+        assert!(overrides.clear().is_empty());
+    }
+
+    /// Non-object initialization options fall back to the shaped defaults,
+    /// which the overrides then apply to.
+    #[test]
+    fn test_apply_to_non_object_options() {
+        let mut overrides = SessionOverrides::default();
+        overrides.set_profile("strict").unwrap();
+        let mut effective = settings::initialization_options(&LspSettings {
+            initialization_options: Some(json!(["unexpected"])),
+            ..Default::default()
+        });
+
+        overrides.apply(&mut effective);
+
+        assert_eq!(effective.options["strictMode"], json!(true));
+        assert_eq!(effective.sources["strictMode"], Source::Session);
+        assert_eq!(effective.warnings.len(), 1);
+        assert!(effective.warnings[0].starts_with("ignoring initialization_options"));
+    }
+}
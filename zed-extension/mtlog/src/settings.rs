@@ -0,0 +1,2107 @@
+//! Conversion of Zed's LSP settings into the initialization options
+//! understood by mtlog-lsp.
+//!
+//! Users can configure the analyzer either through `initialization_options`
+//! (forwarded as-is) or through the older `settings` field, which is shaped
+//! into the option set mtlog-lsp expects with defaults filled in.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use zed_extension_api::{
+    serde_json::{self, json, Map, Value},
+    settings::LspSettings,
+};
+
+use crate::launch;
+use crate::probe::{self, VersionProbe};
+use crate::quiet_hours::{QuietHours, QUIET_HOURS_KEY};
+
+/// Name of the language server in Zed's `lsp` settings.
+pub(crate) const LANGUAGE_SERVER_NAME: &str = "mtlog-analyzer";
+
+/// Option turning on strict format specifier validation.
+const STRICT_MODE_KEY: &str = "strictMode";
+
+/// Version of the contract between this extension and mtlog-lsp, sent as
+/// the `protocolVersion` option and compared with what the binary reports.
+pub(crate) const PROTOCOL_VERSION: u64 = 1;
+
+/// Option carrying [`PROTOCOL_VERSION`] to the server.
+const PROTOCOL_VERSION_KEY: &str = "protocolVersion";
+
+/// Option naming the rule documentation set that matches the binary.
+/// Forwarded only; requires mtlog-lsp support.
+const DOCS_VERSION_KEY: &str = "docsVersion";
+
+/// Option asking mtlog-lsp to report analysis timings.
+/// Forwarded only; requires mtlog-lsp support.
+pub(crate) const ENABLE_METRICS_KEY: &str = "enableMetrics";
+
+/// Key that older documentation nested the analyzer settings under.
+const LEGACY_WRAPPER_KEY: &str = "mtlog";
+
+/// Option turning the package-wide analysis passes on or off.
+/// Forwarded only; requires mtlog-lsp support.
+pub(crate) const CROSS_FILE_ANALYSIS_KEY: &str = "crossFileAnalysis";
+
+/// Alternative spellings accepted in `settings`, mapped to the option they
+/// stand for. The canonical key wins when both are present.
+const OPTION_ALIASES: &[(&str, &str)] = &[("cross_file_analysis", CROSS_FILE_ANALYSIS_KEY)];
+
+/// Option enabling the experimental sampling API checks.
+/// Forwarded only; requires mtlog-lsp support.
+const SAMPLING_CHECKS_KEY: &str = "samplingChecks";
+
+/// Setting selecting a preset from [`PROFILES`].
+const PROFILE_KEY: &str = "profile";
+
+/// Named presets of option values. A profile replaces the built-in default
+/// of each option it lists; options set explicitly still win.
+const PROFILES: &[(&str, &[(&str, bool)])] = &[("strict", &[(STRICT_MODE_KEY, true), (SAMPLING_CHECKS_KEY, true)])];
+
+/// Session profile that selects no preset, leaving the built-in defaults.
+const NO_PROFILE: &str = "none";
+
+/// Option configuring when MTLOG007 suggests extracting a string constant.
+/// Forwarded only; requires mtlog-lsp support.
+const STRING_CONSTANTS_KEY: &str = "stringConstants";
+
+/// Option listing helpers that wrap `With()` and return an enriched logger.
+/// Forwarded only; requires mtlog-lsp support.
+const CONTEXT_ENRICHERS_KEY: &str = "contextEnrichers";
+
+/// Options keeping files out of analysis, listed by `/mtlog-excluded`.
+/// Forwarded only; requires mtlog-lsp support.
+pub(crate) const EXCLUDE_GLOBS_KEY: &str = "excludeGlobs";
+pub(crate) const IGNORE_DIRS_KEY: &str = "ignoreDirs";
+pub(crate) const SKIP_VENDOR_KEY: &str = "skipVendor";
+pub(crate) const SKIP_GENERATED_KEY: &str = "skipGenerated";
+
+/// Option limiting how long the server spends analyzing a single file.
+/// Forwarded only; requires mtlog-lsp support.
+const ANALYSIS_TIMEOUT_KEY: &str = "analysisTimeoutMs";
+
+/// Options that only newer mtlog-lsp releases understand, with the first
+/// release to support each. Older servers silently ignore them. No warning
+/// is given while an option is still [forwarded only](is_forwarded_only).
+const MINIMUM_SERVER_VERSIONS: &[(&str, &str)] = &[
+    (STRING_CONSTANTS_KEY, "0.11.0"),
+    (CONTEXT_ENRICHERS_KEY, "0.11.0"),
+    (ANALYSIS_TIMEOUT_KEY, "0.11.0"),
+    (SAMPLING_CHECKS_KEY, "0.11.0"),
+];
+
+/// Setting that controls how many consecutive failed launches are tolerated.
+pub(crate) const MAX_FAILED_LAUNCHES_KEY: &str = "maxFailedLaunches";
+
+/// Setting capping how many worktrees get their own server.
+const MAX_SERVERS_KEY: &str = "maxServers";
+
+/// Setting asking for a one-time whole-project analysis on first activation.
+const FIRST_RUN_REPORT_KEY: &str = "firstRunReport";
+
+/// Setting choosing whether a crash loop on the current settings is retried.
+const ON_FATAL_CONFIG_KEY: &str = "onFatalConfig";
+
+/// Setting listing extra directories to search for the binary.
+const EXTRA_BIN_DIRS_KEY: &str = "extraBinDirs";
+
+/// Setting replacing the home directory in `/mtlog-support-bundle`.
+const REDACT_PATHS_KEY: &str = "redactPaths";
+
+/// Setting pinning the release the download fallback fetches.
+const LSP_VERSION_KEY: &str = "lspVersion";
+
+/// Object grouping the `go install` fallback settings.
+const INSTALL_KEY: &str = "install";
+
+/// Object grouping binary settings that Zed's own `binary` field cannot hold.
+const BINARY_KEY: &str = "binary";
+
+/// Where the value of a top-level option came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Source {
+    /// Built-in default, used when the option is unset or invalid.
+    Default,
+    /// The preset selected by the `profile` setting.
+    Profile,
+    /// The `settings` field of the Zed LSP settings.
+    Settings,
+    /// The `initialization_options` field of the Zed LSP settings.
+    InitializationOptions,
+    /// An override set for the current session, see [`crate::session`].
+    Session,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Default => "default",
+            Source::Profile => "profile",
+            Source::Settings => "settings",
+            Source::InitializationOptions => "initialization_options",
+            Source::Session => "session",
+        })
+    }
+}
+
+/// Initialization options derived from the user's settings, together with
+/// the source of each top-level option and any warnings produced while
+/// reading them.
+#[derive(Clone)]
+pub(crate) struct EffectiveSettings {
+    pub(crate) options: Value,
+    pub(crate) sources: BTreeMap<String, Source>,
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Builds the initialization options for mtlog-lsp from the user's settings.
+///
+/// `initialization_options` take precedence and are forwarded as-is; otherwise
+/// the `settings` field is shaped into the options mtlog-lsp expects.
+/// Configuration nested under a legacy `"mtlog"` key is unwrapped first.
+pub(crate) fn initialization_options(lsp_settings: &LspSettings) -> EffectiveSettings {
+    let mut warnings = Vec::new();
+
+    // Some tooling writes the object as a JSON string, which is parsed first
+    let stringified_init_options = lsp_settings
+        .initialization_options
+        .as_ref()
+        .and_then(Value::as_str)
+        .map(|text| {
+            parse_stringified("initialization_options", text, "using settings and defaults instead", &mut warnings)
+        });
+    let init_options = match stringified_init_options {
+        Some(parsed) => parsed,
+        None => lsp_settings.initialization_options.clone(),
+    };
+
+    // Anything but an object would be rejected by the server, so it is
+    // ignored in favor of `settings`
+    let init_options = init_options.filter(|init_options| {
+        let is_object = init_options.is_object();
+        if !is_object {
+            warnings.push(format!(
+                "ignoring initialization_options: expected an object, found {}; using settings and defaults instead",
+                json_type(init_options)
+            ));
+        }
+        is_object
+    });
+    if let Some(init_options) = init_options {
+        let options = unwrap_legacy_wrapper(&init_options, "initialization_options", &mut warnings);
+        let sources = options
+            .as_object()
+            .into_iter()
+            .flat_map(Map::keys)
+            .map(|key| (key.clone(), Source::InitializationOptions))
+            .collect();
+        return EffectiveSettings {
+            options: options.clone(),
+            sources,
+            warnings,
+        };
+    }
+
+    let settings = match &lsp_settings.settings {
+        Some(Value::String(text)) => parse_stringified("settings", text, "using the defaults", &mut warnings),
+        settings => settings.clone(),
+    };
+    let settings = settings.unwrap_or_else(|| Value::Object(Map::new()));
+    let settings = unwrap_legacy_wrapper(&settings, "settings", &mut warnings);
+
+    let mut sources = BTreeMap::new();
+    let options = shape(settings, &mut sources, &mut warnings);
+    EffectiveSettings {
+        options,
+        sources,
+        warnings,
+    }
+}
+
+/// Parses `text`, a field's value that tooling wrote as a JSON string, into
+/// the object it holds. Anything else is dropped with a warning ending in
+/// `fallback`.
+fn parse_stringified(field: &str, text: &str, fallback: &str, warnings: &mut Vec<String>) -> Option<Value> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(parsed) if parsed.is_object() => {
+            warnings.push(format!("{field} is a JSON string rather than an object; using the object it contains"));
+            Some(parsed)
+        }
+        Ok(parsed) => {
+            warnings.push(format!(
+                "ignoring {field}: expected an object, found a string holding {}; {fallback}",
+                json_type(&parsed)
+            ));
+            None
+        }
+        Err(err) => {
+            warnings.push(format!(
+                "ignoring {field}: expected an object, found a string that is not JSON ({err}); {fallback}"
+            ));
+            None
+        }
+    }
+}
+
+/// The JSON type of `value`, for warnings.
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Like [`initialization_options`], for settings Zed may fail to provide.
+/// A failed lookup, for example for a `server` id the extension does not
+/// know, yields the default options with a warning instead of an error.
+pub(crate) fn initialization_options_for(lookup: Result<LspSettings, String>, server: &str) -> EffectiveSettings {
+    match lookup {
+        Ok(lsp_settings) => initialization_options(&lsp_settings),
+        Err(err) => {
+            let mut effective = initialization_options(&LspSettings::default());
+            effective
+                .warnings
+                .push(format!("could not read the settings for {server} ({err}); using the defaults"));
+            effective
+        }
+    }
+}
+
+/// Returns the number of consecutive failed launches tolerated before the
+/// extension stops restarting mtlog-lsp, falling back to the default for
+/// missing or non-positive values.
+pub(crate) fn max_failed_launches(lsp_settings: &LspSettings) -> u32 {
+    extension_setting(lsp_settings, MAX_FAILED_LAUNCHES_KEY)
+        .and_then(Value::as_u64)
+        .filter(|&max| max > 0)
+        .map_or(launch::DEFAULT_MAX_FAILED_LAUNCHES, |max| max.min(u32::MAX as u64) as u32)
+}
+
+/// Returns the `maxServers` cap; missing, zero, and invalid values mean no cap.
+pub(crate) fn max_servers(lsp_settings: &LspSettings) -> Option<u32> {
+    extension_setting(lsp_settings, MAX_SERVERS_KEY)
+        .and_then(Value::as_u64)
+        .filter(|&max| max > 0)
+        .map(|max| max.min(u32::MAX as u64) as u32)
+}
+
+/// Whether `firstRunReport` is set, off unless it is `true`.
+pub(crate) fn first_run_report(lsp_settings: &LspSettings) -> bool {
+    extension_setting(lsp_settings, FIRST_RUN_REPORT_KEY)
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Returns the raw `onFatalConfig` setting.
+pub(crate) fn on_fatal_config(lsp_settings: &LspSettings) -> Option<&Value> {
+    extension_setting(lsp_settings, ON_FATAL_CONFIG_KEY)
+}
+
+/// Returns the `extraBinDirs` entries, skipping anything that is not a
+/// non-empty string.
+pub(crate) fn extra_bin_dirs(lsp_settings: &LspSettings) -> Vec<String> {
+    extension_setting(lsp_settings, EXTRA_BIN_DIRS_KEY)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns the raw `cacheDir` setting.
+pub(crate) fn cache_dir(lsp_settings: &LspSettings) -> Option<&Value> {
+    extension_setting(lsp_settings, crate::cache::CACHE_DIR_KEY)
+}
+
+/// Returns the raw `locale` setting.
+pub(crate) fn locale(lsp_settings: &LspSettings) -> Option<&Value> {
+    extension_setting(lsp_settings, crate::locale::LOCALE_KEY)
+}
+
+/// Returns the raw `propertyDictionary` setting.
+pub(crate) fn property_dictionary(lsp_settings: &LspSettings) -> Option<&Value> {
+    extension_setting(lsp_settings, crate::property_dictionary::PROPERTY_DICTIONARY_KEY)
+}
+
+/// Whether `redactPaths` is set, off unless it is `true`.
+pub(crate) fn redact_paths(lsp_settings: &LspSettings) -> bool {
+    extension_setting(lsp_settings, REDACT_PATHS_KEY)
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Returns the raw `lspVersion` setting.
+pub(crate) fn lsp_version(lsp_settings: &LspSettings) -> Option<&Value> {
+    extension_setting(lsp_settings, LSP_VERSION_KEY)
+}
+
+/// Returns the raw `warmupSample` setting.
+pub(crate) fn warmup_sample(lsp_settings: &LspSettings) -> Option<&Value> {
+    extension_setting(lsp_settings, crate::warmup::WARMUP_SAMPLE_KEY)
+}
+
+/// Returns the raw `suppressionsFile` setting.
+pub(crate) fn suppressions_file(lsp_settings: &LspSettings) -> Option<&Value> {
+    extension_setting(lsp_settings, crate::suppressions::SUPPRESSIONS_FILE_KEY)
+}
+
+/// Returns the `install.gobin` setting, if it is a string.
+pub(crate) fn install_gobin(lsp_settings: &LspSettings) -> Option<&str> {
+    grouped_setting(lsp_settings, INSTALL_KEY, "gobin").and_then(Value::as_str)
+}
+
+/// Returns the raw `install.method` setting.
+pub(crate) fn install_method(lsp_settings: &LspSettings) -> Option<&Value> {
+    grouped_setting(lsp_settings, INSTALL_KEY, "method")
+}
+
+/// Returns `binary.path`, trimmed. A path that is empty or only whitespace,
+/// usually a copy-paste slip, is treated as unset with a warning.
+pub(crate) fn binary_path<'a>(lsp_settings: &'a LspSettings, warnings: &mut Vec<String>) -> Option<&'a str> {
+    let path = lsp_settings.binary.as_ref()?.path.as_deref()?;
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        warnings.push(format!("ignoring empty binary.path {path:?}; searching the other locations"));
+        return None;
+    }
+    Some(trimmed)
+}
+
+/// Whether `binary.pathStrict` is set, so an explicit `binary.path` that
+/// cannot be started is an error instead of falling back to discovery.
+pub(crate) fn binary_path_strict(lsp_settings: &LspSettings) -> bool {
+    grouped_setting(lsp_settings, BINARY_KEY, "pathStrict")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Hash of the settings that decide which binary discovery selects:
+/// `binary.path`, `binary.pathStrict`, `extraBinDirs`, `install.gobin`, and
+/// `lspVersion`.
+/// A cached binary is only reused while the hash stays the same.
+pub(crate) fn resolution_hash(lsp_settings: &LspSettings) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    binary_path(lsp_settings, &mut Vec::new()).hash(&mut hasher);
+    binary_path_strict(lsp_settings).hash(&mut hasher);
+    extra_bin_dirs(lsp_settings).hash(&mut hasher);
+    install_gobin(lsp_settings).hash(&mut hasher);
+    crate::download::pinned_tag(lsp_version(lsp_settings)).ok().flatten().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the raw `binary.sha256` setting. Zed drops unknown keys from the
+/// top-level `binary` object, so it is read from `settings`.
+pub(crate) fn binary_sha256(lsp_settings: &LspSettings) -> Option<&Value> {
+    grouped_setting(lsp_settings, BINARY_KEY, "sha256")
+}
+
+/// Looks up `key` in the `group` object, also accepting the flat
+/// `"<group>.<key>"` spelling.
+fn grouped_setting<'a>(lsp_settings: &'a LspSettings, group: &str, key: &str) -> Option<&'a Value> {
+    extension_setting(lsp_settings, group)
+        .and_then(|object| object.get(key))
+        .or_else(|| extension_setting(lsp_settings, &format!("{group}.{key}")))
+}
+
+/// Looks up a setting read by the extension itself rather than mtlog-lsp.
+fn extension_setting<'a>(lsp_settings: &'a LspSettings, key: &str) -> Option<&'a Value> {
+    lsp_settings
+        .settings
+        .as_ref()
+        .map(|settings| unwrap_legacy_wrapper(settings, "settings", &mut Vec::new()))
+        .and_then(|settings| settings.get(key))
+}
+
+/// Returns the object nested under `"mtlog"` when it is the only key present,
+/// recording a deprecation warning. Any other value is returned unchanged.
+fn unwrap_legacy_wrapper<'a>(value: &'a Value, field: &str, warnings: &mut Vec<String>) -> &'a Value {
+    match value.as_object() {
+        Some(object) if object.len() == 1 => match object.get(LEGACY_WRAPPER_KEY) {
+            Some(inner) if inner.is_object() => {
+                warnings.push(format!(
+                    "nesting {field} under \"{LEGACY_WRAPPER_KEY}\" is deprecated; \
+                     move the keys directly into {field}"
+                ));
+                inner
+            }
+            _ => value,
+        },
+        _ => value,
+    }
+}
+
+/// The mtlog log levels, from least to most severe.
+const LOG_LEVELS: &[&str] = &["Verbose", "Debug", "Information", "Warning", "Error", "Fatal"];
+
+/// When the server re-analyzes a file: as it is edited, when it is saved, or
+/// only on request.
+const ANALYSIS_TIMINGS: &[&str] = &["onType", "onSave", "manual"];
+
+/// Casing conventions the server can enforce on property names.
+const NAMING_CONVENTIONS: &[&str] = &["any", "pascal", "camel", "snake"];
+
+/// An option forwarded to mtlog-lsp when shaping `settings`.
+struct ForwardedOption {
+    key: &'static str,
+    kind: OptionKind,
+}
+
+/// The accepted shape of a forwarded option, along with its default.
+enum OptionKind {
+    Bool(bool),
+    StringList,
+    StringMap,
+    /// File globs, each non-empty with balanced `[]` and `{}`.
+    GlobList,
+    /// Property names, each an identifier such as `id`, matched exactly.
+    IdentifierList,
+    /// Format specifiers as written after the colon in `{Price:F2}`.
+    SpecifierList,
+    /// Go functions such as `Infof`, `logx.Infof`, or
+    /// `example.com/app/logx.(*Logger).Infof`.
+    FunctionList,
+    /// Comment directives such as `//nolint:mtlog`, each a non-empty single
+    /// line listed once, forwarded trimmed.
+    DirectiveList,
+    /// A non-empty string, forwarded trimmed.
+    Text(&'static str),
+    /// A short single-line string without a default of its own: unless set,
+    /// the option is left out so mtlog-lsp uses its built-in value.
+    Label { max_len: usize },
+    /// A non-negative whole number.
+    Count(u64),
+    /// A whole number, 1 or more.
+    Positive(u64),
+    /// A duration in whole milliseconds of at least `min`, likewise left out
+    /// unless set.
+    Millis { min: u64 },
+    /// The `{threshold, scope}` object tuning MTLOG007's constant suggestions.
+    StringConstants,
+    /// A `{start, end}` range of local `HH:MM` times, left out unless set.
+    QuietHours,
+    /// The `{open, close}` pair of single characters delimiting template
+    /// properties.
+    TemplateDelimiters,
+    /// `{function, argIndices}` entries naming `With()`-style helpers and the
+    /// zero-based positions of their key/value arguments.
+    ContextEnrichers,
+    /// One of a fixed set of values, matched case-insensitively and
+    /// forwarded in its canonical spelling.
+    Choice {
+        values: &'static [&'static str],
+        default: &'static str,
+    },
+}
+
+/// Options mtlog-lsp reads from its initialization options. Every other
+/// option, from [`FORWARDED_OPTIONS`] or elsewhere, is forwarded only: it is
+/// validated and sent, and requires mtlog-lsp support to take effect.
+const SERVER_READ_OPTIONS: &[&str] =
+    &["suppressedCodes", "severityOverrides", "disableAll", "commonKeys", STRICT_MODE_KEY, "ignoreDynamicTemplates"];
+
+/// Options shaped from `settings`, with the defaults mtlog-lsp assumes. Only
+/// those in [`SERVER_READ_OPTIONS`] are read by mtlog-lsp today; the rest are
+/// forwarded only and require mtlog-lsp support.
+const FORWARDED_OPTIONS: &[ForwardedOption] = &[
+    ForwardedOption { key: "suppressedCodes", kind: OptionKind::StringList },
+    ForwardedOption { key: "severityOverrides", kind: OptionKind::StringMap },
+    ForwardedOption { key: "disableAll", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "commonKeys", kind: OptionKind::StringList },
+    ForwardedOption { key: STRICT_MODE_KEY, kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "ignoreDynamicTemplates", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "flagEmptyTemplates", kind: OptionKind::Bool(true) },
+    ForwardedOption {
+        key: "minConsideredLevel",
+        kind: OptionKind::Choice { values: LOG_LEVELS, default: "Verbose" },
+    },
+    ForwardedOption { key: "checkDynamicLevels", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "additionalFileGlobs", kind: OptionKind::GlobList },
+    ForwardedOption { key: EXCLUDE_GLOBS_KEY, kind: OptionKind::GlobList },
+    ForwardedOption { key: IGNORE_DIRS_KEY, kind: OptionKind::StringList },
+    ForwardedOption { key: SKIP_VENDOR_KEY, kind: OptionKind::Bool(true) },
+    ForwardedOption { key: SKIP_GENERATED_KEY, kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "notifyOnError", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: QUIET_HOURS_KEY, kind: OptionKind::QuietHours },
+    ForwardedOption { key: "analyzeChangedOnly", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "changedBaseRef", kind: OptionKind::Text("HEAD") },
+    ForwardedOption { key: ENABLE_METRICS_KEY, kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "allowPositionalTemplates", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "templateDelimiters", kind: OptionKind::TemplateDelimiters },
+    ForwardedOption { key: "maxPlaceholders", kind: OptionKind::Count(0) },
+    ForwardedOption { key: "maxDestructureDepth", kind: OptionKind::Positive(5) },
+    ForwardedOption { key: "requireUnitsOnNumerics", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "checkErrorWrapping", kind: OptionKind::Bool(true) },
+    ForwardedOption { key: STRING_CONSTANTS_KEY, kind: OptionKind::StringConstants },
+    ForwardedOption { key: "namingExceptions", kind: OptionKind::IdentifierList },
+    ForwardedOption { key: "checkDuplicateProperties", kind: OptionKind::Bool(true) },
+    ForwardedOption { key: "allowDuplicateProperties", kind: OptionKind::IdentifierList },
+    ForwardedOption {
+        key: "propertyNamingConvention",
+        kind: OptionKind::Choice { values: NAMING_CONVENTIONS, default: "any" },
+    },
+    ForwardedOption { key: "allowedFormatSpecifiers", kind: OptionKind::SpecifierList },
+    ForwardedOption { key: CONTEXT_ENRICHERS_KEY, kind: OptionKind::ContextEnrichers },
+    ForwardedOption { key: "loggerWrappers", kind: OptionKind::FunctionList },
+    ForwardedOption { key: "enableScopedSuppression", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "scopedSuppressionAnnotation", kind: OptionKind::Text("//mtlog:relax") },
+    ForwardedOption { key: "ignoreDirectives", kind: OptionKind::DirectiveList },
+    ForwardedOption { key: "fileProfileAnnotation", kind: OptionKind::Label { max_len: 64 } },
+    ForwardedOption { key: CROSS_FILE_ANALYSIS_KEY, kind: OptionKind::Bool(true) },
+    ForwardedOption { key: ANALYSIS_TIMEOUT_KEY, kind: OptionKind::Millis { min: 100 } },
+    ForwardedOption {
+        key: "analysisTiming",
+        kind: OptionKind::Choice { values: ANALYSIS_TIMINGS, default: "onType" },
+    },
+    ForwardedOption { key: "stripMessagePrefix", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "collapseRepeats", kind: OptionKind::Bool(false) },
+    ForwardedOption { key: SAMPLING_CHECKS_KEY, kind: OptionKind::Bool(false) },
+    ForwardedOption { key: "diagnosticSource", kind: OptionKind::Label { max_len: 32 } },
+];
+
+/// Repetitions after which MTLOG007 suggests a constant, unless configured.
+const DEFAULT_STRING_CONSTANT_THRESHOLD: u64 = 3;
+
+/// Where MTLOG007 counts repetitions: within one file or across the package.
+const STRING_CONSTANT_SCOPES: &[&str] = &["file", "package"];
+
+/// Property delimiters mtlog templates use, unless configured.
+const DEFAULT_TEMPLATE_DELIMITERS: (char, char) = ('{', '}');
+
+/// Extensions of files that are never text, which a glob should not target.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "a", "bin", "dll", "dylib", "exe", "gif", "gz", "ico", "jpeg", "jpg", "o", "pdf", "png", "so", "tar", "wasm",
+    "zip",
+];
+
+impl OptionKind {
+    /// The value forwarded when the option is unset or invalid, if any.
+    fn default_value(&self) -> Option<Value> {
+        match self {
+            OptionKind::Bool(default) => Some(Value::Bool(*default)),
+            OptionKind::Count(default) | OptionKind::Positive(default) => Some(json!(default)),
+            OptionKind::StringList
+            | OptionKind::GlobList
+            | OptionKind::IdentifierList
+            | OptionKind::SpecifierList
+            | OptionKind::FunctionList
+            | OptionKind::DirectiveList
+            | OptionKind::ContextEnrichers => {
+                Some(Value::Array(Vec::new()))
+            }
+            OptionKind::StringMap => Some(Value::Object(Map::new())),
+            OptionKind::Text(default) | OptionKind::Choice { default, .. } => {
+                Some(Value::String(default.to_string()))
+            }
+            OptionKind::StringConstants => Some(json!({
+                "threshold": DEFAULT_STRING_CONSTANT_THRESHOLD,
+                "scope": STRING_CONSTANT_SCOPES[0],
+            })),
+            OptionKind::TemplateDelimiters => Some(json!({
+                "open": DEFAULT_TEMPLATE_DELIMITERS.0.to_string(),
+                "close": DEFAULT_TEMPLATE_DELIMITERS.1.to_string(),
+            })),
+            OptionKind::Label { .. } | OptionKind::Millis { .. } | OptionKind::QuietHours => None,
+        }
+    }
+
+    /// Returns the value to forward, or a description of what was expected.
+    fn validate(&self, value: &Value) -> Result<Value, String> {
+        match self {
+            OptionKind::Bool(_) => match value {
+                Value::Bool(_) => Ok(value.clone()),
+                _ => Err("expected true or false".to_string()),
+            },
+            OptionKind::StringList => match value.as_array() {
+                Some(items) if items.iter().all(Value::is_string) => Ok(value.clone()),
+                _ => Err("expected an array of strings".to_string()),
+            },
+            OptionKind::StringMap => match value.as_object() {
+                Some(entries) if entries.values().all(Value::is_string) => Ok(value.clone()),
+                _ => Err("expected an object with string values".to_string()),
+            },
+            OptionKind::GlobList => {
+                let globs = value.as_array().ok_or("expected an array of globs")?;
+                for glob in globs {
+                    match glob.as_str() {
+                        Some(glob) if is_valid_glob(glob) => {}
+                        _ => return Err(format!("{glob} is not a valid glob")),
+                    }
+                }
+                Ok(value.clone())
+            }
+            OptionKind::IdentifierList => {
+                let names = value.as_array().ok_or("expected an array of property names")?;
+                for name in names {
+                    match name.as_str() {
+                        Some(name) if is_identifier(name) => {}
+                        _ => return Err(format!("{name} is not a property name")),
+                    }
+                }
+                Ok(value.clone())
+            }
+            OptionKind::SpecifierList => {
+                let specifiers = value.as_array().ok_or("expected an array of format specifiers")?;
+                for specifier in specifiers {
+                    match specifier.as_str() {
+                        Some(specifier) if is_format_specifier(specifier) => {}
+                        Some(specifier) if specifier.starts_with(':') => {
+                            return Err(format!("write {} without the leading ':'", &specifier[1..]))
+                        }
+                        _ => return Err(format!("{specifier} is not a format specifier")),
+                    }
+                }
+                Ok(value.clone())
+            }
+            OptionKind::Text(_) => match value.as_str().map(str::trim) {
+                Some(text) if !text.is_empty() => Ok(Value::String(text.to_string())),
+                _ => Err("expected a non-empty string".to_string()),
+            },
+            OptionKind::DirectiveList => {
+                let entries = value.as_array().ok_or("expected an array of comment directives")?;
+                let mut directives: Vec<&str> = Vec::new();
+                for entry in entries {
+                    match entry.as_str().map(str::trim) {
+                        Some(directive) if directives.contains(&directive) => {
+                            return Err(format!("\"{directive}\" is listed more than once"));
+                        }
+                        Some(directive) if !directive.is_empty() && !directive.contains(['\n', '\r']) => {
+                            directives.push(directive);
+                        }
+                        _ => return Err(format!("{entry} is not a single-line comment directive")),
+                    }
+                }
+                Ok(json!(directives))
+            }
+            OptionKind::Label { max_len } => match value.as_str().map(str::trim) {
+                Some(label)
+                    if !label.is_empty() && label.chars().count() <= *max_len && !label.contains(['\n', '\r']) =>
+                {
+                    Ok(Value::String(label.to_string()))
+                }
+                _ => Err(format!("expected a single-line string of at most {max_len} characters")),
+            },
+            OptionKind::StringConstants => {
+                let object = value.as_object().ok_or("expected an object with threshold and scope")?;
+                if let Some(unknown) = object.keys().find(|key| !matches!(key.as_str(), "threshold" | "scope")) {
+                    return Err(format!("unknown key \"{unknown}\", expected threshold or scope"));
+                }
+                let threshold = match object.get("threshold") {
+                    None => DEFAULT_STRING_CONSTANT_THRESHOLD,
+                    Some(threshold) => threshold
+                        .as_u64()
+                        .filter(|&threshold| threshold >= 2)
+                        .ok_or("expected threshold to be an integer of at least 2")?,
+                };
+                let scope = match object.get("scope") {
+                    None => STRING_CONSTANT_SCOPES[0],
+                    Some(scope) => scope
+                        .as_str()
+                        .and_then(|given| STRING_CONSTANT_SCOPES.iter().find(|s| s.eq_ignore_ascii_case(given)))
+                        .ok_or_else(|| format!("expected scope to be one of {}", STRING_CONSTANT_SCOPES.join(", ")))?,
+                };
+                Ok(json!({ "threshold": threshold, "scope": scope }))
+            }
+            OptionKind::TemplateDelimiters => {
+                let object = value.as_object().ok_or("expected an object with open and close")?;
+                if let Some(unknown) = object.keys().find(|key| !matches!(key.as_str(), "open" | "close")) {
+                    return Err(format!("unknown key \"{unknown}\", expected open or close"));
+                }
+                let delimiter = |key: &str| {
+                    let given = object.get(key).and_then(Value::as_str).ok_or(format!("expected {key} to be a string"))?;
+                    let mut chars = given.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) if !c.is_whitespace() && !c.is_alphanumeric() => Ok(c),
+                        _ => Err(format!("expected {key} to be a single symbol character, got \"{given}\"")),
+                    }
+                };
+                let (open, close) = (delimiter("open")?, delimiter("close")?);
+                if open == close {
+                    return Err("expected open and close to be different characters".to_string());
+                }
+                Ok(json!({ "open": open.to_string(), "close": close.to_string() }))
+            }
+            OptionKind::FunctionList => {
+                let functions = value.as_array().ok_or("expected an array of function names")?;
+                for function in functions {
+                    match function.as_str() {
+                        Some(function) if is_function_name(function) => {}
+                        _ => return Err(format!("{function} is not a function name like logx.Infof")),
+                    }
+                }
+                Ok(value.clone())
+            }
+            OptionKind::QuietHours => QuietHours::parse(value).map(QuietHours::to_value),
+            OptionKind::ContextEnrichers => {
+                let entries = value.as_array().ok_or("expected an array of {function, argIndices} objects")?;
+                for (i, entry) in entries.iter().enumerate() {
+                    validate_context_enricher(entry).map_err(|err| format!("entry {i}: {err}"))?;
+                }
+                Ok(value.clone())
+            }
+            OptionKind::Count(_) => match value.as_u64() {
+                Some(_) => Ok(value.clone()),
+                None => Err("expected a whole number, 0 or more".to_string()),
+            },
+            OptionKind::Positive(_) => match value.as_u64() {
+                Some(count) if count > 0 => Ok(value.clone()),
+                _ => Err("expected a whole number, 1 or more".to_string()),
+            },
+            OptionKind::Millis { min } => match value.as_u64() {
+                Some(millis) if millis >= *min => Ok(value.clone()),
+                _ => Err(format!("expected a whole number of milliseconds, at least {min}")),
+            },
+            OptionKind::Choice { values, .. } => value
+                .as_str()
+                .and_then(|given| values.iter().find(|v| v.eq_ignore_ascii_case(given)))
+                .map(|canonical| Value::String(canonical.to_string()))
+                .ok_or_else(|| format!("expected one of {}", values.join(", "))),
+        }
+    }
+
+    /// Returns warnings about a valid value that is still likely a mistake.
+    fn advisories(&self, key: &str, value: &Value) -> Vec<String> {
+        match self {
+            // Excluding non-text files is harmless, only analyzing them is not
+            OptionKind::GlobList if key != EXCLUDE_GLOBS_KEY => value
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .filter(|glob| matches_non_text_files(glob))
+                .map(|glob| format!("{key} pattern \"{glob}\" may match non-text files"))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Adds the extension's protocol version, which callers cannot override.
+pub(crate) fn set_protocol_version(effective: &mut EffectiveSettings) {
+    if let Some(options) = effective.options.as_object_mut() {
+        options.insert(PROTOCOL_VERSION_KEY.to_string(), json!(PROTOCOL_VERSION));
+        effective.sources.insert(PROTOCOL_VERSION_KEY.to_string(), Source::Default);
+    }
+}
+
+/// Adds the documentation set for the probed `version`, so hovers and
+/// explanations describe the rules as the installed binary implements them.
+/// Left out when the version is unknown.
+pub(crate) fn set_docs_version(effective: &mut EffectiveSettings, version: &VersionProbe) {
+    let (Some(options), Some(docs_version)) = (effective.options.as_object_mut(), probe::docs_version(version)) else {
+        return;
+    };
+    options.insert(DOCS_VERSION_KEY.to_string(), Value::String(docs_version));
+    effective.sources.insert(DOCS_VERSION_KEY.to_string(), Source::Default);
+}
+
+/// Whether mtlog-lsp ignores `key` whatever its version, because it only
+/// reads [`SERVER_READ_OPTIONS`].
+pub(crate) fn is_forwarded_only(key: &str) -> bool {
+    !SERVER_READ_OPTIONS.contains(&key)
+}
+
+/// Warns about configured options that the server, at the probed `version`,
+/// predates and will ignore. Options left at their defaults are not reported.
+pub(crate) fn unsupported_option_warnings(effective: &EffectiveSettings, version: &VersionProbe) -> Vec<String> {
+    version_gate_warnings(effective, version, MINIMUM_SERVER_VERSIONS)
+}
+
+/// [`unsupported_option_warnings`] against the given `(key, minimum)` gates.
+/// Forwarded-only options are skipped, since no version honors them yet.
+fn version_gate_warnings(effective: &EffectiveSettings, version: &VersionProbe, gates: &[(&str, &str)]) -> Vec<String> {
+    gates
+        .iter()
+        .filter(|(key, _)| !is_forwarded_only(key))
+        .filter(|(key, _)| effective.sources.get(*key).is_some_and(|source| *source != Source::Default))
+        .filter(|(_, minimum)| probe::is_older_than(version, minimum))
+        .map(|(key, minimum)| {
+            format!("{key} requires mtlog-lsp {minimum} or newer and will be ignored by this server ({version})")
+        })
+        .collect()
+}
+
+/// Whether `glob` is non-empty, single-line, and has balanced brackets and braces.
+fn is_valid_glob(glob: &str) -> bool {
+    let mut brackets = 0i32;
+    let mut braces = 0i32;
+    for c in glob.chars() {
+        match c {
+            '[' => brackets += 1,
+            ']' => brackets -= 1,
+            '{' => braces += 1,
+            '}' => braces -= 1,
+            '\n' | '\r' | '\0' => return false,
+            _ => {}
+        }
+        if brackets < 0 || braces < 0 {
+            return false;
+        }
+    }
+    !glob.trim().is_empty() && brackets == 0 && braces == 0
+}
+
+/// Checks one `contextEnrichers` entry: a fully qualified function such as
+/// `example.com/app/logging.WithRequest` and a non-empty list of argument
+/// positions.
+fn validate_context_enricher(entry: &Value) -> Result<(), String> {
+    let entry = entry.as_object().ok_or("expected an object")?;
+    if let Some(unknown) = entry.keys().find(|key| !matches!(key.as_str(), "function" | "argIndices")) {
+        return Err(format!("unknown key \"{unknown}\", expected function or argIndices"));
+    }
+    match entry.get("function").and_then(Value::as_str) {
+        Some(function)
+            if function.contains('.') && !function.ends_with('.') && !function.contains(char::is_whitespace) => {}
+        Some(function) => return Err(format!("function \"{function}\" is not a qualified name like pkg.Func")),
+        None => return Err("expected function to be a string".to_string()),
+    }
+    match entry.get("argIndices").and_then(Value::as_array) {
+        Some(indices) if !indices.is_empty() && indices.iter().all(Value::is_u64) => Ok(()),
+        Some(_) => Err("expected argIndices to be non-negative integers".to_string()),
+        None => Err("expected argIndices to be an array of argument positions".to_string()),
+    }
+}
+
+/// Whether `name` is a letter or underscore followed by letters, digits, and
+/// underscores, which also rules out braces and whitespace.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Whether `name` is a Go function: dot-separated identifiers, where a
+/// receiver may be written `(*Type)`, optionally after an import path. A
+/// name with an import path must include the package, as in `path/logx.Infof`.
+fn is_function_name(name: &str) -> bool {
+    let (path, qualified) = match name.rsplit_once('/') {
+        Some((path, qualified)) => (Some(path), qualified),
+        None => (None, name),
+    };
+    let valid_path = path.is_none_or(|path| {
+        path.split('/').all(|segment| {
+            !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | '~'))
+        })
+    });
+    let segments: Vec<&str> = qualified.split('.').collect();
+    let valid_segments = segments.iter().all(|segment| {
+        let receiver = segment.strip_prefix('(').and_then(|rest| rest.strip_suffix(')'));
+        match receiver {
+            Some(receiver) => is_identifier(receiver.strip_prefix('*').unwrap_or(receiver)),
+            None => is_identifier(segment),
+        }
+    });
+    valid_path && valid_segments && (path.is_none() || segments.len() >= 2)
+}
+
+/// Whether `specifier` could follow the colon of a property, like `F2`,
+/// `000`, or `yyyy-MM-dd`: up to 32 visible characters without braces.
+fn is_format_specifier(specifier: &str) -> bool {
+    (1..=32).contains(&specifier.chars().count())
+        && !specifier.starts_with([':', ','])
+        && specifier.chars().all(|c| c.is_ascii_graphic() && !matches!(c, '{' | '}'))
+}
+
+/// Whether `glob` matches any file name or targets a binary file extension.
+fn matches_non_text_files(glob: &str) -> bool {
+    let name = glob.rsplit(['/', '\\']).next().unwrap_or(glob);
+    if matches!(name, "*" | "**" | "*.*") {
+        return true;
+    }
+    name.rsplit_once('.')
+        .is_some_and(|(_, extension)| BINARY_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Returns the option values of the selected `profile`, warning about
+/// unknown profiles.
+fn profile_values(settings: &Value, warnings: &mut Vec<String>) -> &'static [(&'static str, bool)] {
+    let Some(profile) = settings.get(PROFILE_KEY) else {
+        return &[];
+    };
+    let found = profile
+        .as_str()
+        .and_then(|name| PROFILES.iter().find(|(known, _)| known.eq_ignore_ascii_case(name)));
+    match found {
+        Some((_, values)) => values,
+        None => {
+            let known: Vec<&str> = PROFILES.iter().map(|(name, _)| *name).collect();
+            warnings.push(format!("ignoring unknown {PROFILE_KEY} {profile}; expected one of {}", known.join(", ")));
+            &[]
+        }
+    }
+}
+
+/// Names a session profile may take: each preset, then [`NO_PROFILE`].
+pub(crate) fn profile_names() -> impl Iterator<Item = &'static str> {
+    PROFILES.iter().map(|(name, _)| *name).chain([NO_PROFILE])
+}
+
+/// The values selecting `profile` gives every option a preset controls: the
+/// preset's own, or the built-in default for options it leaves alone, so
+/// switching from another profile undoes that one. `None` when `profile` is
+/// not one of [`profile_names`].
+pub(crate) fn profile_overrides(profile: &str) -> Option<Vec<(&'static str, Value)>> {
+    let preset: &[(&str, bool)] = if profile.eq_ignore_ascii_case(NO_PROFILE) {
+        &[]
+    } else {
+        PROFILES.iter().find(|(name, _)| name.eq_ignore_ascii_case(profile))?.1
+    };
+    let mut keys: Vec<&'static str> =
+        PROFILES.iter().flat_map(|(_, values)| values.iter().map(|(key, _)| *key)).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    let values = keys
+        .into_iter()
+        .filter_map(|key| {
+            let value = match preset.iter().find(|(listed, _)| *listed == key) {
+                Some((_, value)) => Value::Bool(*value),
+                None => FORWARDED_OPTIONS.iter().find(|option| option.key == key)?.kind.default_value()?,
+            };
+            Some((key, value))
+        })
+        .collect();
+    Some(values)
+}
+
+/// Shapes `settings` into the option set mtlog-lsp expects, filling in
+/// defaults and replacing invalid values with a warning. The source of each
+/// option is recorded in `sources`.
+fn shape(settings: &Value, sources: &mut BTreeMap<String, Source>, warnings: &mut Vec<String>) -> Value {
+    let mut options = Map::new();
+    let profile = profile_values(settings, warnings);
+
+    for option in FORWARDED_OPTIONS {
+        let given = settings.get(option.key).or_else(|| {
+            OPTION_ALIASES
+                .iter()
+                .find(|(_, key)| *key == option.key)
+                .and_then(|(alias, _)| settings.get(*alias))
+        });
+        // A profile's value stands in for the built-in default.
+        let (fallback, fallback_source) = match profile.iter().find(|(key, _)| *key == option.key) {
+            Some((_, value)) => (Some(Value::Bool(*value)), Source::Profile),
+            None => (option.kind.default_value(), Source::Default),
+        };
+        let (value, source) = match given {
+            None => (fallback, fallback_source),
+            Some(value) => match option.kind.validate(value) {
+                Ok(value) => {
+                    warnings.extend(option.kind.advisories(option.key, &value));
+                    (Some(value), Source::Settings)
+                }
+                Err(expected) => {
+                    warnings.push(format!(
+                        "ignoring invalid {} {}: {}; using {}",
+                        option.key,
+                        value,
+                        expected,
+                        fallback.as_ref().map_or("the server default".to_string(), Value::to_string)
+                    ));
+                    (fallback, fallback_source)
+                }
+            },
+        };
+        // Options without a default are left for mtlog-lsp to fill in.
+        let Some(value) = value else {
+            continue;
+        };
+        options.insert(option.key.to_string(), value);
+        sources.insert(option.key.to_string(), source);
+    }
+
+    Value::Object(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{self, CandidateSource, DiscoveryInput};
+    use zed_extension_api::settings::CommandSettings;
+
+    fn with_settings(settings: Value) -> LspSettings {
+        LspSettings {
+            settings: Some(settings),
+            ..Default::default()
+        }
+    }
+
+    /// Settings nested under the legacy "mtlog" key shape the same as unwrapped ones.
+    #[test]
+    fn test_legacy_wrapper_matches_unwrapped() {
+        let settings = json!({
+            "suppressedCodes": ["MTLOG001"],
+            "strictMode": true
+        });
+
+        let unwrapped = initialization_options(&with_settings(settings.clone()));
+        let wrapped = initialization_options(&with_settings(json!({ "mtlog": settings })));
+
+        assert_eq!(wrapped.options, unwrapped.options);
+        assert!(unwrapped.warnings.is_empty());
+        assert_eq!(wrapped.warnings.len(), 1);
+        assert!(wrapped.warnings[0].contains("deprecated"));
+    }
+
+    /// The wrapper is also unwrapped when it appears in initialization_options.
+    #[test]
+    fn test_legacy_wrapper_in_initialization_options() {
+        let lsp_settings = LspSettings {
+            initialization_options: Some(json!({ "mtlog": { "disableAll": true } })),
+            ..Default::default()
+        };
+
+        let shaped = initialization_options(&lsp_settings);
+
+        assert_eq!(shaped.options, json!({ "disableAll": true }));
+        assert_eq!(shaped.warnings.len(), 1);
+    }
+
+    /// An "mtlog" key alongside other keys is left alone rather than guessed at.
+    #[test]
+    fn test_mtlog_key_with_siblings_is_not_unwrapped() {
+        let shaped = initialization_options(&with_settings(json!({
+            "mtlog": { "strictMode": true },
+            "disableAll": true
+        })));
+
+        assert_eq!(shaped.options["disableAll"], json!(true));
+        assert_eq!(shaped.options["strictMode"], json!(false));
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// The failed-launch threshold defaults when missing or invalid.
+    #[test]
+    fn test_max_failed_launches() {
+        assert_eq!(max_failed_launches(&LspSettings::default()), launch::DEFAULT_MAX_FAILED_LAUNCHES);
+        assert_eq!(max_failed_launches(&with_settings(json!({ "maxFailedLaunches": 2 }))), 2);
+        assert_eq!(
+            max_failed_launches(&with_settings(json!({ "maxFailedLaunches": 0 }))),
+            launch::DEFAULT_MAX_FAILED_LAUNCHES
+        );
+        assert_eq!(
+            max_failed_launches(&with_settings(json!({ "maxFailedLaunches": "3" }))),
+            launch::DEFAULT_MAX_FAILED_LAUNCHES
+        );
+    }
+
+    #[test]
+    fn test_max_servers() {
+        assert_eq!(max_servers(&LspSettings::default()), None);
+        assert_eq!(max_servers(&with_settings(json!({ "maxServers": 4 }))), Some(4));
+        assert_eq!(max_servers(&with_settings(json!({ "maxServers": 0 }))), None);
+        assert_eq!(max_servers(&with_settings(json!({ "maxServers": -1 }))), None);
+    }
+
+    #[test]
+    fn test_first_run_report() {
+        assert!(!first_run_report(&LspSettings::default()));
+        assert!(first_run_report(&with_settings(json!({ "firstRunReport": true }))));
+        assert!(!first_run_report(&with_settings(json!({ "firstRunReport": "yes" }))));
+    }
+
+    #[test]
+    fn test_redact_paths() {
+        assert!(!redact_paths(&LspSettings::default()));
+        assert!(redact_paths(&with_settings(json!({ "redactPaths": true }))));
+        assert!(!redact_paths(&with_settings(json!({ "redactPaths": 1 }))));
+    }
+
+    /// Extra bin dirs keep their order and drop invalid entries.
+    #[test]
+    fn test_extra_bin_dirs() {
+        assert!(extra_bin_dirs(&LspSettings::default()).is_empty());
+        assert_eq!(
+            extra_bin_dirs(&with_settings(json!({ "extraBinDirs": ["tools/bin", "", 3, " /opt/bin "] }))),
+            vec!["tools/bin", "/opt/bin"]
+        );
+        assert!(extra_bin_dirs(&with_settings(json!({ "extraBinDirs": "tools/bin" }))).is_empty());
+    }
+
+    /// `install.gobin` is read from the nested object or the flat dotted key.
+    #[test]
+    fn test_install_gobin() {
+        assert_eq!(install_gobin(&LspSettings::default()), None);
+        assert_eq!(
+            install_gobin(&with_settings(json!({ "install": { "gobin": "system" } }))),
+            Some("system")
+        );
+        assert_eq!(install_gobin(&with_settings(json!({ "install.gobin": "/opt/bin" }))), Some("/opt/bin"));
+        assert_eq!(install_gobin(&with_settings(json!({ "install": { "gobin": 1 } }))), None);
+    }
+
+    fn with_binary_path(path: &str) -> LspSettings {
+        LspSettings {
+            binary: Some(CommandSettings {
+                path: Some(path.to_string()),
+                arguments: None,
+                env: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// A blank `binary.path` is unset, with a warning; others are trimmed.
+    #[test]
+    fn test_binary_path() {
+        let mut warnings = Vec::new();
+        assert_eq!(binary_path(&LspSettings::default(), &mut warnings), None);
+        assert_eq!(binary_path(&with_binary_path(" /opt/mtlog-lsp\n"), &mut warnings), Some("/opt/mtlog-lsp"));
+        assert!(warnings.is_empty());
+
+        for blank in ["", "   ", "\t"] {
+            let mut warnings = Vec::new();
+            assert_eq!(binary_path(&with_binary_path(blank), &mut warnings), None);
+            assert_eq!(warnings.len(), 1, "{blank:?}");
+        }
+    }
+
+    /// Only the settings that steer discovery change the resolution hash.
+    #[test]
+    fn test_resolution_hash() {
+        let base = resolution_hash(&with_binary_path("/opt/mtlog-lsp"));
+
+        assert_eq!(resolution_hash(&with_binary_path(" /opt/mtlog-lsp ")), base);
+        assert_ne!(resolution_hash(&with_binary_path("/usr/local/bin/mtlog-lsp")), base);
+        assert_ne!(resolution_hash(&LspSettings::default()), base);
+
+        let mut with_dirs = with_binary_path("/opt/mtlog-lsp");
+        with_dirs.settings = Some(json!({ "extraBinDirs": ["tools/bin"] }));
+        assert_ne!(resolution_hash(&with_dirs), base);
+
+        let mut with_analyzer_setting = with_binary_path("/opt/mtlog-lsp");
+        with_analyzer_setting.settings = Some(json!({ "strictMode": true }));
+        assert_eq!(resolution_hash(&with_analyzer_setting), base);
+    }
+
+    /// Worktrees pinning different releases never share a cached binary.
+    #[test]
+    fn test_resolution_hash_lsp_version() {
+        let pinned = |version: &str| resolution_hash(&with_settings(json!({ "lspVersion": version })));
+
+        assert_ne!(pinned("0.9.0"), pinned("0.10.0"));
+        assert_eq!(pinned("0.10.0"), pinned("v0.10.0"));
+        assert_eq!(pinned("latest"), resolution_hash(&LspSettings::default()));
+    }
+
+    /// Discovery goes on past a blank `binary.path` to the other locations.
+    #[test]
+    fn test_blank_binary_path_falls_back() {
+        let lsp_settings = with_binary_path("  ");
+        let input = DiscoveryInput {
+            explicit_path: binary_path(&lsp_settings, &mut Vec::new()),
+            installed_paths: vec!["/ext/work/go-bin/mtlog-lsp".to_string()],
+            path_lookup: Some("/usr/bin/mtlog-lsp".to_string()),
+            ..Default::default()
+        };
+
+        let candidates = discovery::candidates(&input);
+
+        assert_eq!(candidates[0].source, CandidateSource::Path);
+        assert!(candidates.iter().all(|candidate| !candidate.path.trim().is_empty()));
+    }
+
+    /// `binary.pathStrict` is off unless set to true.
+    #[test]
+    fn test_binary_path_strict() {
+        assert!(!binary_path_strict(&LspSettings::default()));
+        assert!(binary_path_strict(&with_settings(json!({ "binary": { "pathStrict": true } }))));
+        assert!(binary_path_strict(&with_settings(json!({ "binary.pathStrict": true }))));
+        assert!(!binary_path_strict(&with_settings(json!({ "binary": { "pathStrict": "yes" } }))));
+    }
+
+    /// Shaping an empty settings object yields every option at its default.
+    #[test]
+    fn test_shape_defaults() {
+        let shaped = initialization_options(&LspSettings::default());
+
+        assert_eq!(
+            shaped.options,
+            json!({
+                "suppressedCodes": [],
+                "severityOverrides": {},
+                "disableAll": false,
+                "commonKeys": [],
+                "strictMode": false,
+                "ignoreDynamicTemplates": false,
+                "flagEmptyTemplates": true,
+                "minConsideredLevel": "Verbose",
+                "checkDynamicLevels": false,
+                "additionalFileGlobs": [],
+                "excludeGlobs": [],
+                "ignoreDirs": [],
+                "skipVendor": true,
+                "skipGenerated": false,
+                "notifyOnError": false,
+                "analyzeChangedOnly": false,
+                "changedBaseRef": "HEAD",
+                "enableMetrics": false,
+                "allowPositionalTemplates": false,
+                "templateDelimiters": { "open": "{", "close": "}" },
+                "maxPlaceholders": 0,
+                "maxDestructureDepth": 5,
+                "requireUnitsOnNumerics": false,
+                "checkErrorWrapping": true,
+                "stringConstants": { "threshold": 3, "scope": "file" },
+                "namingExceptions": [],
+                "checkDuplicateProperties": true,
+                "allowDuplicateProperties": [],
+                "allowedFormatSpecifiers": [],
+                "propertyNamingConvention": "any",
+                "contextEnrichers": [],
+                "loggerWrappers": [],
+                "enableScopedSuppression": false,
+                "scopedSuppressionAnnotation": "//mtlog:relax",
+                "ignoreDirectives": [],
+                "crossFileAnalysis": true,
+                "analysisTiming": "onType",
+                "stripMessagePrefix": false,
+                "collapseRepeats": false,
+                "samplingChecks": false
+            })
+        );
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// A value each kind of option accepts and forwards unchanged, and one
+    /// it rejects.
+    fn kind_samples(kind: &OptionKind) -> (Value, Value) {
+        match kind {
+            OptionKind::Bool(default) => (json!(!default), json!("on")),
+            OptionKind::StringList => (json!(["tenant_id"]), json!([1])),
+            OptionKind::StringMap => (json!({ "MTLOG002": "warning" }), json!({ "MTLOG002": 2 })),
+            OptionKind::GlobList => (json!(["gen/*.go.tpl"]), json!(["[a-z"])),
+            OptionKind::IdentifierList => (json!(["TraceId"]), json!(["{TraceId}"])),
+            OptionKind::SpecifierList => (json!(["Q"]), json!([":Q"])),
+            OptionKind::FunctionList => (json!(["logx.Infof"]), json!(["log this"])),
+            OptionKind::DirectiveList => (json!(["//nolint:mtlog"]), json!(["//nolint:mtlog", "//nolint:mtlog"])),
+            OptionKind::Text(_) => (json!("//custom"), json!("  ")),
+            OptionKind::Label { .. } => (json!("obs-lint"), json!("two\nlines")),
+            OptionKind::Count(default) => (json!(default + 7), json!(-1)),
+            OptionKind::Positive(default) => (json!(default + 1), json!(0)),
+            OptionKind::Millis { min } => (json!(min), json!(min - 1)),
+            OptionKind::StringConstants => (json!({ "threshold": 5, "scope": "package" }), json!({ "threshold": 1 })),
+            OptionKind::QuietHours => (json!({ "start": "22:00", "end": "07:00" }), json!({ "start": "22:00" })),
+            OptionKind::TemplateDelimiters => {
+                (json!({ "open": "<", "close": ">" }), json!({ "open": "<", "close": "<" }))
+            }
+            OptionKind::ContextEnrichers => (
+                json!([{ "function": "logging.WithRequest", "argIndices": [1] }]),
+                json!([{ "function": "logging.WithRequest" }]),
+            ),
+            OptionKind::Choice { values, default } => {
+                let other = values.iter().find(|value| *value != default).expect("a second choice");
+                (json!(other), json!("no-such-choice"))
+            }
+        }
+    }
+
+    /// Every forwarded option defaults to its kind's default, forwards a valid
+    /// value from settings, and replaces a rejected one by the default with a
+    /// warning.
+    #[test]
+    fn test_forwarded_options_by_kind() {
+        let defaults = initialization_options(&LspSettings::default());
+
+        for option in FORWARDED_OPTIONS {
+            let key = option.key;
+            let default = option.kind.default_value();
+            let (valid, rejected) = kind_samples(&option.kind);
+
+            assert_eq!(defaults.options.get(key), default.as_ref(), "{key}");
+            if default.is_some() {
+                assert_eq!(defaults.sources[key], Source::Default, "{key}");
+            }
+
+            let shaped = initialization_options(&with_settings(json!({ key: valid })));
+            assert_eq!(shaped.options[key], valid, "{key}");
+            assert_eq!(shaped.sources[key], Source::Settings, "{key}");
+            assert!(shaped.warnings.is_empty(), "{key}: {:?}", shaped.warnings);
+
+            let shaped = initialization_options(&with_settings(json!({ key: rejected })));
+            assert_eq!(shaped.options.get(key), default.as_ref(), "{key}");
+            assert_eq!(shaped.warnings.len(), 1, "{key}: {:?}", shaped.warnings);
+        }
+    }
+
+    /// A valid minimum level is forwarded in its canonical spelling.
+    #[test]
+    fn test_min_considered_level_forwarded() {
+        let shaped = initialization_options(&with_settings(json!({ "minConsideredLevel": "warning" })));
+
+        assert_eq!(shaped.options["minConsideredLevel"], json!("Warning"));
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// An unknown level falls back to Verbose with a warning.
+    #[test]
+    fn test_min_considered_level_invalid() {
+        let shaped = initialization_options(&with_settings(json!({ "minConsideredLevel": "Trace" })));
+
+        assert_eq!(shaped.options["minConsideredLevel"], json!("Verbose"));
+        assert_eq!(shaped.warnings.len(), 1);
+        assert!(shaped.warnings[0].contains("minConsideredLevel"));
+    }
+
+    /// The changed-files options are forwarded together.
+    #[test]
+    fn test_analyze_changed_only_forwarded() {
+        let shaped = initialization_options(&with_settings(json!({
+            "analyzeChangedOnly": true,
+            "changedBaseRef": " origin/main "
+        })));
+
+        assert_eq!(shaped.options["analyzeChangedOnly"], json!(true));
+        assert_eq!(shaped.options["changedBaseRef"], json!("origin/main"));
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// An empty base ref falls back to HEAD.
+    #[test]
+    fn test_changed_base_ref_invalid() {
+        let shaped = initialization_options(&with_settings(json!({ "changedBaseRef": "" })));
+
+        assert_eq!(shaped.options["changedBaseRef"], json!("HEAD"));
+        assert_eq!(shaped.sources["changedBaseRef"], Source::Default);
+        assert_eq!(shaped.warnings.len(), 1);
+    }
+
+    /// Function-level suppression is off by default, with the standard annotation.
+    #[test]
+    fn test_scoped_suppression_forwarded() {
+        let default = initialization_options(&LspSettings::default());
+        assert_eq!(default.options["enableScopedSuppression"], json!(false));
+        assert_eq!(default.options["scopedSuppressionAnnotation"], json!("//mtlog:relax"));
+
+        let shaped = initialization_options(&with_settings(json!({
+            "enableScopedSuppression": true,
+            "scopedSuppressionAnnotation": " //lint:quiet "
+        })));
+
+        assert_eq!(shaped.options["enableScopedSuppression"], json!(true));
+        assert_eq!(shaped.options["scopedSuppressionAnnotation"], json!("//lint:quiet"));
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// A blank annotation falls back to the standard one.
+    #[test]
+    fn test_scoped_suppression_annotation_invalid() {
+        for annotation in [json!("  "), json!(true)] {
+            let shaped = initialization_options(&with_settings(json!({ "scopedSuppressionAnnotation": annotation })));
+
+            assert_eq!(shaped.options["scopedSuppressionAnnotation"], json!("//mtlog:relax"), "{annotation}");
+            assert_eq!(shaped.warnings.len(), 1, "{annotation}");
+        }
+    }
+
+    #[test]
+    fn test_ignore_directives() {
+        let default = initialization_options(&LspSettings::default());
+        assert_eq!(default.options["ignoreDirectives"], json!([]));
+        assert_eq!(default.sources["ignoreDirectives"], Source::Default);
+
+        let shaped = initialization_options(&with_settings(json!({
+            "ignoreDirectives": [" //nolint:mtlog", "//mtlog:disable "]
+        })));
+        assert_eq!(shaped.options["ignoreDirectives"], json!(["//nolint:mtlog", "//mtlog:disable"]));
+        assert_eq!(shaped.sources["ignoreDirectives"], Source::Settings);
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// Empty, multi-line, and repeated directives reject the whole list.
+    #[test]
+    fn test_ignore_directives_invalid() {
+        for directives in [
+            json!(["//nolint:mtlog", ""]),
+            json!(["//mtlog:disable\n//x"]),
+            json!(["//nolint:mtlog", " //nolint:mtlog"]),
+            json!([7]),
+            json!("//nolint:mtlog"),
+        ] {
+            let shaped = initialization_options(&with_settings(json!({ "ignoreDirectives": directives })));
+
+            assert_eq!(shaped.options["ignoreDirectives"], json!([]), "{directives}");
+            assert_eq!(shaped.warnings.len(), 1, "{directives}");
+        }
+        let repeated = initialization_options(&with_settings(json!({ "ignoreDirectives": ["//a", "//a"] })));
+        assert!(repeated.warnings[0].contains("\"//a\" is listed more than once"), "{:?}", repeated.warnings);
+    }
+
+    /// Positional templates are flagged unless explicitly allowed.
+    #[test]
+    fn test_allow_positional_templates() {
+        let default = initialization_options(&LspSettings::default());
+        assert_eq!(default.options["allowPositionalTemplates"], json!(false));
+
+        let shaped = initialization_options(&with_settings(json!({ "allowPositionalTemplates": true })));
+        assert_eq!(shaped.options["allowPositionalTemplates"], json!(true));
+        assert_eq!(shaped.sources["allowPositionalTemplates"], Source::Settings);
+
+        let invalid = initialization_options(&with_settings(json!({ "allowPositionalTemplates": "yes" })));
+        assert_eq!(invalid.options["allowPositionalTemplates"], json!(false));
+        assert_eq!(invalid.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_template_delimiters_forwarded() {
+        let shaped = initialization_options(&with_settings(json!({ "templateDelimiters": { "open": "<", "close": ">" } })));
+
+        assert_eq!(shaped.options["templateDelimiters"], json!({ "open": "<", "close": ">" }));
+        assert_eq!(shaped.sources["templateDelimiters"], Source::Settings);
+        assert!(shaped.warnings.is_empty());
+
+        let unset = initialization_options(&with_settings(json!({})));
+        assert_eq!(unset.options["templateDelimiters"], json!({ "open": "{", "close": "}" }));
+        assert_eq!(unset.sources["templateDelimiters"], Source::Default);
+    }
+
+    /// Delimiters must be two different single symbols; anything else keeps `{` and `}`.
+    #[test]
+    fn test_template_delimiters_invalid() {
+        for given in [
+            json!({ "open": "<", "close": "<" }),
+            json!({ "open": "<<", "close": ">>" }),
+            json!({ "open": "", "close": ">" }),
+            json!({ "open": "a", "close": "b" }),
+            json!({ "open": " ", "close": ">" }),
+            json!({ "open": "<" }),
+            json!({ "open": "<", "close": ">", "escape": "\\" }),
+            json!("<>"),
+        ] {
+            let shaped = initialization_options(&with_settings(json!({ "templateDelimiters": given })));
+
+            assert_eq!(shaped.options["templateDelimiters"], json!({ "open": "{", "close": "}" }), "{given}");
+            assert_eq!(shaped.warnings.len(), 1, "{given}");
+        }
+    }
+
+    /// Both scopes are accepted, and missing fields take their defaults.
+    #[test]
+    fn test_string_constants_forwarded() {
+        for (given, expected) in [
+            (json!({ "threshold": 5, "scope": "package" }), json!({ "threshold": 5, "scope": "package" })),
+            (json!({ "threshold": 2, "scope": "File" }), json!({ "threshold": 2, "scope": "file" })),
+            (json!({ "scope": "package" }), json!({ "threshold": 3, "scope": "package" })),
+            (json!({}), json!({ "threshold": 3, "scope": "file" })),
+        ] {
+            let shaped = initialization_options(&with_settings(json!({ "stringConstants": given })));
+
+            assert_eq!(shaped.options["stringConstants"], expected, "{given}");
+            assert_eq!(shaped.sources["stringConstants"], Source::Settings, "{given}");
+            assert!(shaped.warnings.is_empty(), "{given}");
+        }
+    }
+
+    /// Thresholds below 2, unknown scopes, and unknown keys fall back to the default.
+    #[test]
+    fn test_string_constants_invalid() {
+        for given in [
+            json!({ "threshold": 1 }),
+            json!({ "threshold": 0, "scope": "file" }),
+            json!({ "threshold": 2.5 }),
+            json!({ "threshold": -3 }),
+            json!({ "scope": "module" }),
+            json!({ "threshold": 5, "scoep": "package" }),
+            json!(5),
+        ] {
+            let shaped = initialization_options(&with_settings(json!({ "stringConstants": given })));
+
+            assert_eq!(shaped.options["stringConstants"], json!({ "threshold": 3, "scope": "file" }), "{given}");
+            assert_eq!(shaped.warnings.len(), 1, "{given}");
+        }
+    }
+
+    /// Configured options newer than the server are reported; defaults and
+    /// forwarded-only options are not.
+    #[test]
+    fn test_version_gate_warnings() {
+        let gates = [("strictMode", "0.11.0"), ("stringConstants", "0.11.0")];
+        let old = VersionProbe::Version("0.10.0".to_string());
+        let configured = initialization_options(&with_settings(json!({
+            "strictMode": true,
+            "stringConstants": { "threshold": 5, "scope": "package" }
+        })));
+        let defaults = initialization_options(&LspSettings::default());
+
+        let warnings = version_gate_warnings(&configured, &old, &gates);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("strictMode requires mtlog-lsp 0.11.0"));
+        assert!(version_gate_warnings(&defaults, &old, &gates).is_empty());
+        assert!(version_gate_warnings(&configured, &VersionProbe::Version("0.11.0".to_string()), &gates).is_empty());
+    }
+
+    /// None of the version-gated options is read by mtlog-lsp yet, so setting
+    /// them all warns about none.
+    #[test]
+    fn test_forwarded_only_options_are_not_gated() {
+        let configured = initialization_options(&with_settings(json!({
+            "stringConstants": { "threshold": 5, "scope": "package" },
+            "contextEnrichers": [{ "function": "logging.WithRequest", "argIndices": [1] }],
+            "analysisTimeoutMs": 2_000,
+            "samplingChecks": true
+        })));
+
+        assert!(MINIMUM_SERVER_VERSIONS.iter().all(|(key, _)| is_forwarded_only(key)));
+        assert!(unsupported_option_warnings(&configured, &VersionProbe::Version("0.10.0".to_string())).is_empty());
+        assert!(!is_forwarded_only("suppressedCodes"));
+    }
+
+    /// Naming exceptions are forwarded exactly as given, case included.
+    #[test]
+    fn test_naming_exceptions_forwarded() {
+        let shaped = initialization_options(&with_settings(json!({ "namingExceptions": ["id", "ip", "url", "IPv4"] })));
+
+        assert_eq!(shaped.options["namingExceptions"], json!(["id", "ip", "url", "IPv4"]));
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// Entries that are empty, braced, or not identifiers reject the list.
+    #[test]
+    fn test_naming_exceptions_invalid() {
+        for names in [json!([""]), json!(["{id}"]), json!(["user id"]), json!(["9lives"]), json!([1]), json!("id")] {
+            let shaped = initialization_options(&with_settings(json!({ "namingExceptions": names })));
+
+            assert_eq!(shaped.options["namingExceptions"], json!([]), "{names}");
+            assert_eq!(shaped.warnings.len(), 1, "{names}");
+        }
+    }
+
+    /// Custom format specifiers are forwarded; malformed ones reject the list.
+    #[test]
+    fn test_allowed_format_specifiers() {
+        let shaped = initialization_options(&with_settings(json!({
+            "allowedFormatSpecifiers": ["Q", "yyyy-QQ", "000.0"]
+        })));
+        assert_eq!(shaped.options["allowedFormatSpecifiers"], json!(["Q", "yyyy-QQ", "000.0"]));
+        assert!(shaped.warnings.is_empty());
+
+        let too_long = "Q".repeat(33);
+        for specifiers in [json!([""]), json!(["{Q}"]), json!(["two words"]), json!([":Q"]), json!([too_long]), json!("Q")] {
+            let invalid = initialization_options(&with_settings(json!({ "allowedFormatSpecifiers": specifiers })));
+            assert_eq!(invalid.options["allowedFormatSpecifiers"], json!([]), "{specifiers}");
+            assert_eq!(invalid.warnings.len(), 1, "{specifiers}");
+        }
+
+        let colon = initialization_options(&with_settings(json!({ "allowedFormatSpecifiers": [":Q"] })));
+        assert!(colon.warnings[0].contains("write Q without the leading ':'"));
+    }
+
+    /// Properties allowed to repeat are forwarded, and must be identifiers.
+    #[test]
+    fn test_allow_duplicate_properties() {
+        let shaped = initialization_options(&with_settings(json!({ "allowDuplicateProperties": ["TraceId"] })));
+        assert_eq!(shaped.options["allowDuplicateProperties"], json!(["TraceId"]));
+        assert!(shaped.warnings.is_empty());
+
+        for names in [json!(["Trace*"]), json!(["{TraceId}"]), json!([""])] {
+            let invalid = initialization_options(&with_settings(json!({ "allowDuplicateProperties": names })));
+            assert_eq!(invalid.options["allowDuplicateProperties"], json!([]), "{names}");
+            assert_eq!(invalid.warnings.len(), 1, "{names}");
+        }
+    }
+
+    /// The naming convention is forwarded in lowercase and defaults to any.
+    #[test]
+    fn test_property_naming_convention() {
+        let default = initialization_options(&LspSettings::default());
+        assert_eq!(default.options["propertyNamingConvention"], json!("any"));
+
+        let shaped = initialization_options(&with_settings(json!({ "propertyNamingConvention": "Pascal" })));
+        assert_eq!(shaped.options["propertyNamingConvention"], json!("pascal"));
+        assert!(shaped.warnings.is_empty());
+
+        let invalid = initialization_options(&with_settings(json!({ "propertyNamingConvention": "kebab" })));
+        assert_eq!(invalid.options["propertyNamingConvention"], json!("any"));
+        assert_eq!(invalid.warnings.len(), 1);
+    }
+
+    /// Well-formed enricher entries are forwarded unchanged.
+    #[test]
+    fn test_context_enrichers_forwarded() {
+        let enrichers = json!([
+            { "function": "example.com/app/logging.WithRequest", "argIndices": [1] },
+            { "function": "example.com/app/logging.(*Scope).With", "argIndices": [0, 1] }
+        ]);
+
+        let shaped = initialization_options(&with_settings(json!({ "contextEnrichers": enrichers })));
+
+        assert_eq!(shaped.options["contextEnrichers"], enrichers);
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// Wrapper names are forwarded unchanged in any of the accepted forms.
+    #[test]
+    fn test_logger_wrappers_forwarded() {
+        let wrappers = json!([
+            "Infof",
+            "logx.Infof",
+            "logx.Logger.Warn",
+            "example.com/app/logx.Errorf",
+            "github.com/acme/go-kit/log.(*Logger).Debug"
+        ]);
+
+        let shaped = initialization_options(&with_settings(json!({ "loggerWrappers": wrappers })));
+
+        assert_eq!(shaped.options["loggerWrappers"], wrappers);
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// Anything that is not a plausible function name rejects the list.
+    #[test]
+    fn test_logger_wrappers_invalid() {
+        for wrappers in [
+            json!(["logx.Infof", ""]),
+            json!(["logx.Info f"]),
+            json!(["logx."]),
+            json!(["example.com/app/logx"]),
+            json!(["example.com//logx.Infof"]),
+            json!(["logx.Infof(string, ...any)"]),
+            json!(["1logx.Infof"]),
+            json!([7]),
+            json!("logx.Infof"),
+        ] {
+            let shaped = initialization_options(&with_settings(json!({ "loggerWrappers": wrappers })));
+
+            assert_eq!(shaped.options["loggerWrappers"], json!([]), "{wrappers}");
+            assert_eq!(shaped.warnings.len(), 1, "{wrappers}");
+        }
+    }
+
+    /// Entries lacking argument positions reject the list.
+    #[test]
+    fn test_context_enrichers_missing_indices() {
+        for entry in [
+            json!({ "function": "logging.WithRequest" }),
+            json!({ "function": "logging.WithRequest", "argIndices": [] }),
+        ] {
+            let shaped = initialization_options(&with_settings(json!({ "contextEnrichers": [entry] })));
+
+            assert_eq!(shaped.options["contextEnrichers"], json!([]), "{entry}");
+            assert_eq!(shaped.warnings.len(), 1, "{entry}");
+            assert!(shaped.warnings[0].contains("entry 0: expected argIndices"), "{entry}");
+        }
+    }
+
+    /// Entries with fields of the wrong type reject the list.
+    #[test]
+    fn test_context_enrichers_wrong_types() {
+        for enrichers in [
+            json!([{ "function": 7, "argIndices": [1] }]),
+            json!([{ "function": "WithRequest", "argIndices": [1] }]),
+            json!([{ "function": "logging.WithRequest", "argIndices": ["1"] }]),
+            json!([{ "function": "logging.WithRequest", "argIndices": [-1] }]),
+            json!([{ "function": "logging.WithRequest", "argIndices": 1 }]),
+            json!([{ "function": "logging.WithRequest", "argIndices": [1], "kind": "with" }]),
+            json!(["logging.WithRequest"]),
+            json!({ "function": "logging.WithRequest", "argIndices": [1] }),
+        ] {
+            let shaped = initialization_options(&with_settings(json!({ "contextEnrichers": enrichers })));
+
+            assert_eq!(shaped.options["contextEnrichers"], json!([]), "{enrichers}");
+            assert_eq!(shaped.warnings.len(), 1, "{enrichers}");
+        }
+    }
+
+
+
+
+
+
+    /// Cross-file analysis is on by default and accepts its snake_case spelling.
+    #[test]
+    fn test_cross_file_analysis() {
+        let default = initialization_options(&LspSettings::default());
+        assert_eq!(default.options["crossFileAnalysis"], json!(true));
+
+        let off = initialization_options(&with_settings(json!({ "crossFileAnalysis": false })));
+        assert_eq!(off.options["crossFileAnalysis"], json!(false));
+        assert_eq!(off.sources["crossFileAnalysis"], Source::Settings);
+
+        let alias = initialization_options(&with_settings(json!({ "cross_file_analysis": false })));
+        assert_eq!(alias.options["crossFileAnalysis"], json!(false));
+        assert!(alias.options.get("cross_file_analysis").is_none());
+        assert!(alias.warnings.is_empty());
+
+        let both = initialization_options(&with_settings(json!({
+            "crossFileAnalysis": true,
+            "cross_file_analysis": false
+        })));
+        assert_eq!(both.options["crossFileAnalysis"], json!(true));
+
+        let invalid = initialization_options(&with_settings(json!({ "cross_file_analysis": "off" })));
+        assert_eq!(invalid.options["crossFileAnalysis"], json!(true));
+        assert_eq!(invalid.warnings.len(), 1);
+    }
+
+    /// The analysis timeout is only forwarded when set to at least 100ms.
+    #[test]
+    fn test_analysis_timeout() {
+        assert!(initialization_options(&LspSettings::default()).options.get("analysisTimeoutMs").is_none());
+
+        for millis in [100, 5_000] {
+            let shaped = initialization_options(&with_settings(json!({ "analysisTimeoutMs": millis })));
+            assert_eq!(shaped.options["analysisTimeoutMs"], json!(millis));
+            assert!(shaped.warnings.is_empty());
+        }
+
+        for millis in [json!(99), json!(0), json!(-500), json!(1.5), json!("5000")] {
+            let shaped = initialization_options(&with_settings(json!({ "analysisTimeoutMs": millis })));
+            assert!(shaped.options.get("analysisTimeoutMs").is_none(), "{millis}");
+            assert_eq!(shaped.warnings.len(), 1, "{millis}");
+        }
+    }
+
+
+
+
+    /// Quiet hours are forwarded normalized, and left out when unset or invalid.
+    #[test]
+    fn test_quiet_hours() {
+        assert!(initialization_options(&LspSettings::default()).options.get("quietHours").is_none());
+
+        let shaped = initialization_options(&with_settings(json!({ "quietHours": { "start": "22:00", "end": "7:00" } })));
+        assert_eq!(shaped.options["quietHours"], json!({ "start": "22:00", "end": "07:00" }));
+        assert_eq!(shaped.sources["quietHours"], Source::Settings);
+
+        let invalid = initialization_options(&with_settings(json!({ "quietHours": { "start": "22:00" } })));
+        assert!(invalid.options.get("quietHours").is_none());
+        assert_eq!(invalid.warnings.len(), 1);
+    }
+
+    /// analysisTiming defaults to onType and is forwarded in canonical case.
+    #[test]
+    fn test_analysis_timing() {
+        assert_eq!(initialization_options(&LspSettings::default()).options["analysisTiming"], json!("onType"));
+
+        let shaped = initialization_options(&with_settings(json!({ "analysisTiming": "MANUAL" })));
+        assert_eq!(shaped.options["analysisTiming"], json!("manual"));
+        assert_eq!(shaped.sources["analysisTiming"], Source::Settings);
+
+        let invalid = initialization_options(&with_settings(json!({ "analysisTiming": "onIdle" })));
+        assert_eq!(invalid.options["analysisTiming"], json!("onType"));
+        assert_eq!(invalid.warnings.len(), 1);
+    }
+
+
+
+    /// The strict profile turns on strict mode and sampling checks, but
+    /// explicit settings still win over it.
+    #[test]
+    fn test_strict_profile() {
+        let strict = initialization_options(&with_settings(json!({ "profile": "strict" })));
+        assert_eq!(strict.options["strictMode"], json!(true));
+        assert_eq!(strict.options["samplingChecks"], json!(true));
+        assert_eq!(strict.sources["samplingChecks"], Source::Profile);
+        assert_eq!(strict.options["disableAll"], json!(false));
+        assert!(strict.options.get("profile").is_none());
+        assert!(strict.warnings.is_empty());
+
+        let overridden = initialization_options(&with_settings(json!({ "profile": "strict", "samplingChecks": false })));
+        assert_eq!(overridden.options["samplingChecks"], json!(false));
+        assert_eq!(overridden.sources["samplingChecks"], Source::Settings);
+
+        let invalid = initialization_options(&with_settings(json!({ "profile": "strict", "samplingChecks": "on" })));
+        assert_eq!(invalid.options["samplingChecks"], json!(true));
+        assert_eq!(invalid.sources["samplingChecks"], Source::Profile);
+        assert!(invalid.warnings[0].contains("using true"));
+    }
+
+    /// An unknown profile is ignored with a warning.
+    #[test]
+    fn test_unknown_profile() {
+        let shaped = initialization_options(&with_settings(json!({ "profile": "paranoid" })));
+
+        assert_eq!(shaped.options["samplingChecks"], json!(false));
+        assert_eq!(shaped.warnings.len(), 1);
+        assert!(shaped.warnings[0].contains("expected one of strict"));
+    }
+
+    /// A session profile sets every option a preset controls; "none" restores the defaults.
+    #[test]
+    fn test_profile_overrides() {
+        assert_eq!(
+            profile_overrides("Strict"),
+            Some(vec![("samplingChecks", json!(true)), ("strictMode", json!(true))])
+        );
+        assert_eq!(
+            profile_overrides("none"),
+            Some(vec![("samplingChecks", json!(false)), ("strictMode", json!(false))])
+        );
+        assert_eq!(profile_overrides("paranoid"), None);
+        assert_eq!(profile_names().collect::<Vec<_>>(), vec!["strict", "none"]);
+    }
+
+    /// A configured diagnostic source is forwarded from settings.
+    #[test]
+    fn test_diagnostic_source_forwarded() {
+        let shaped = initialization_options(&with_settings(json!({ "diagnosticSource": " obs-lint " })));
+
+        assert_eq!(shaped.options["diagnosticSource"], json!("obs-lint"));
+        assert_eq!(shaped.sources["diagnosticSource"], Source::Settings);
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// The diagnostic source is forwarded untouched from initialization_options.
+    #[test]
+    fn test_diagnostic_source_in_initialization_options() {
+        let lsp_settings = LspSettings {
+            initialization_options: Some(json!({ "diagnosticSource": "obs-lint" })),
+            ..Default::default()
+        };
+
+        let shaped = initialization_options(&lsp_settings);
+
+        assert_eq!(shaped.options, json!({ "diagnosticSource": "obs-lint" }));
+    }
+
+    /// Unset or invalid diagnostic sources are left out so the server default applies.
+    #[test]
+    fn test_diagnostic_source_invalid() {
+        assert!(initialization_options(&LspSettings::default()).options.get("diagnosticSource").is_none());
+
+        let too_long = "x".repeat(33);
+        for source in [json!(""), json!("obs\nlint"), json!(too_long), json!(7)] {
+            let shaped = initialization_options(&with_settings(json!({ "diagnosticSource": source })));
+
+            assert!(shaped.options.get("diagnosticSource").is_none(), "{source}");
+            assert!(!shaped.sources.contains_key("diagnosticSource"), "{source}");
+            assert_eq!(shaped.warnings.len(), 1, "{source}");
+            assert!(shaped.warnings[0].contains("the server default"), "{source}");
+        }
+    }
+
+    /// The file profile annotation is off unless a prefix is set.
+    #[test]
+    fn test_file_profile_annotation() {
+        assert!(initialization_options(&LspSettings::default()).options.get("fileProfileAnnotation").is_none());
+
+        let shaped = initialization_options(&with_settings(json!({ "fileProfileAnnotation": " //mtlog:profile " })));
+        assert_eq!(shaped.options["fileProfileAnnotation"], json!("//mtlog:profile"));
+        assert_eq!(shaped.sources["fileProfileAnnotation"], Source::Settings);
+        assert!(shaped.warnings.is_empty());
+
+        for prefix in [json!(""), json!("  "), json!("//mtlog:\nprofile"), json!(true)] {
+            let invalid = initialization_options(&with_settings(json!({ "fileProfileAnnotation": prefix })));
+            assert!(invalid.options.get("fileProfileAnnotation").is_none(), "{prefix}");
+            assert_eq!(invalid.warnings.len(), 1, "{prefix}");
+        }
+    }
+
+    /// Each shaped option records whether it came from settings or the defaults.
+    #[test]
+    fn test_shape_records_sources() {
+        let shaped = initialization_options(&with_settings(json!({
+            "strictMode": true,
+            "disableAll": "yes"
+        })));
+
+        assert_eq!(shaped.sources["strictMode"], Source::Settings);
+        assert_eq!(shaped.sources["disableAll"], Source::Default);
+        assert_eq!(shaped.sources["suppressedCodes"], Source::Default);
+        let with_defaults = FORWARDED_OPTIONS
+            .iter()
+            .filter(|option| option.kind.default_value().is_some())
+            .count();
+        assert_eq!(shaped.sources.len(), with_defaults);
+    }
+
+    /// A settings lookup that fails for an unknown server id still yields the defaults.
+    #[test]
+    fn test_unknown_server_id_yields_defaults() {
+        let shaped = initialization_options_for(Err("unknown language server".to_string()), "not-mtlog");
+
+        assert_eq!(shaped.options, initialization_options(&LspSettings::default()).options);
+        assert!(shaped.sources.values().all(|source| *source == Source::Default));
+        assert_eq!(shaped.warnings.len(), 1);
+        assert!(shaped.warnings[0].contains("not-mtlog"));
+    }
+
+    /// The protocol version is added in both configuration branches.
+    #[test]
+    fn test_set_protocol_version() {
+        for lsp_settings in [
+            with_settings(json!({ "strictMode": true })),
+            LspSettings {
+                initialization_options: Some(json!({ "protocolVersion": 99 })),
+                ..Default::default()
+            },
+        ] {
+            let mut effective = initialization_options(&lsp_settings);
+
+            set_protocol_version(&mut effective);
+
+            assert_eq!(effective.options["protocolVersion"], json!(PROTOCOL_VERSION));
+            assert_eq!(effective.sources["protocolVersion"], Source::Default);
+        }
+    }
+
+    /// The docs version follows the binary, and is left out when the probe found none.
+    #[test]
+    fn test_set_docs_version() {
+        let mut effective = initialization_options(&with_settings(json!({ "docsVersion": "0.9" })));
+        assert!(effective.options.get("docsVersion").is_none());
+
+        set_docs_version(&mut effective, &VersionProbe::Unrecognized);
+        assert!(effective.options.get("docsVersion").is_none());
+
+        set_docs_version(&mut effective, &VersionProbe::Version("0.11.2".to_string()));
+        assert_eq!(effective.options["docsVersion"], json!("0.11"));
+        assert_eq!(effective.sources["docsVersion"], Source::Default);
+    }
+
+    /// Non-object initialization options are ignored with a warning, and
+    /// `settings` is shaped as if they were unset.
+    #[test]
+    fn test_non_object_initialization_options() {
+        for init_options in [json!(["MTLOG001"]), json!("strict"), json!(null), json!(3)] {
+            let shaped = initialization_options(&LspSettings {
+                initialization_options: Some(init_options.clone()),
+                settings: Some(json!({ "strictMode": true })),
+                ..Default::default()
+            });
+
+            let expected = initialization_options(&with_settings(json!({ "strictMode": true })));
+            assert_eq!(shaped.options, expected.options, "{init_options}");
+            assert_eq!(shaped.sources["strictMode"], Source::Settings);
+            assert_eq!(shaped.warnings.len(), 1, "{init_options}");
+            assert!(shaped.warnings[0].starts_with("ignoring initialization_options: expected an object"));
+        }
+    }
+
+    /// Settings written as a JSON string shape like the object they hold.
+    #[test]
+    fn test_stringified_settings_parsed() {
+        let settings = json!({ "suppressedCodes": ["MTLOG004"], "strictMode": true });
+
+        let shaped = initialization_options(&with_settings(Value::String(settings.to_string())));
+
+        let expected = initialization_options(&with_settings(settings));
+        assert_eq!(shaped.options, expected.options);
+        assert_eq!(shaped.sources, expected.sources);
+        assert_eq!(
+            shaped.warnings,
+            vec!["settings is a JSON string rather than an object; using the object it contains"]
+        );
+    }
+
+    /// Stringified initialization options are parsed and forwarded as given.
+    #[test]
+    fn test_stringified_initialization_options_parsed() {
+        let shaped = initialization_options(&LspSettings {
+            initialization_options: Some(json!(r#"{ "disableAll": true }"#)),
+            ..Default::default()
+        });
+
+        assert_eq!(shaped.options, json!({ "disableAll": true }));
+        assert_eq!(shaped.sources["disableAll"], Source::InitializationOptions);
+        assert_eq!(shaped.warnings.len(), 1);
+    }
+
+    /// A string that does not hold a JSON object leaves the defaults in place.
+    #[test]
+    fn test_malformed_stringified_settings() {
+        let defaults = initialization_options(&LspSettings::default());
+        for text in [r#"{ "strictMode": true"#, "strictMode", r#"["MTLOG001"]"#, ""] {
+            let shaped = initialization_options(&with_settings(json!(text)));
+
+            assert_eq!(shaped.options, defaults.options, "{text}");
+            assert_eq!(shaped.warnings.len(), 1, "{text}");
+            assert!(shaped.warnings[0].starts_with("ignoring settings: expected an object, found a string"), "{text}");
+            assert!(shaped.warnings[0].ends_with("; using the defaults"), "{text}");
+        }
+    }
+
+    /// Forwarded initialization options are attributed to that field.
+    #[test]
+    fn test_initialization_options_sources() {
+        let lsp_settings = LspSettings {
+            initialization_options: Some(json!({ "disableAll": true })),
+            ..Default::default()
+        };
+
+        let shaped = initialization_options(&lsp_settings);
+
+        assert_eq!(shaped.sources.len(), 1);
+        assert_eq!(shaped.sources["disableAll"], Source::InitializationOptions);
+    }
+
+    /// Valid globs are forwarded unchanged.
+    #[test]
+    fn test_additional_file_globs_forwarded() {
+        let shaped = initialization_options(&with_settings(json!({
+            "additionalFileGlobs": ["**/*.go.tpl", "templates/{a,b}/*.tmpl"]
+        })));
+
+        assert_eq!(
+            shaped.options["additionalFileGlobs"],
+            json!(["**/*.go.tpl", "templates/{a,b}/*.tmpl"])
+        );
+        assert!(shaped.warnings.is_empty());
+    }
+
+    /// A malformed glob rejects the setting in favour of the default.
+    #[test]
+    fn test_additional_file_globs_invalid() {
+        for globs in [json!(["*.tpl", "[unclosed"]), json!([""]), json!("*.tpl"), json!([1])] {
+            let shaped = initialization_options(&with_settings(json!({ "additionalFileGlobs": globs })));
+
+            assert_eq!(shaped.options["additionalFileGlobs"], json!([]), "{globs}");
+            assert_eq!(shaped.warnings.len(), 1, "{globs}");
+        }
+    }
+
+    /// Globs that can match binary files are forwarded with a warning.
+    #[test]
+    fn test_additional_file_globs_non_text_warning() {
+        let shaped = initialization_options(&with_settings(json!({
+            "additionalFileGlobs": ["assets/*.PNG", "gen/**", "*.go.tpl"]
+        })));
+
+        assert_eq!(shaped.options["additionalFileGlobs"], json!(["assets/*.PNG", "gen/**", "*.go.tpl"]));
+        assert_eq!(shaped.warnings.len(), 2);
+        assert!(shaped.warnings[0].contains("assets/*.PNG"));
+        assert!(shaped.warnings[1].contains("gen/**"));
+    }
+
+    /// Exclusion globs are validated like other globs but never warned about.
+    #[test]
+    fn test_exclusions_forwarded() {
+        let shaped = initialization_options(&with_settings(json!({
+            "excludeGlobs": ["gen/**", "*.pb.go"],
+            "ignoreDirs": ["testdata"],
+            "skipVendor": false,
+            "skipGenerated": true
+        })));
+
+        assert_eq!(shaped.options["excludeGlobs"], json!(["gen/**", "*.pb.go"]));
+        assert_eq!(shaped.options["ignoreDirs"], json!(["testdata"]));
+        assert_eq!(shaped.options["skipVendor"], json!(false));
+        assert_eq!(shaped.options["skipGenerated"], json!(true));
+        assert!(shaped.warnings.is_empty(), "{:?}", shaped.warnings);
+
+        let invalid = initialization_options(&with_settings(json!({ "excludeGlobs": ["gen/{a,b"] })));
+        assert_eq!(invalid.options["excludeGlobs"], json!([]));
+        assert_eq!(invalid.warnings.len(), 1);
+    }
+
+    /// Values of the wrong type are replaced by the option's default.
+    #[test]
+    fn test_invalid_types_fall_back_to_defaults() {
+        let shaped = initialization_options(&with_settings(json!({
+            "suppressedCodes": "MTLOG001",
+            "disableAll": "yes"
+        })));
+
+        assert_eq!(shaped.options["suppressedCodes"], json!([]));
+        assert_eq!(shaped.options["disableAll"], json!(false));
+        assert_eq!(shaped.warnings.len(), 2);
+    }
+}
@@ -0,0 +1,181 @@
+//! The `suppressionsFile` setting: a project file listing diagnostic codes
+//! to add to `suppressedCodes`, for teams bringing a long list over from
+//! another linter.
+//!
+//! Codes are separated by newlines or commas, and `#` starts a comment that
+//! runs to the end of the line:
+//!
+//! ```text
+//! # carried over from the old linter
+//! MTLOG004, MTLOG007
+//! mtlog011   # lowercase is fine
+//! ```
+
+use zed_extension_api::serde_json::Value;
+
+use crate::report::RULES;
+use crate::settings::{EffectiveSettings, Source};
+
+/// Setting naming the file, relative to the worktree root.
+pub(crate) const SUPPRESSIONS_FILE_KEY: &str = "suppressionsFile";
+
+/// Option the codes are merged into.
+const SUPPRESSED_CODES_KEY: &str = "suppressedCodes";
+
+/// Codes read from a suppressions file, with warnings for entries that are
+/// not diagnostic codes.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Suppressions {
+    pub(crate) codes: Vec<&'static str>,
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Returns the file named by the setting, or `Ok(None)` when it is absent.
+pub(crate) fn path(setting: Option<&Value>) -> Result<Option<&str>, String> {
+    let Some(setting) = setting else {
+        return Ok(None);
+    };
+    match setting.as_str().map(str::trim) {
+        Some(path) if !path.is_empty() => Ok(Some(path)),
+        _ => Err("expected a non-empty path relative to the project root".to_string()),
+    }
+}
+
+/// Parses the contents of a suppressions file. Codes are matched ignoring
+/// case and listed once each, in the order they first appear.
+pub(crate) fn parse(text: &str) -> Suppressions {
+    let mut suppressions = Suppressions::default();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split_once('#').map_or(line, |(before, _)| before);
+        for entry in line.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            match RULES.iter().find(|(code, _)| code.eq_ignore_ascii_case(entry)) {
+                Some((code, _)) if !suppressions.codes.contains(code) => suppressions.codes.push(code),
+                Some(_) => {}
+                None => suppressions
+                    .warnings
+                    .push(format!("line {}: \"{entry}\" is not a diagnostic code", number + 1)),
+            }
+        }
+    }
+    suppressions
+}
+
+/// Merges the codes read from a file into `suppressedCodes`, or records a
+/// warning when the setting was invalid or the file could not be read.
+pub(crate) fn apply(loaded: Result<Option<(String, Suppressions)>, String>, effective: &mut EffectiveSettings) {
+    let (file, suppressions) = match loaded {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) => return,
+        Err(err) => {
+            effective.warnings.push(format!("ignoring {SUPPRESSIONS_FILE_KEY}: {err}"));
+            return;
+        }
+    };
+    effective.warnings.extend(
+        suppressions
+            .warnings
+            .into_iter()
+            .map(|warning| format!("{SUPPRESSIONS_FILE_KEY} {file}, {warning}")),
+    );
+    if suppressions.codes.is_empty() {
+        return;
+    }
+
+    let Some(options) = effective.options.as_object_mut() else {
+        return;
+    };
+    let codes = options
+        .entry(SUPPRESSED_CODES_KEY)
+        .or_insert_with(|| Value::Array(Vec::new()));
+    let Some(codes) = codes.as_array_mut() else {
+        effective
+            .warnings
+            .push(format!("{SUPPRESSED_CODES_KEY} is not a list, so {file} was not merged into it"));
+        return;
+    };
+    for code in suppressions.codes {
+        if !codes.iter().any(|existing| existing.as_str() == Some(code)) {
+            codes.push(Value::String(code.to_string()));
+        }
+    }
+    // Codes already set somewhere else keep that source
+    let source = effective.sources.entry(SUPPRESSED_CODES_KEY.to_string()).or_insert(Source::Settings);
+    if *source == Source::Default {
+        *source = Source::Settings;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings;
+    use zed_extension_api::{serde_json::json, settings::LspSettings};
+
+    const SAMPLE: &str = "\
+# Codes carried over from the old linter
+MTLOG004, MTLOG007
+
+mtlog011   # lowercase is fine
+MTLOG007,MTLOG012,, # repeated and empty entries are skipped
+   # indented comment
+MTLOG099, not-a-code
+";
+
+    #[test]
+    fn test_parse() {
+        let suppressions = parse(SAMPLE);
+
+        assert_eq!(suppressions.codes, vec!["MTLOG004", "MTLOG007", "MTLOG011", "MTLOG012"]);
+        assert_eq!(
+            suppressions.warnings,
+            vec![
+                "line 7: \"MTLOG099\" is not a diagnostic code",
+                "line 7: \"not-a-code\" is not a diagnostic code",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_crlf_and_empty() {
+        assert_eq!(parse("MTLOG001\r\nMTLOG002\r\n").codes, vec!["MTLOG001", "MTLOG002"]);
+        assert_eq!(parse(""), Suppressions::default());
+        assert_eq!(parse("# nothing yet\n\n").codes, Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_path() {
+        assert_eq!(path(None), Ok(None));
+        assert_eq!(path(Some(&json!(" .mtlog-suppressions "))), Ok(Some(".mtlog-suppressions")));
+        assert!(path(Some(&json!(""))).is_err());
+        assert!(path(Some(&json!(["a.txt"]))).is_err());
+    }
+
+    /// File codes are added after the configured ones, without duplicates.
+    #[test]
+    fn test_apply_merges() {
+        let mut effective = settings::initialization_options(&LspSettings {
+            settings: Some(json!({ "suppressedCodes": ["MTLOG007"] })),
+            ..Default::default()
+        });
+
+        apply(Ok(Some((".mtlog-suppressions".to_string(), parse(SAMPLE)))), &mut effective);
+
+        assert_eq!(
+            effective.options["suppressedCodes"],
+            json!(["MTLOG007", "MTLOG004", "MTLOG011", "MTLOG012"])
+        );
+        assert_eq!(effective.sources["suppressedCodes"], Source::Settings);
+        assert_eq!(effective.warnings.len(), 2);
+        assert!(effective.warnings[0].starts_with("suppressionsFile .mtlog-suppressions, line 7:"));
+    }
+
+    #[test]
+    fn test_apply_unreadable() {
+        let mut effective = settings::initialization_options(&LspSettings::default());
+
+        apply(Err("could not read missing.txt: no such file".to_string()), &mut effective);
+
+        assert_eq!(effective.options["suppressedCodes"], json!([]));
+        assert_eq!(effective.warnings, vec!["ignoring suppressionsFile: could not read missing.txt: no such file"]);
+    }
+}
@@ -0,0 +1,127 @@
+//! The `warmupSample` setting: a Go file mtlog-lsp analyzes as soon as it
+//! starts, so the package loading and fact caches are warm before the first
+//! file is opened.
+//!
+//! Extensions cannot ask the server to analyze anything, so the resolved
+//! path is forwarded as the `warmupSample` option and mtlog-lsp runs the
+//! analysis itself. `"auto"` picks a file from `git ls-files`.
+
+use zed_extension_api::serde_json::Value;
+
+use crate::settings::{EffectiveSettings, Source};
+
+/// Setting, and initialization option, holding the sample.
+/// Forwarded only; requires mtlog-lsp support.
+pub(crate) const WARMUP_SAMPLE_KEY: &str = "warmupSample";
+
+/// Setting value that picks the sample automatically.
+const AUTO: &str = "auto";
+
+/// How the sample is chosen.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Sample<'a> {
+    Auto,
+    /// A Go file relative to the worktree root.
+    Path(&'a str),
+}
+
+/// Returns the configured sample, or `Ok(None)` when the setting is absent.
+pub(crate) fn sample(setting: Option<&Value>) -> Result<Option<Sample<'_>>, String> {
+    let Some(setting) = setting else {
+        return Ok(None);
+    };
+    match setting.as_str().map(str::trim) {
+        Some(value) if value.eq_ignore_ascii_case(AUTO) => Ok(Some(Sample::Auto)),
+        Some(path) if path.ends_with(".go") && !path.starts_with(['/', '\\']) && !path.contains(':') => {
+            Ok(Some(Sample::Path(path)))
+        }
+        _ => Err(format!("expected \"{AUTO}\" or the path of a Go file relative to the project root")),
+    }
+}
+
+/// Picks the sample for `"auto"` from the project's Go files: the shallowest
+/// one that is not a test, vendored, or test data, keeping the listed order
+/// among equally deep files.
+pub(crate) fn select_auto(files: &[String]) -> Option<&str> {
+    files
+        .iter()
+        .map(String::as_str)
+        .filter(|file| file.ends_with(".go") && !file.ends_with("_test.go"))
+        .filter(|file| !file.split('/').any(|dir| matches!(dir, "vendor" | "testdata")))
+        .min_by_key(|file| file.matches('/').count())
+}
+
+/// Adds the resolved sample, an absolute path, to the initialization
+/// options, or records a warning when it could not be resolved.
+pub(crate) fn apply(resolved: Result<Option<String>, String>, effective: &mut EffectiveSettings) {
+    let path = match resolved {
+        Ok(Some(path)) => path,
+        Ok(None) => return,
+        Err(err) => {
+            effective.warnings.push(format!("ignoring {WARMUP_SAMPLE_KEY}: {err}"));
+            return;
+        }
+    };
+    if let Some(options) = effective.options.as_object_mut() {
+        options.insert(WARMUP_SAMPLE_KEY.to_string(), Value::String(path));
+        effective.sources.insert(WARMUP_SAMPLE_KEY.to_string(), Source::Settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings;
+    use zed_extension_api::{serde_json::json, settings::LspSettings};
+
+    #[test]
+    fn test_sample() {
+        assert_eq!(sample(None), Ok(None));
+        assert_eq!(sample(Some(&json!("auto"))), Ok(Some(Sample::Auto)));
+        assert_eq!(sample(Some(&json!(" Auto "))), Ok(Some(Sample::Auto)));
+        assert_eq!(sample(Some(&json!("cmd/api/main.go"))), Ok(Some(Sample::Path("cmd/api/main.go"))));
+
+        for invalid in [json!(""), json!("cmd/api"), json!("/src/app/main.go"), json!("C:\\app\\main.go"), json!(true)] {
+            assert!(sample(Some(&invalid)).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_select_auto() {
+        let files: Vec<String> = [
+            "vendor/github.com/x/y.go",
+            "internal/db/db.go",
+            "main_test.go",
+            "testdata/bad.go",
+            "cmd/api/main.go",
+            "internal/log.go",
+            "doc.txt",
+        ]
+        .iter()
+        .map(|file| file.to_string())
+        .collect();
+
+        assert_eq!(select_auto(&files), Some("internal/log.go"));
+        assert_eq!(select_auto(&[files[1].clone(), "main.go".to_string()]), Some("main.go"));
+        assert_eq!(select_auto(&files[..4]), Some("internal/db/db.go"));
+        assert_eq!(select_auto(&["main_test.go".to_string()]), None);
+        assert_eq!(select_auto(&[]), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut effective = settings::initialization_options(&LspSettings::default());
+
+        apply(Ok(None), &mut effective);
+        assert!(effective.options.get(WARMUP_SAMPLE_KEY).is_none());
+
+        apply(Ok(Some("/work/app/cmd/api/main.go".to_string())), &mut effective);
+        assert_eq!(effective.options[WARMUP_SAMPLE_KEY], json!("/work/app/cmd/api/main.go"));
+        assert_eq!(effective.sources[WARMUP_SAMPLE_KEY], Source::Settings);
+
+        let mut failed = settings::initialization_options(&LspSettings::default());
+        apply(Err("found no Go file to warm up with".to_string()), &mut failed);
+        assert!(failed.options.get(WARMUP_SAMPLE_KEY).is_none());
+        assert_eq!(failed.warnings, vec!["ignoring warmupSample: found no Go file to warm up with"]);
+    }
+}